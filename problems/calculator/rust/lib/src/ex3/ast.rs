@@ -2,6 +2,12 @@
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
 
+use super::bigfloat::BigFloat;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
 #[derive(PartialEq, Clone)]
 pub enum Ex {
     Val(Val),
@@ -9,6 +15,8 @@ pub enum Ex {
     Neg(Box<Ex>),
     Fn(Fnc),
     Mat(Vec<Ex>),
+    /// Undefined, such as `1/0` or a limit that does not exist, written
+    /// `undef`
     Invalid,
 }
 
@@ -24,10 +32,15 @@ pub enum Val {
 
 //---------------------------------------------------------------------------//
 
-#[derive(PartialEq, Clone, Copy)]
+/// Numeric literal
+///
+/// - `Int` is exact over the whole range of a [`Rat`](super::rational::Rat)
+///   part
+#[derive(PartialEq, Clone)]
 pub enum Num {
-    Int(i32),
+    Int(i128),
     Flt(f64),
+    Big(BigFloat),
 }
 
 //---------------------------------------------------------------------------//
@@ -60,6 +73,35 @@ pub enum Rl {
     Gtt,
     Geq,
     Neq,
+    Equiv,
+    Cong,
+    Sim,
+    SimEq,
+    Approx,
+    Asymp,
+    Prop,
+    NEquiv,
+    NCong,
+    NSim,
+    NApprox,
+    NLtt,
+    NLeq,
+    NGtt,
+    NGeq,
+    In,
+    NotIn,
+    Sub,
+    SubEq,
+    Sup,
+    SupEq,
+    NSub,
+    NSubEq,
+    If,
+    To,
+    Implies,
+    MapsTo,
+    Iff,
+    Or,
     Where,
 }
 
@@ -71,6 +113,42 @@ pub enum Fnc {
     Sin(Box<Ex>),
     Cos(Box<Ex>),
     Tan(Box<Ex>),
+    Atan(Box<Ex>),
+    Abs(Box<Ex>),
+    Floor(Box<Ex>),
+    Ceil(Box<Ex>),
+    Round(Box<Ex>),
+    Frac(Box<Ex>),
+    Sign(Box<Ex>),
+    Norm(Box<Ex>),
+    D(Box<Ex>, Box<Ex>),
+    Int(Box<Ex>, Box<Ex>),
+    DefInt(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    NInt(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    Solve(Box<Ex>, Box<Ex>),
+    CSolve(Box<Ex>, Box<Ex>),
+    NSolve(Box<Ex>, Box<Ex>, Box<Ex>),
+    Zeros(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    Factor(Box<Ex>),
+    Expand(Box<Ex>),
+    ExpandWith(Box<Ex>, Box<Ex>),
+    TExpand(Box<Ex>),
+    TCollect(Box<Ex>),
+    Approx(Box<Ex>),
+    ApproxTo(Box<Ex>, Box<Ex>),
+    Quo(Box<Ex>, Box<Ex>, Box<Ex>),
+    Rem(Box<Ex>, Box<Ex>, Box<Ex>),
+    PropFrac(Box<Ex>, Box<Ex>),
+    Gcd(Box<Ex>, Box<Ex>),
+    Lcm(Box<Ex>, Box<Ex>),
+    Lim(Box<Ex>, Box<Ex>, Box<Ex>),
+    LimSide(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    Taylor(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    Sum(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    Prod(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    DeSolve(Box<Ex>, Box<Ex>, Box<Ex>),
+    DeSolveIvp(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
+    NDeSolve(Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>, Box<Ex>),
 }
 
 //---------------------------------------------------------------------------//
@@ -0,0 +1,952 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use super::{ast::*, rational::Rat};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Base of the limbs of a [`Nat`]
+const BASE: u64 = 1_000_000_000;
+
+/// Decimal digits per limb
+const LIMB_DIGITS: usize = 9;
+
+/// Digits computed beyond the requested precision inside transcendental
+/// functions, before the final rounding
+const GUARD: usize = 10;
+
+/// Largest magnitude, as a power of ten, `exp` and the trig functions take
+const MAX_MAGNITUDE: i64 = 15;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Natural number of any size, little-endian limbs in base `10^9` without
+/// leading zero limbs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Nat(Vec<u32>);
+
+impl Nat {
+    pub fn from_u128(mut n: u128) -> Nat {
+        let mut limbs = vec![];
+        while n > 0 {
+            limbs.push((n % BASE as u128) as u32);
+            n /= BASE as u128;
+        }
+        Nat(limbs)
+    }
+
+    pub fn one() -> Nat {
+        Nat(vec![1])
+    }
+
+    /// `10^n`
+    pub fn pow10(n: usize) -> Nat {
+        let mut limbs = vec![0; n / LIMB_DIGITS];
+        limbs.push(10u32.pow((n % LIMB_DIGITS) as u32));
+        Nat(limbs)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.0.first().is_some_and(|l| l % 2 == 1)
+    }
+
+    /// Number of decimal digits, zero for zero
+    pub fn digits(&self) -> usize {
+        match self.0.last() {
+            None => 0,
+            Some(top) => (self.0.len() - 1) * LIMB_DIGITS + top.to_string().len(),
+        }
+    }
+
+    fn trim(mut self) -> Nat {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+        self
+    }
+
+    pub fn add(&self, other: &Nat) -> Nat {
+        let mut out = Vec::with_capacity(self.0.len().max(other.0.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.0.len().max(other.0.len()) {
+            let s = *self.0.get(i).unwrap_or(&0) as u64 + *other.0.get(i).unwrap_or(&0) as u64;
+            out.push(((s + carry) % BASE) as u32);
+            carry = (s + carry) / BASE;
+        }
+        out.push(carry as u32);
+        Nat(out).trim()
+    }
+
+    /// `self - other`, for `self >= other`
+    pub fn sub(&self, other: &Nat) -> Nat {
+        let mut out = Vec::with_capacity(self.0.len());
+        let mut borrow = 0;
+        for i in 0..self.0.len() {
+            let mut d = self.0[i] as i64 - *other.0.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = (d < 0) as i64;
+            if d < 0 {
+                d += BASE as i64;
+            }
+            out.push(d as u32);
+        }
+        Nat(out).trim()
+    }
+
+    pub fn mul(&self, other: &Nat) -> Nat {
+        if self.is_zero() || other.is_zero() {
+            return Nat::default();
+        }
+        let mut out = vec![0u64; self.0.len() + other.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.0.iter().enumerate() {
+                let t = out[i + j] + a as u64 * b as u64 + carry;
+                out[i + j] = t % BASE;
+                carry = t / BASE;
+            }
+            out[i + other.0.len()] += carry;
+        }
+        Nat(out.into_iter().map(|l| l as u32).collect()).trim()
+    }
+
+    pub fn mul_small(&self, m: u32) -> Nat {
+        let mut out = Vec::with_capacity(self.0.len() + 1);
+        let mut carry = 0;
+        for &a in &self.0 {
+            let t = a as u64 * m as u64 + carry;
+            out.push((t % BASE) as u32);
+            carry = t / BASE;
+        }
+        out.push(carry as u32);
+        Nat(out).trim()
+    }
+
+    /// `self * 10^n`
+    pub fn shift(&self, n: usize) -> Nat {
+        if self.is_zero() {
+            return Nat::default();
+        }
+        let mut limbs = vec![0; n / LIMB_DIGITS];
+        limbs.extend(&self.mul_small(10u32.pow((n % LIMB_DIGITS) as u32)).0);
+        Nat(limbs)
+    }
+
+    pub fn divrem_small(&self, d: u32) -> (Nat, u32) {
+        let mut out = vec![0; self.0.len()];
+        let mut rem = 0u64;
+        for i in (0..self.0.len()).rev() {
+            let t = rem * BASE + self.0[i] as u64;
+            out[i] = (t / d as u64) as u32;
+            rem = t % d as u64;
+        }
+        (Nat(out).trim(), rem as u32)
+    }
+
+    /// Quotient and remainder, by Knuth's algorithm D
+    ///
+    /// - Panics on division by zero
+    pub fn divrem(&self, d: &Nat) -> (Nat, Nat) {
+        assert!(!d.is_zero(), "division by zero");
+        if self.cmp(d) == Ordering::Less {
+            return (Nat::default(), self.clone());
+        }
+        if d.0.len() == 1 {
+            let (q, r) = self.divrem_small(d.0[0]);
+            return (q, Nat::from_u128(r as u128));
+        }
+        let norm = (BASE / (*d.0.last().unwrap() as u64 + 1)) as u32;
+        let v = d.mul_small(norm).0;
+        let mut u = self.mul_small(norm).0;
+        u.resize(self.0.len() + 1, 0);
+        let (n, m) = (v.len(), u.len() - v.len() - 1);
+        let mut q = vec![0; m + 1];
+        for j in (0..=m).rev() {
+            let num = u[j + n] as u64 * BASE + u[j + n - 1] as u64;
+            let mut qhat = num / v[n - 1] as u64;
+            let mut rhat = num % v[n - 1] as u64;
+            while qhat >= BASE || qhat * v[n - 2] as u64 > rhat * BASE + u[j + n - 2] as u64 {
+                qhat -= 1;
+                rhat += v[n - 1] as u64;
+                if rhat >= BASE {
+                    break;
+                }
+            }
+            // multiply and subtract
+            let (mut borrow, mut carry) = (0i64, 0u64);
+            for i in 0..n {
+                let p = qhat * v[i] as u64 + carry;
+                carry = p / BASE;
+                let t = u[i + j] as i64 - (p % BASE) as i64 + borrow;
+                borrow = if t < 0 { -1 } else { 0 };
+                u[i + j] = t.rem_euclid(BASE as i64) as u32;
+            }
+            let t = u[j + n] as i64 - carry as i64 + borrow;
+            u[j + n] = t.rem_euclid(BASE as i64) as u32;
+            if t < 0 {
+                // add back
+                qhat -= 1;
+                let mut carry = 0;
+                for i in 0..n {
+                    let s = u[i + j] as u64 + v[i] as u64 + carry;
+                    u[i + j] = (s % BASE) as u32;
+                    carry = s / BASE;
+                }
+                u[j + n] = ((u[j + n] as u64 + carry) % BASE) as u32;
+            }
+            q[j] = qhat as u32;
+        }
+        u.truncate(n);
+        let (r, _) = Nat(u).trim().divrem_small(norm);
+        (Nat(q).trim(), r)
+    }
+
+    /// Largest `r` with `r^2 <= self`, by Newton's method
+    pub fn isqrt(&self) -> Nat {
+        if self.is_zero() {
+            return Nat::default();
+        }
+        let mut x = Nat::pow10(self.digits().div_ceil(2));
+        loop {
+            let y = x.add(&self.divrem(&x).0).divrem_small(2).0;
+            if y.cmp(&x) != Ordering::Less {
+                return x;
+            }
+            x = y;
+        }
+    }
+}
+
+impl Ord for Nat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for Nat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Nat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(top) = self.0.last() else {
+            return f.write_str("0");
+        };
+        write!(f, "{}", top)?;
+        for limb in self.0.iter().rev().skip(1) {
+            write!(f, "{:09}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Decimal floating point number `(-1)^neg * mant * 10^exp` of any precision
+///
+/// - `mant` holds at most `prec` significant digits and no trailing zeros
+/// - Every operation is rounded to nearest, ties to even, at the larger
+///   precision of its operands; `+ - * /` and `sqrt` are correctly rounded,
+///   the transcendental functions carry guard digits
+/// - Equality ignores the precision
+#[derive(Clone, Debug)]
+pub struct BigFloat {
+    neg: bool,
+    mant: Nat,
+    exp: i64,
+    prec: usize,
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.neg == other.neg && self.mant == other.mant && self.exp == other.exp
+    }
+}
+
+impl BigFloat {
+    pub fn zero(prec: usize) -> BigFloat {
+        BigFloat {
+            neg: false,
+            mant: Nat::default(),
+            exp: 0,
+            prec: prec.max(1),
+        }
+    }
+
+    pub fn int(n: i128, prec: usize) -> BigFloat {
+        round(n < 0, Nat::from_u128(n.unsigned_abs()), 0, prec, false)
+    }
+
+    /// Nearest value to the shortest decimal that reads back as `v`
+    pub fn from_f64(v: f64, prec: usize) -> Option<BigFloat> {
+        if !v.is_finite() {
+            return None;
+        }
+        Some(format!("{:e}", v).parse::<BigFloat>().ok()?.with_prec(prec))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_sci(17).parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn prec(&self) -> usize {
+        self.prec
+    }
+
+    /// Rounded to `prec` significant digits
+    pub fn with_prec(&self, prec: usize) -> BigFloat {
+        round(self.neg, self.mant.clone(), self.exp, prec, false)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mant.is_zero()
+    }
+
+    pub fn is_neg(&self) -> bool {
+        self.neg
+    }
+
+    /// `mant e exp` with every digit of the mantissa, such as `-125e-4`,
+    /// which reads back exactly at precision [`prec`](Self::prec)
+    pub fn to_exact(&self) -> String {
+        let sign = if self.neg { "-" } else { "" };
+        format!("{}{}e{}", sign, self.mant, self.exp)
+    }
+
+    pub fn abs(&self) -> BigFloat {
+        BigFloat {
+            neg: false,
+            ..self.clone()
+        }
+    }
+
+    pub fn neg(&self) -> BigFloat {
+        BigFloat {
+            neg: !self.neg && !self.is_zero(),
+            ..self.clone()
+        }
+    }
+
+    /// Decimal exponent of the leading digit, as in `d.ddd * 10^top`
+    fn top(&self) -> i64 {
+        self.mant.digits() as i64 - 1 + self.exp
+    }
+
+    /// The integer value, if it is one of reasonable size
+    pub fn as_int(&self) -> Option<i128> {
+        if self.exp < 0 || self.top() > 30 {
+            return None;
+        }
+        let n: i128 = self
+            .mant
+            .shift(self.exp as usize)
+            .to_string()
+            .parse()
+            .ok()?;
+        Some(if self.neg { -n } else { n })
+    }
+
+    /// Nearest integer, ties away from zero
+    fn nearest(&self) -> BigFloat {
+        if self.exp >= 0 {
+            return self.clone();
+        }
+        let k = (-self.exp) as usize;
+        let (q, r) = self.mant.divrem(&Nat::pow10(k));
+        let q = match r.cmp(&Nat::pow10(k - 1).mul_small(5)) {
+            Ordering::Less => q,
+            _ => q.add(&Nat::one()),
+        };
+        round(
+            self.neg,
+            q,
+            0,
+            self.prec.max(self.top().max(0) as usize + 1),
+            false,
+        )
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    pub fn add(&self, other: &BigFloat) -> BigFloat {
+        let prec = self.prec.max(other.prec);
+        if self.is_zero() || other.is_zero() {
+            let x = if self.is_zero() { other } else { self };
+            return x.with_prec(prec);
+        }
+        // too small to move the rounded sum
+        let (big, small) = match self.top() >= other.top() {
+            true => (self, other),
+            false => (other, self),
+        };
+        if small.top() < big.top() - prec as i64 - 2 {
+            return big.with_prec(prec);
+        }
+        let e = self.exp.min(other.exp);
+        let a = self.mant.shift((self.exp - e) as usize);
+        let b = other.mant.shift((other.exp - e) as usize);
+        match (self.neg == other.neg, a.cmp(&b)) {
+            (true, _) => round(self.neg, a.add(&b), e, prec, false),
+            (false, Ordering::Less) => round(other.neg, b.sub(&a), e, prec, false),
+            (false, _) => round(self.neg, a.sub(&b), e, prec, false),
+        }
+    }
+
+    pub fn sub(&self, other: &BigFloat) -> BigFloat {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &BigFloat) -> BigFloat {
+        round(
+            self.neg != other.neg,
+            self.mant.mul(&other.mant),
+            self.exp + other.exp,
+            self.prec.max(other.prec),
+            false,
+        )
+    }
+
+    /// `None` on division by zero
+    pub fn div(&self, other: &BigFloat) -> Option<BigFloat> {
+        if other.is_zero() {
+            return None;
+        }
+        let prec = self.prec.max(other.prec);
+        let s = (prec + 2 + other.mant.digits()).saturating_sub(self.mant.digits());
+        let (q, r) = self.mant.shift(s).divrem(&other.mant);
+        Some(round(
+            self.neg != other.neg,
+            q,
+            self.exp - other.exp - s as i64,
+            prec,
+            !r.is_zero(),
+        ))
+    }
+
+    /// `None` for negative numbers
+    pub fn sqrt(&self) -> Option<BigFloat> {
+        if self.neg {
+            return None;
+        }
+        let mut shift = (2 * self.prec + 2).saturating_sub(self.mant.digits());
+        if (self.exp - shift as i64) % 2 != 0 {
+            shift += 1;
+        }
+        let m = self.mant.shift(shift);
+        let r = m.isqrt();
+        let exact = r.mul(&r) == m;
+        Some(round(
+            false,
+            r,
+            (self.exp - shift as i64) / 2,
+            self.prec,
+            !exact,
+        ))
+    }
+
+    /// `self^n`, by repeated squaring
+    pub fn powi(&self, n: i64) -> Option<BigFloat> {
+        let wp = self.prec + GUARD + 64;
+        let mut base = self.with_prec(wp);
+        let mut out = BigFloat::int(1, wp);
+        let mut k = n.unsigned_abs();
+        while k > 0 {
+            if k % 2 == 1 {
+                out = out.mul(&base);
+            }
+            base = base.mul(&base);
+            k /= 2;
+        }
+        if n < 0 {
+            out = BigFloat::int(1, wp).div(&out)?;
+        }
+        Some(out.with_prec(self.prec))
+    }
+
+    /// `self^y`, through `exp(y*ln(self))` unless `y` is an integer
+    pub fn pow(&self, y: &BigFloat) -> Option<BigFloat> {
+        let prec = self.prec.max(y.prec);
+        if let Some(n) = y.as_int().and_then(|n| i64::try_from(n).ok()) {
+            return self.with_prec(prec).powi(n);
+        }
+        if self.is_zero() && !y.neg {
+            return Some(BigFloat::zero(prec));
+        }
+        let wp = prec + GUARD;
+        let ln = self.with_prec(wp).ln()?;
+        Some(ln.mul(&y.with_prec(wp)).exp()?.with_prec(prec))
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    /// `pi` to `prec` digits, by Machin's formula
+    pub fn pi(prec: usize) -> BigFloat {
+        let wp = prec + GUARD;
+        let a = atan_inv(5, wp).mul(&BigFloat::int(16, wp));
+        let b = atan_inv(239, wp).mul(&BigFloat::int(4, wp));
+        a.sub(&b).with_prec(prec)
+    }
+
+    /// `e` to `prec` digits
+    pub fn e(prec: usize) -> BigFloat {
+        BigFloat::int(1, prec).exp().unwrap()
+    }
+
+    /// `None` if the result is too large to hold
+    pub fn exp(&self) -> Option<BigFloat> {
+        if self.top() > MAX_MAGNITUDE {
+            return None;
+        }
+        // e^x = (e^(x/2^n))^(2^n), with |x/2^n| below 2^-8
+        let n = match self.is_zero() {
+            true => 0,
+            false => (self.top() as f64 * 10f64.log2()).ceil().max(0.0) as usize + 12,
+        };
+        let wp = self.prec + GUARD + n / 3 + self.top().max(0) as usize;
+        let two = BigFloat::int(2, wp);
+        let mut x = self.with_prec(wp);
+        for _ in 0..n {
+            x = x.div(&two)?;
+        }
+        let mut sum = BigFloat::int(1, wp);
+        let mut term = BigFloat::int(1, wp);
+        for k in 1.. {
+            term = term.mul(&x).div(&BigFloat::int(k, wp))?;
+            if term.is_zero() || term.top() < sum.top() - wp as i64 - 2 {
+                break;
+            }
+            sum = sum.add(&term);
+        }
+        for _ in 0..n {
+            sum = sum.mul(&sum);
+        }
+        Some(sum.with_prec(self.prec))
+    }
+
+    /// Natural logarithm, by Halley's iteration on `exp`; `None` unless
+    /// positive
+    pub fn ln(&self) -> Option<BigFloat> {
+        if self.neg || self.is_zero() {
+            return None;
+        }
+        // digits lost to cancellation near 1
+        let near = self.sub(&BigFloat::int(1, self.prec));
+        let cancel = match near.is_zero() {
+            true => return Some(BigFloat::zero(self.prec)),
+            false => (-near.top()).max(0) as usize,
+        };
+        let wp = self.prec + GUARD + cancel;
+        let x = self.with_prec(wp);
+
+        let lead = self.with_prec(17);
+        let y0 = lead.mant.to_string().parse::<f64>().ok()?.ln()
+            + lead.exp as f64 * std::f64::consts::LN_10;
+        let mut y = BigFloat::from_f64(y0, wp)?;
+        let two = BigFloat::int(2, wp);
+        for _ in 0..64 {
+            let e = y.exp()?;
+            let step = two.mul(&x.sub(&e)).div(&x.add(&e))?;
+            y = y.add(&step);
+            if step.is_zero() || step.top() < y.top() - wp as i64 {
+                break;
+            }
+        }
+        Some(y.with_prec(self.prec))
+    }
+
+    /// `(sin, cos)`, reducing the argument by multiples of `2*pi` first
+    fn sin_cos(&self) -> Option<(BigFloat, BigFloat)> {
+        if self.top() > MAX_MAGNITUDE {
+            return None;
+        }
+        let wp = self.prec + GUARD + self.top().max(0) as usize;
+        let tau = BigFloat::pi(wp).mul(&BigFloat::int(2, wp));
+        let x = self.with_prec(wp);
+        let k = x.div(&tau)?.nearest();
+        let r = x.sub(&k.mul(&tau));
+
+        let (mut sin, mut cos) = (BigFloat::zero(wp), BigFloat::zero(wp));
+        let mut term = BigFloat::int(1, wp);
+        for n in 0.. {
+            if term.is_zero() || (n > 2 && term.top() < -(wp as i64) - 2) {
+                break;
+            }
+            match n % 4 {
+                0 => cos = cos.add(&term),
+                1 => sin = sin.add(&term),
+                2 => cos = cos.sub(&term),
+                _ => sin = sin.sub(&term),
+            }
+            term = term.mul(&r).div(&BigFloat::int(n + 1, wp))?;
+        }
+        Some((sin.with_prec(self.prec), cos.with_prec(self.prec)))
+    }
+
+    pub fn sin(&self) -> Option<BigFloat> {
+        Some(self.sin_cos()?.0)
+    }
+
+    pub fn cos(&self) -> Option<BigFloat> {
+        Some(self.sin_cos()?.1)
+    }
+
+    pub fn tan(&self) -> Option<BigFloat> {
+        let (sin, cos) = self.with_prec(self.prec + GUARD).sin_cos()?;
+        Some(sin.div(&cos)?.with_prec(self.prec))
+    }
+
+    /// Arc tangent, halving the argument until the series converges fast
+    pub fn atan(&self) -> Option<BigFloat> {
+        let wp = self.prec + GUARD;
+        let one = BigFloat::int(1, wp);
+        if self.neg {
+            return Some(self.neg().atan()?.neg());
+        }
+        if self.top() > 0 || (self.top() == 0 && self.mant != Nat::one()) {
+            // atan(x) = pi/2 - atan(1/x)
+            let inv = one.div(&self.with_prec(wp))?;
+            let half_pi = BigFloat::pi(wp).div(&BigFloat::int(2, wp))?;
+            return Some(half_pi.sub(&inv.atan()?).with_prec(self.prec));
+        }
+        // atan(x) = 2 atan(x / (1 + sqrt(1 + x^2)))
+        let mut x = self.with_prec(wp);
+        let mut doublings = 0;
+        while !x.is_zero() && x.top() >= -1 {
+            x = x.div(&one.add(&one.add(&x.mul(&x)).sqrt()?))?;
+            doublings += 1;
+        }
+        let x2 = x.mul(&x);
+        let (mut sum, mut p) = (BigFloat::zero(wp), x.clone());
+        for k in 0.. {
+            let term = p.div(&BigFloat::int(2 * k + 1, wp))?;
+            if term.is_zero() || term.top() < sum.top() - wp as i64 - 2 {
+                break;
+            }
+            sum = match k % 2 {
+                0 => sum.add(&term),
+                _ => sum.sub(&term),
+            };
+            p = p.mul(&x2);
+        }
+        let scale = BigFloat::int(1 << doublings, wp);
+        Some(sum.mul(&scale).with_prec(self.prec))
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    /// `d.ddd...e±x` with `digits` significant digits
+    fn to_sci(&self, digits: usize) -> String {
+        let r = self.with_prec(digits);
+        if r.is_zero() {
+            return "0".to_owned();
+        }
+        let s = r.mant.to_string();
+        let sign = if r.neg { "-" } else { "" };
+        format!("{}{}.{}e{}", sign, &s[..1], &s[1..], r.top())
+            .replace(".e", "e")
+            .replace("e0", "")
+    }
+}
+
+/// `atan(1/n)` by its series, for an integer `n > 1`
+fn atan_inv(n: i128, wp: usize) -> BigFloat {
+    let n2 = BigFloat::int(n * n, wp);
+    let mut p = BigFloat::int(1, wp).div(&BigFloat::int(n, wp)).unwrap();
+    let mut sum = BigFloat::zero(wp);
+    for k in 0.. {
+        let term = p.div(&BigFloat::int(2 * k + 1, wp)).unwrap();
+        if term.is_zero() || term.top() < -(wp as i64) - 2 {
+            break;
+        }
+        sum = match k % 2 {
+            0 => sum.add(&term),
+            _ => sum.sub(&term),
+        };
+        p = p.div(&n2).unwrap();
+    }
+    sum
+}
+
+/// Normalised number from an exact `mant * 10^exp`, rounded to `prec` digits
+///
+/// - `sticky` means the true value is a little larger than `mant`, so that
+///   an exact tie rounds up
+fn round(neg: bool, mut mant: Nat, mut exp: i64, prec: usize, sticky: bool) -> BigFloat {
+    let prec = prec.max(1);
+    let d = mant.digits();
+    if d > prec {
+        let k = d - prec;
+        let (q, r) = mant.divrem(&Nat::pow10(k));
+        let up = match r.cmp(&Nat::pow10(k - 1).mul_small(5)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => sticky || q.is_odd(),
+        };
+        mant = if up { q.add(&Nat::one()) } else { q };
+        exp += k as i64;
+    }
+    if mant.is_zero() {
+        return BigFloat::zero(prec);
+    }
+    loop {
+        let (q, r) = mant.divrem_small(10);
+        if r != 0 {
+            break;
+        }
+        mant = q;
+        exp += 1;
+    }
+    BigFloat {
+        neg,
+        mant,
+        exp,
+        prec,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl fmt::Display for BigFloat {
+    /// Positional notation for moderate exponents, scientific otherwise
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+        let top = self.top();
+        if top < -7 || top >= self.prec.max(21) as i64 {
+            return f.write_str(&self.to_sci(self.prec));
+        }
+        let s = self.mant.to_string();
+        let sign = if self.neg { "-" } else { "" };
+        let body = match self.exp {
+            e if e >= 0 => format!("{}{}", s, "0".repeat(e as usize)),
+            _ if top >= 0 => {
+                let (int, frac) = s.split_at(top as usize + 1);
+                format!("{}.{}", int, frac)
+            }
+            _ => format!("0.{}{}", "0".repeat((-top - 1) as usize), s),
+        };
+        write!(f, "{}{}", sign, body)
+    }
+}
+
+impl FromStr for BigFloat {
+    type Err = ();
+
+    /// Decimal literal such as `-12.5e-3`, with as much precision as it has
+    /// significant digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (neg, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (num, e) = match s.split_once(['e', 'E']) {
+            Some((num, e)) => (num, e.parse::<i64>().map_err(|_| ())?),
+            None => (s, 0),
+        };
+        let (int, frac) = num.split_once('.').unwrap_or((num, ""));
+        let digits = format!("{}{}", int, frac);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(());
+        }
+        let digits = digits.trim_start_matches('0');
+        let mut mant = Nat::default();
+        for chunk in digits.as_bytes().chunks(LIMB_DIGITS) {
+            let chunk = std::str::from_utf8(chunk).unwrap();
+            mant = mant
+                .shift(chunk.len())
+                .add(&Nat::from_u128(chunk.parse().unwrap()));
+        }
+        Ok(round(
+            neg,
+            mant,
+            e - frac.len() as i64,
+            digits.len().max(1),
+            false,
+        ))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Evaluate a constant expression to `prec` significant digits
+    ///
+    /// - The arbitrary precision counterpart of [`Ex::flt`], without free
+    ///   variables
+    pub fn big(&self, prec: usize) -> Option<BigFloat> {
+        let wp = prec + GUARD;
+        let v = match self {
+            Ex::Val(Val::Num(Num::Int(n))) => BigFloat::int(*n, wp),
+            Ex::Val(Val::Num(Num::Flt(v))) => BigFloat::from_f64(*v, wp)?,
+            Ex::Val(Val::Num(Num::Big(v))) => v.with_prec(wp),
+            Ex::Val(Val::Tok(Tok::Pi)) => BigFloat::pi(wp),
+            Ex::Val(Val::Tok(Tok::E)) => BigFloat::e(wp),
+            Ex::Val(_) | Ex::Mat(_) | Ex::Invalid => return None,
+            Ex::Neg(a) => a.big(wp)?.neg(),
+            Ex::Infix(a, Bin::Op(op), b) => {
+                let a = a.big(wp)?;
+                match (op, b.as_ref()) {
+                    // square roots exactly rounded
+                    (Op::Pow, b) if b.as_rat() == Some(Rat::new(1, 2)) => a.sqrt()?,
+                    (Op::Add, b) => a.add(&b.big(wp)?),
+                    (Op::Sub, b) => a.sub(&b.big(wp)?),
+                    (Op::Mul, b) => a.mul(&b.big(wp)?),
+                    (Op::Div, b) => a.div(&b.big(wp)?)?,
+                    (Op::Pow, b) => a.pow(&b.big(wp)?)?,
+                    (Op::Mod, _) => return None,
+                }
+            }
+            Ex::Infix(_, Bin::Rl(_), _) => return None,
+            Ex::Fn(f) => match f {
+                Fnc::Ln(a) => a.big(wp)?.ln()?,
+                Fnc::Sin(a) => a.big(wp)?.sin()?,
+                Fnc::Cos(a) => a.big(wp)?.cos()?,
+                Fnc::Tan(a) => a.big(wp)?.tan()?,
+                Fnc::Atan(a) => a.big(wp)?.atan()?,
+                _ => return None,
+            },
+        };
+        Some(v.with_prec(prec))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigFloat {
+        s.parse().unwrap()
+    }
+
+    const PI_60: &str = "3.14159265358979323846264338327950288419716939937510582097494";
+    const E_60: &str = "2.71828182845904523536028747135266249775724709369995957496697";
+
+    #[test]
+    fn ex3_bigfloat_arithmetic() {
+        let n = Nat::from_u128(123_456_789_012_345_678_901_234_567_890);
+        let d = Nat::from_u128(987_654_321_987);
+        let (q, r) = n.divrem(&d);
+        assert_eq!(q.mul(&d).add(&r), n);
+        assert!(r < d);
+        assert_eq!(Nat::from_u128(1 << 100).isqrt(), Nat::from_u128(1 << 50));
+
+        let third = BigFloat::int(1, 50).div(&BigFloat::int(3, 50)).unwrap();
+        assert_eq!(third.to_string(), format!("0.{}", "3".repeat(50)));
+        let two_thirds = BigFloat::int(2, 50).div(&BigFloat::int(3, 50)).unwrap();
+        assert_eq!(two_thirds.to_string(), format!("0.{}7", "6".repeat(49)));
+        assert_eq!(big("0.1").add(&big("0.2")), big("0.3"));
+        assert_eq!(
+            big("1e30").with_prec(40).add(&big("1")),
+            big("1000000000000000000000000000001")
+        );
+        assert_eq!(big("1e30").add(&big("1")), big("1e30"));
+        assert_eq!(big("1.5").sub(&big("2.25")).to_string(), "-0.75");
+        assert_eq!(big("12.5e-3").mul(&big("-4")).to_string(), "-0.05");
+        // ties to even
+        assert_eq!(big("2.5").with_prec(1), big("2"));
+        assert_eq!(big("3.5").with_prec(1), big("4"));
+        assert_eq!(big("1e100").to_string(), "1e100");
+        assert_eq!(big("-0.000000001234").to_string(), "-1.234e-9");
+
+        let root2 = BigFloat::int(2, 60).sqrt().unwrap();
+        assert_eq!(
+            root2.to_string(),
+            "1.41421356237309504880168872420969807856967187537694807317668"
+        );
+        assert_eq!(big("1.44").sqrt().unwrap(), big("1.2"));
+        assert_eq!(
+            BigFloat::int(3, 40).powi(-2).unwrap(),
+            BigFloat::int(1, 40).div(&BigFloat::int(9, 40)).unwrap()
+        );
+    }
+
+    #[test]
+    fn ex3_bigfloat_functions() {
+        assert_eq!(BigFloat::pi(60).to_string(), PI_60);
+        assert_eq!(BigFloat::e(60).to_string(), E_60);
+        assert_eq!(
+            BigFloat::int(2, 50).ln().unwrap().to_string(),
+            "0.69314718055994530941723212145817656807550013436026"
+        );
+        assert_eq!(
+            BigFloat::int(10, 50)
+                .ln()
+                .unwrap()
+                .exp()
+                .unwrap()
+                .with_prec(45),
+            big("10")
+        );
+        assert_eq!(
+            BigFloat::int(1, 50).sin().unwrap().to_string(),
+            "0.84147098480789650665250232163029899962256306079837"
+        );
+        assert_eq!(
+            BigFloat::int(1, 50).cos().unwrap().to_string(),
+            "0.54030230586813971740093660744297660373231042061792"
+        );
+        assert_eq!(
+            BigFloat::int(1, 50)
+                .atan()
+                .unwrap()
+                .mul(&BigFloat::int(4, 50)),
+            BigFloat::pi(50)
+        );
+        assert_eq!(
+            BigFloat::int(100, 30).sin().unwrap().to_string(),
+            "-0.50636564110975879365655761046"
+        );
+        assert_eq!(
+            big("0.5").with_prec(20).tan().unwrap().to_string(),
+            "0.54630248984379051326"
+        );
+        assert_eq!(
+            BigFloat::int(2, 30).pow(&big("0.5")).unwrap().with_prec(25),
+            BigFloat::int(2, 25).sqrt().unwrap()
+        );
+        assert!(BigFloat::int(-1, 30).ln().is_none());
+        assert!((BigFloat::pi(30).to_f64() - std::f64::consts::PI).abs() < 1e-15);
+    }
+
+    #[test]
+    fn ex3_bigfloat_approx() {
+        let approx = |s: &str| s.parse::<Ex>().unwrap().simplified().to_text();
+        assert_eq!(approx("approx(pi, 60)"), PI_60);
+        assert_eq!(approx("approx(e, 60)"), E_60);
+        assert_eq!(
+            approx("approx(2^(1/2) + 1/3, 40)"),
+            "1.747546895706428382135022057543031411903"
+        );
+        assert_eq!(approx("approx(x + pi, 20)"), "x + 3.1415926535897932385");
+        assert_eq!(
+            approx("approx(-ln(3), 30)"),
+            "-1.09861228866810969139524523692"
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
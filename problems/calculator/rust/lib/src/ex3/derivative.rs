@@ -0,0 +1,171 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, helper::*, trace};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Symbolic derivative with respect to the variable `x`
+    ///
+    /// - Anything that cannot be differentiated is left as `d(.., x)`
+    pub fn derivative(&self, x: &str) -> Ex {
+        let raw = trace::at(0, || self.diff(x));
+        let out = raw.simplified();
+        trace::record("simplify", &raw, &out);
+        out
+    }
+
+    fn diff(&self, x: &str) -> Ex {
+        if trace::active() {
+            let (rule, step) = self.rule(x, &mut |a| d(a.clone(), x.into()));
+            trace::record(rule, &d(self.clone(), x.into()), &step);
+        }
+        self.rule(x, &mut |a| a.diff(x)).1
+    }
+
+    /// Name and result of the rule for the outermost node, with `sub` giving
+    /// the derivatives of the subterms
+    fn rule(&self, x: &str, sub: &mut dyn FnMut(&Ex) -> Ex) -> (&'static str, Ex) {
+        let mut dx = |i: usize, a: &Ex| trace::at(i, || sub(a));
+        if !self.has(x) {
+            return (
+                "constant rule",
+                match self {
+                    Ex::Mat(xs) => Ex::Mat(xs.iter().map(|_| Ex::from(0)).collect()),
+                    Ex::Infix(_, Bin::Rl(_), _) => self.map(|_| Ex::from(0)),
+                    _ => Ex::from(0),
+                },
+            );
+        }
+        match self {
+            Ex::Val(_) => ("variable", Ex::from(1)),
+            Ex::Neg(a) => ("negation", -dx(0, a)),
+            Ex::Infix(a, j, b) => match j {
+                Bin::Op(Op::Add) => ("sum rule", dx(0, a) + dx(1, b)),
+                Bin::Op(Op::Sub) => ("difference rule", dx(0, a) - dx(1, b)),
+                Bin::Op(Op::Mul) => (
+                    "product rule",
+                    dx(0, a) * *b.clone() + *a.clone() * dx(1, b),
+                ),
+                Bin::Op(Op::Div) => (
+                    "quotient rule",
+                    (dx(0, a) * *b.clone() - *a.clone() * dx(1, b)) / b.clone().pow(2.into()),
+                ),
+                Bin::Op(Op::Pow) if !b.has(x) => (
+                    "power rule",
+                    *b.clone() * a.clone().pow(*b.clone() - 1.into()) * dx(0, a),
+                ),
+                Bin::Op(Op::Pow) if **a == Ex::from(Tok::E) => {
+                    ("exponential rule", self.clone() * dx(1, b))
+                }
+                Bin::Op(Op::Pow) if !a.has(x) => {
+                    ("exponential rule", self.clone() * ln(*a.clone()) * dx(1, b))
+                }
+                Bin::Op(Op::Pow) => (
+                    "general power rule",
+                    self.clone() * (dx(1, b) * ln(*a.clone()) + *b.clone() * dx(0, a) / *a.clone()),
+                ),
+                Bin::Op(Op::Mod) => ("unevaluated", d(self.clone(), x.into())),
+                Bin::Rl(Rl::Where) => ("unevaluated", d(self.clone(), x.into())),
+                Bin::Rl(_) => ("both sides", dx(0, a).c(dx(1, b), j.clone())),
+            },
+            Ex::Fn(f) => match f {
+                Fnc::Ln(a) => ("ln rule", dx(0, a) / *a.clone()),
+                Fnc::Sin(a) => ("sin rule", cos(*a.clone()) * dx(0, a)),
+                Fnc::Cos(a) => ("cos rule", -sin(*a.clone()) * dx(0, a)),
+                Fnc::Tan(a) => ("tan rule", dx(0, a) / cos(*a.clone()).pow(2.into())),
+                Fnc::Atan(a) => (
+                    "atan rule",
+                    dx(0, a) / (Ex::from(1) + a.clone().pow(2.into())),
+                ),
+                Fnc::Abs(a) => ("abs rule", sign(*a.clone()) * dx(0, a)),
+                Fnc::Floor(_) | Fnc::Ceil(_) | Fnc::Round(_) | Fnc::Sign(_) => {
+                    ("step rule", Ex::from(0))
+                }
+                Fnc::Frac(a) => ("frac rule", dx(0, a)),
+                Fnc::Int(a, y) if y.var() == Some(x) => ("fundamental theorem", *a.clone()),
+                Fnc::DefInt(a, y, lo, hi) | Fnc::NInt(a, y, lo, hi)
+                    if !a.has(x) || y.var() == Some(x) =>
+                {
+                    let y = y.var().unwrap_or(x);
+                    (
+                        "fundamental theorem",
+                        a.subs(y, hi) * dx(3, hi) - a.subs(y, lo) * dx(2, lo),
+                    )
+                }
+                Fnc::Norm(..)
+                | Fnc::D(..)
+                | Fnc::Int(..)
+                | Fnc::DefInt(..)
+                | Fnc::NInt(..)
+                | Fnc::Solve(..)
+                | Fnc::CSolve(..)
+                | Fnc::NSolve(..)
+                | Fnc::Zeros(..)
+                | Fnc::Factor(..)
+                | Fnc::Expand(..)
+                | Fnc::ExpandWith(..)
+                | Fnc::TExpand(..)
+                | Fnc::TCollect(..)
+                | Fnc::Approx(..)
+                | Fnc::ApproxTo(..)
+                | Fnc::Quo(..)
+                | Fnc::Rem(..)
+                | Fnc::PropFrac(..)
+                | Fnc::Gcd(..)
+                | Fnc::Lcm(..)
+                | Fnc::Lim(..)
+                | Fnc::LimSide(..)
+                | Fnc::Taylor(..)
+                | Fnc::Sum(..)
+                | Fnc::Prod(..)
+                | Fnc::DeSolve(..)
+                | Fnc::DeSolveIvp(..)
+                | Fnc::NDeSolve(..) => match trace::quiet(|| self.simplified()) {
+                    e @ Ex::Fn(_) if e == *self => ("unevaluated", d(e, x.into())),
+                    e => ("evaluate", sub(&e)),
+                },
+            },
+            Ex::Mat(xs) => (
+                "elementwise",
+                Ex::Mat(xs.iter().enumerate().map(|(i, a)| dx(i, a)).collect()),
+            ),
+            Ex::Invalid => ("undefined", Ex::Invalid),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::Ex;
+    use crate::ex3::testing::simp;
+
+    fn deriv(s: &str) -> Ex {
+        s.parse::<Ex>().unwrap().derivative("x")
+    }
+
+    #[test]
+    fn ex3_derivative() {
+        assert_eq!(deriv("5"), simp("0"));
+        assert_eq!(deriv("x"), simp("1"));
+        assert_eq!(deriv("y"), simp("0"));
+        assert_eq!(deriv("x^3"), simp("3*x^2"));
+        assert_eq!(deriv("sin(x)"), simp("cos(x)"));
+        assert_eq!(deriv("ln(x)"), simp("1/x"));
+        assert_eq!(deriv("e^(2*x)"), simp("2*e^(2*x)"));
+        assert_eq!(deriv("d(x^2, x)"), simp("2"));
+        assert_eq!(deriv("int(x^2, x)"), simp("x^2"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -26,7 +26,7 @@ impl std::fmt::Debug for Ex {
                 _ => f.write_fmt(format_args!("-({:?})", a)),
             },
             Ex::Mat(a) => f.write_fmt(format_args!("{:?}", a)),
-            Ex::Invalid => f.write_str("invalid"),
+            Ex::Invalid => f.write_str("undef"),
         }
     }
 }
@@ -51,6 +51,7 @@ impl std::fmt::Debug for Num {
         match self {
             Num::Int(a) => f.write_fmt(format_args!("{}", a)),
             Num::Flt(a) => f.write_fmt(format_args!("{:.00001}", a)),
+            Num::Big(a) => f.write_fmt(format_args!("{}", a)),
         }
     }
 }
@@ -91,7 +92,36 @@ impl std::fmt::Debug for Rl {
             Rl::Leq => f.write_str("<="),
             Rl::Gtt => f.write_str(">"),
             Rl::Geq => f.write_str(">="),
-            Rl::Neq => f.write_str("!"),
+            Rl::Neq => f.write_str("!="),
+            Rl::Equiv => f.write_str("≡"),
+            Rl::Cong => f.write_str("≅"),
+            Rl::Sim => f.write_str("∼"),
+            Rl::SimEq => f.write_str("≃"),
+            Rl::Approx => f.write_str("≈"),
+            Rl::Asymp => f.write_str("≍"),
+            Rl::Prop => f.write_str("∝"),
+            Rl::NEquiv => f.write_str("≢"),
+            Rl::NCong => f.write_str("≇"),
+            Rl::NSim => f.write_str("≁"),
+            Rl::NApprox => f.write_str("≉"),
+            Rl::NLtt => f.write_str("≮"),
+            Rl::NLeq => f.write_str("≰"),
+            Rl::NGtt => f.write_str("≯"),
+            Rl::NGeq => f.write_str("≱"),
+            Rl::In => f.write_str("∈"),
+            Rl::NotIn => f.write_str("∉"),
+            Rl::Sub => f.write_str("⊂"),
+            Rl::SubEq => f.write_str("⊆"),
+            Rl::Sup => f.write_str("⊃"),
+            Rl::SupEq => f.write_str("⊇"),
+            Rl::NSub => f.write_str("⊄"),
+            Rl::NSubEq => f.write_str("⊈"),
+            Rl::If => f.write_str("<=="),
+            Rl::To => f.write_str("->"),
+            Rl::Implies => f.write_str("=>"),
+            Rl::MapsTo => f.write_str("|->"),
+            Rl::Iff => f.write_str("<=>"),
+            Rl::Or => f.write_str("or"),
             Rl::Where => f.write_char('|'),
         }
     }
@@ -106,6 +136,64 @@ impl std::fmt::Debug for Fnc {
             Fnc::Sin(a) => f.write_fmt(format_args!("sin({:?})", a)),
             Fnc::Cos(a) => f.write_fmt(format_args!("cos({:?})", a)),
             Fnc::Tan(a) => f.write_fmt(format_args!("tan({:?})", a)),
+            Fnc::Atan(a) => f.write_fmt(format_args!("atan({:?})", a)),
+            Fnc::Abs(a) => f.write_fmt(format_args!("abs({:?})", a)),
+            Fnc::Floor(a) => f.write_fmt(format_args!("floor({:?})", a)),
+            Fnc::Ceil(a) => f.write_fmt(format_args!("ceil({:?})", a)),
+            Fnc::Round(a) => f.write_fmt(format_args!("round({:?})", a)),
+            Fnc::Frac(a) => f.write_fmt(format_args!("frac({:?})", a)),
+            Fnc::Sign(a) => f.write_fmt(format_args!("sign({:?})", a)),
+            Fnc::Norm(a) => f.write_fmt(format_args!("norm({:?})", a)),
+            Fnc::D(a, x) => f.write_fmt(format_args!("d({:?}, {:?})", a, x)),
+            Fnc::Int(a, x) => f.write_fmt(format_args!("int({:?}, {:?})", a, x)),
+            Fnc::DefInt(a, x, lo, hi) => {
+                f.write_fmt(format_args!("int({:?}, {:?}, {:?}, {:?})", a, x, lo, hi))
+            }
+            Fnc::NInt(a, x, lo, hi) => {
+                f.write_fmt(format_args!("nInt({:?}, {:?}, {:?}, {:?})", a, x, lo, hi))
+            }
+            Fnc::Solve(a, x) => f.write_fmt(format_args!("solve({:?}, {:?})", a, x)),
+            Fnc::CSolve(a, x) => f.write_fmt(format_args!("cSolve({:?}, {:?})", a, x)),
+            Fnc::NSolve(a, x, g) => f.write_fmt(format_args!("nSolve({:?}, {:?}, {:?})", a, x, g)),
+            Fnc::Zeros(a, x, lo, hi) => {
+                f.write_fmt(format_args!("zeros({:?}, {:?}, {:?}, {:?})", a, x, lo, hi))
+            }
+            Fnc::Factor(a) => f.write_fmt(format_args!("factor({:?})", a)),
+            Fnc::Expand(a) => f.write_fmt(format_args!("expand({:?})", a)),
+            Fnc::ExpandWith(a, o) => f.write_fmt(format_args!("expand({:?}, {:?})", a, o)),
+            Fnc::TExpand(a) => f.write_fmt(format_args!("tExpand({:?})", a)),
+            Fnc::TCollect(a) => f.write_fmt(format_args!("tCollect({:?})", a)),
+            Fnc::Approx(a) => f.write_fmt(format_args!("approx({:?})", a)),
+            Fnc::ApproxTo(a, d) => f.write_fmt(format_args!("approx({:?}, {:?})", a, d)),
+            Fnc::Quo(a, b, x) => f.write_fmt(format_args!("quo({:?}, {:?}, {:?})", a, b, x)),
+            Fnc::Rem(a, b, x) => f.write_fmt(format_args!("rem({:?}, {:?}, {:?})", a, b, x)),
+            Fnc::PropFrac(a, x) => f.write_fmt(format_args!("propFrac({:?}, {:?})", a, x)),
+            Fnc::Gcd(a, b) => f.write_fmt(format_args!("gcd({:?}, {:?})", a, b)),
+            Fnc::Lcm(a, b) => f.write_fmt(format_args!("lcm({:?}, {:?})", a, b)),
+            Fnc::Lim(a, x, p) => f.write_fmt(format_args!("lim({:?}, {:?}, {:?})", a, x, p)),
+            Fnc::LimSide(a, x, p, s) => {
+                f.write_fmt(format_args!("lim({:?}, {:?}, {:?}, {:?})", a, x, p, s))
+            }
+            Fnc::Taylor(a, x, n, p) => {
+                f.write_fmt(format_args!("taylor({:?}, {:?}, {:?}, {:?})", a, x, n, p))
+            }
+            Fnc::Sum(a, k, lo, hi) => {
+                f.write_fmt(format_args!("sum({:?}, {:?}, {:?}, {:?})", a, k, lo, hi))
+            }
+            Fnc::Prod(a, k, lo, hi) => {
+                f.write_fmt(format_args!("prod({:?}, {:?}, {:?}, {:?})", a, k, lo, hi))
+            }
+            Fnc::DeSolve(a, x, y) => {
+                f.write_fmt(format_args!("deSolve({:?}, {:?}, {:?})", a, x, y))
+            }
+            Fnc::DeSolveIvp(a, x, y, init) => f.write_fmt(format_args!(
+                "deSolve({:?}, {:?}, {:?}, {:?})",
+                a, x, y, init
+            )),
+            Fnc::NDeSolve(a, x, y, init, end) => f.write_fmt(format_args!(
+                "nDeSolve({:?}, {:?}, {:?}, {:?}, {:?})",
+                a, x, y, init, end
+            )),
         }
     }
 }
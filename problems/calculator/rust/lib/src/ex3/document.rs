@@ -0,0 +1,237 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::str::FromStr;
+
+use pest::Parser;
+
+use super::{
+    ast::*,
+    parser::{in_range, ExParser, Rule},
+    symbols,
+};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Statements of a script, one per line or separated by `;`
+///
+/// - `//` and `#` start comments that run to the end of the line
+/// - Blank lines are skipped
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    pub statements: Vec<Statement>,
+}
+
+/// One statement of a [`Document`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    /// An expression to evaluate
+    Expr(Ex),
+    /// `x := e`, binding `x` to the value of `e` for later statements
+    Assign(String, Ex),
+}
+
+/// Variables bound by earlier statements
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    vars: Vec<(String, Ex)>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Document {
+    /// Value of every statement, evaluated in order in a fresh [`Session`]
+    pub fn evaluate(&self) -> Vec<Ex> {
+        self.run(&mut Session::default())
+    }
+
+    /// Value of every statement, evaluated in order in `session`
+    pub fn run(&self, session: &mut Session) -> Vec<Ex> {
+        self.statements.iter().map(|s| session.run(s)).collect()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Session {
+    /// Value bound to `x`
+    pub fn get(&self, x: &str) -> Option<&Ex> {
+        self.vars.iter().find(|(y, _)| y == x).map(|(_, v)| v)
+    }
+
+    /// Bind `x` to `v`, replacing an earlier binding
+    pub fn set(&mut self, x: &str, v: Ex) {
+        match self.vars.iter_mut().find(|(y, _)| y == x) {
+            Some((_, old)) => *old = v,
+            None => self.vars.push((x.to_owned(), v)),
+        }
+    }
+
+    /// `e` with every free occurrence of a bound variable replaced by its
+    /// value, evaluated in the current [`Mode`](super::mode::Mode)
+    ///
+    /// - Variables bound inside `e`, such as `x` in `d(x^2, x)`, are kept,
+    ///   see [`Ex::subs_free`]
+    pub fn eval(&self, e: &Ex) -> Ex {
+        self.vars
+            .iter()
+            .fold(e.clone(), |e, (x, v)| e.subs_free(x, v))
+            .evaluated()
+    }
+
+    /// Value of a statement, binding the variable of an assignment
+    pub fn run(&mut self, s: &Statement) -> Ex {
+        match s {
+            Statement::Expr(e) => self.eval(e),
+            Statement::Assign(x, e) => {
+                let v = self.eval(e);
+                self.set(x, v.clone());
+                v
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl FromStr for Document {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut pairs = ExParser::parse(Rule::document, value).map_err(|e| e.to_string())?;
+        in_range(pairs.clone())?;
+        let statements = pairs
+            .next()
+            .unwrap()
+            .into_inner()
+            .filter_map(|pair| match pair.as_rule() {
+                Rule::expr => Some(Statement::Expr(Ex::from(pair.into_inner()))),
+                Rule::assign => {
+                    let mut inner = pair.into_inner();
+                    let x = symbols::ascii(inner.next().unwrap().as_str());
+                    let e = Ex::from(inner.next().unwrap().into_inner());
+                    Some(Statement::Assign(x, e))
+                }
+                _ => None,
+            })
+            .collect();
+        Ok(Document { statements })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for s in &self.statements {
+            writeln!(f, "{}", s)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::Expr(e) => f.write_str(&e.to_text()),
+            Statement::Assign(x, e) => write!(f, "{} := {}", x, e.to_text()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::simp;
+
+    fn doc(s: &str) -> Document {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn ex3_document_parse() {
+        let d = doc("\n# setup\na := 2; b := a + 1 // three\n\n\na*b\n// done\n");
+        assert_eq!(
+            d.statements,
+            vec![
+                Statement::Assign("a".to_owned(), Ex::from(2)),
+                Statement::Assign("b".to_owned(), Ex::from("a") + Ex::from(1)),
+                Statement::Expr(Ex::from("a") * Ex::from("b")),
+            ]
+        );
+        assert_eq!(d.to_string(), "a := 2\nb := a + 1\na*b\n");
+        assert_eq!(doc("").statements, vec![]);
+        assert_eq!(doc(" ;; # nothing").statements, vec![]);
+        assert_eq!(doc("θ := 1").statements[0], doc("theta := 1").statements[0]);
+
+        // trailing input is an error instead of being dropped
+        assert!("x + 1 )".parse::<Document>().is_err());
+        assert!("x + 1 )".parse::<Ex>().is_err());
+        assert!("x +".parse::<Document>().is_err());
+        assert!("x\ny".parse::<Ex>().is_err());
+        // beyond an i128
+        let big = "170141183460469231731687303715884105728";
+        assert!(format!("x + {big}").parse::<Ex>().is_err());
+        assert!(format!("y := 1\n{big}*y").parse::<Document>().is_err());
+        assert!("170141183460469231731687303715884105727"
+            .parse::<Ex>()
+            .is_ok());
+        assert_eq!("\nx + 1\n".parse::<Ex>(), Ok(Ex::from("x") + Ex::from(1)));
+    }
+
+    #[test]
+    fn ex3_document_session() {
+        let d = doc("r := 3\narea := pi*r^2\nr := r + 1\narea + r\nsolve(y^2 = r, y)");
+        assert_eq!(
+            d.evaluate(),
+            vec![
+                simp("3"),
+                simp("9*pi"),
+                simp("4"),
+                simp("9*pi + 4"),
+                simp("y = -2 or y = 2"),
+            ]
+        );
+
+        let mut session = Session::default();
+        doc("k := 1/2").run(&mut session);
+        assert_eq!(session.get("k"), Some(&simp("1/2")));
+        assert_eq!(doc("4*k").run(&mut session), vec![simp("2")]);
+        assert_eq!(session.get("x"), None);
+    }
+
+    #[test]
+    fn ex3_document_scope() {
+        let d = doc("x := 3; k := 2; n := 4\n\
+             d(x^2, x); x^2\n\
+             sum(k^2, k, 1, 3); sum(k, k, 1, n)\n\
+             int(x, x, 0, k); lim(sin(x)/x, x, 0)\n\
+             solve(x^2 = n, x); d(x*k, x)");
+        assert_eq!(
+            d.evaluate()[3..],
+            [
+                simp("2*x"),
+                simp("9"),
+                simp("14"),
+                simp("10"),
+                simp("2"),
+                simp("1"),
+                simp("x = -2 or x = 2"),
+                simp("2"),
+            ]
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
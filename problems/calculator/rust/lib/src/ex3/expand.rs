@@ -0,0 +1,294 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Highest power of a sum multiplied out
+const MAX_POWER: i128 = 32;
+
+/// Most terms of an expansion before the input is returned as it is
+const MAX_TERMS: usize = 10_000;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Functions whose arguments [`Ex::expand`] also takes apart
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Expand {
+    /// `ln(a*b) = ln(a) + ln(b)` and `ln(a^k) = k*ln(a)`
+    pub ln: bool,
+    /// Angle addition and multiple angles of `sin`, `cos` and `tan`
+    pub trig: bool,
+}
+
+impl Expand {
+    /// Options named by `ln`, `trig` or `all`, or a list of those
+    pub fn of(e: &Ex) -> Option<Expand> {
+        match e {
+            Ex::Mat(xs) => xs.iter().try_fold(Expand::default(), |acc, x| {
+                let x = Expand::of(x)?;
+                Some(Expand {
+                    ln: acc.ln || x.ln,
+                    trig: acc.trig || x.trig,
+                })
+            }),
+            _ => match e.var()? {
+                "ln" => Some(Expand {
+                    ln: true,
+                    trig: false,
+                }),
+                "trig" => Some(Expand {
+                    ln: false,
+                    trig: true,
+                }),
+                "all" => Some(Expand {
+                    ln: true,
+                    trig: true,
+                }),
+                _ => None,
+            },
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Multiply out products and integer powers of sums, the counterpart of
+    /// [`Ex::factor`]
+    ///
+    /// - Powers of sums by binomial coefficients, several terms at a time
+    /// - Denominators and negative powers are left alone
+    /// - `opts` also takes apart the arguments of `ln` and trig functions
+    pub fn expand(&self, opts: Expand) -> Ex {
+        expand(&self.simplified(), opts)
+    }
+}
+
+fn expand(e: &Ex, opts: Expand) -> Ex {
+    let e = e.map(|c| expand(c, opts)).simplified();
+    let r = match &e {
+        Ex::Fn(Fnc::Ln(a)) if opts.ln => ln(a),
+        Ex::Fn(f @ (Fnc::Sin(_) | Fnc::Cos(_) | Fnc::Tan(_))) if opts.trig => angle(f).unwrap(),
+        Ex::Infix(_, Bin::Rl(_), _) | Ex::Mat(_) | Ex::Invalid => return e,
+        _ => return distribute(&e).unwrap_or(e),
+    };
+    match r == e {
+        true => e,
+        false => expand(&r, opts),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Every product in `e` multiplied out over the sums it holds
+fn distribute(e: &Ex) -> Option<Ex> {
+    let s = Sum::of(e);
+    let mut out = vec![s.constant.to_ex()];
+    for (c, t) in &s.terms {
+        let p = Product::of(t);
+        let mut terms = vec![product(&[c.to_ex(), p.coef.to_ex()])];
+        for (b, k) in &p.factors {
+            let parts = match k.as_rat() {
+                Some(k) if k.is_int() && k.numer() > 0 && k.numer() <= MAX_POWER => {
+                    binomial(&summands(b), k.numer())?
+                }
+                _ => vec![power(b.clone(), k.clone())],
+            };
+            terms = times(&terms, &parts)?;
+        }
+        out.extend(terms);
+    }
+    if out.len() > MAX_TERMS {
+        return None;
+    }
+    Some(sum(&out))
+}
+
+/// Terms of a sum, or the expression itself
+fn summands(e: &Ex) -> Vec<Ex> {
+    let s = Sum::of(e);
+    let mut out: Vec<Ex> = s
+        .terms
+        .iter()
+        .map(|(c, t)| product(&[c.to_ex(), t.clone()]))
+        .collect();
+    if !s.constant.is_zero() {
+        out.push(s.constant.to_ex());
+    }
+    out
+}
+
+/// `(t0 + t1 + ..)^n` as a list of terms
+fn binomial(terms: &[Ex], n: i128) -> Option<Vec<Ex>> {
+    let (first, rest) = match terms {
+        [] => return Some(vec![]),
+        [t] => return Some(vec![power(t.clone(), Rat::int(n).to_ex())]),
+        [first, rest @ ..] => (first, rest),
+    };
+    let mut out = vec![];
+    let mut c: i128 = 1;
+    for k in 0..=n {
+        let head = vec![product(&[
+            Rat::int(c).to_ex(),
+            power(first.clone(), Rat::int(k).to_ex()),
+        ])];
+        out.extend(times(&head, &binomial(rest, n - k)?)?);
+        c = c.checked_mul(n - k)? / (k + 1);
+    }
+    Some(out)
+}
+
+/// Every product of a term of `a` with a term of `b`
+fn times(a: &[Ex], b: &[Ex]) -> Option<Vec<Ex>> {
+    if a.len().saturating_mul(b.len()) > MAX_TERMS {
+        return None;
+    }
+    Some(
+        a.iter()
+            .flat_map(|x| b.iter().map(move |y| product(&[x.clone(), y.clone()])))
+            .collect(),
+    )
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// `ln(a)` as a sum of logarithms of the factors of `a`
+///
+/// - A negative coefficient is left inside
+fn ln(a: &Ex) -> Ex {
+    let p = Product::of(a);
+    if p.coef.is_neg() {
+        return Fnc::Ln(Box::new(a.clone())).into();
+    }
+    let ln = |b: Ex| Ex::from(Fnc::Ln(Box::new(b))).simplified();
+    let mut out = vec![ln(p.coef.to_ex())];
+    for (b, k) in p.factors {
+        out.push(product(&[k, ln(b)]));
+    }
+    sum(&out)
+}
+
+/// `sin`, `cos` or `tan` by angle addition, splitting sums and integer
+/// multiples in the argument
+pub fn angle(f: &Fnc) -> Option<Ex> {
+    match f {
+        Fnc::Sin(a) => Some(sin_cos(a).0),
+        Fnc::Cos(a) => Some(sin_cos(a).1),
+        Fnc::Tan(a) => Some(tan(a)),
+        _ => None,
+    }
+}
+
+/// `(sin(a), cos(a))`
+fn sin_cos(a: &Ex) -> (Ex, Ex) {
+    let Some((u, v)) = split(a) else {
+        return (
+            Ex::from(Fnc::Sin(Box::new(a.clone()))).simplified(),
+            Ex::from(Fnc::Cos(Box::new(a.clone()))).simplified(),
+        );
+    };
+    let ((su, cu), (sv, cv)) = (sin_cos(&u), sin_cos(&v));
+    (
+        sum(&[
+            product(&[su.clone(), cv.clone()]),
+            product(&[cu.clone(), sv.clone()]),
+        ]),
+        sum(&[product(&[cu, cv]), product(&[Ex::from(-1), su, sv])]),
+    )
+}
+
+fn tan(a: &Ex) -> Ex {
+    let Some((u, v)) = split(a) else {
+        return Ex::from(Fnc::Tan(Box::new(a.clone()))).simplified();
+    };
+    let (tu, tv) = (tan(&u), tan(&v));
+    let den = sum(&[
+        Ex::from(1),
+        product(&[Ex::from(-1), tu.clone(), tv.clone()]),
+    ]);
+    product(&[sum(&[tu, tv]), power(den, Ex::from(-1))])
+}
+
+/// `a` as `u + v`, or `(k-1)*t + t` for an integer multiple `k*t`
+fn split(a: &Ex) -> Option<(Ex, Ex)> {
+    if let [first, rest @ ..] = summands(a).as_slice() {
+        if !rest.is_empty() {
+            return Some((first.clone(), sum(rest)));
+        }
+    }
+    let p = Product::of(a);
+    match p.coef {
+        Coef::Q(k) if k.is_int() && k.numer() >= 2 && !p.factors.is_empty() => {
+            let t = product(&[power(k.to_ex(), Ex::from(-1)), a.clone()]);
+            Some((product(&[Rat::int(k.numer() - 1).to_ex(), t.clone()]), t))
+        }
+        _ => None,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::simp;
+
+    fn expand(s: &str) -> String {
+        simp(s).to_text()
+    }
+
+    #[test]
+    fn ex3_expand_products() {
+        assert_eq!(simp("expand(2*(3+1*x))"), simp("2*x + 6"));
+        assert_eq!(simp("expand((x+1)^3)"), simp("x^3 + 3*x^2 + 3*x + 1"));
+        assert_eq!(
+            simp("expand((a+b+c)^2)"),
+            simp("a^2 + b^2 + c^2 + 2*a*b + 2*a*c + 2*b*c")
+        );
+        assert_eq!(simp("expand((x-1)*(x+1)*x)"), simp("x^3 - x"));
+        assert_eq!(
+            simp("expand((sin(x)+1)^2)"),
+            simp("sin(x)^2 + 2*sin(x) + 1")
+        );
+        assert_eq!(expand("expand(sin((x+1)^2))"), "sin(x^2 + 2*x + 1)");
+        // denominators stay
+        assert_eq!(expand("expand((x+1)/(x-1))"), "x/(x - 1) + 1/(x - 1)");
+        // the counterpart of factor
+        assert_eq!(simp("expand(factor(x^3 - x))"), simp("x^3 - x"));
+    }
+
+    #[test]
+    fn ex3_expand_functions() {
+        assert_eq!(expand("expand(ln(2*x))"), "ln(2*x)");
+        assert_eq!(expand("expand(ln(2*x), ln)"), "ln(x) + ln(2)");
+        assert_eq!(expand("expand(ln(x^3*y), ln)"), "3*ln(x) + ln(y)");
+        assert_eq!(
+            simp("expand(sin(x+y), trig)"),
+            simp("sin(x)*cos(y) + cos(x)*sin(y)")
+        );
+        assert_eq!(simp("expand(cos(2*x), trig)"), simp("cos(x)^2 - sin(x)^2"));
+        assert_eq!(
+            simp("expand(sin(3*x), trig)"),
+            simp("3*sin(x)*cos(x)^2 - sin(x)^3")
+        );
+        assert_eq!(
+            simp("expand(ln(x*y) + sin(2*x), all)"),
+            simp("ln(x) + ln(y) + 2*sin(x)*cos(x)")
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
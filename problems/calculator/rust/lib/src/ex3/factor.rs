@@ -0,0 +1,862 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, mpoly::*, poly::*, rational::*, simplify::*, solve::coeffs};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Placeholder variable for univariate factoring, never produced by the
+/// parser
+const T: &str = "_t";
+
+/// Largest coefficient bound handled by the modular method, so that products
+/// modulo the prime fit in an `i128`
+const MAX_BOUND: i128 = 100_000_000_000_000_000;
+
+/// Highest degree passed to the modular method
+const MAX_DEGREE: usize = 40;
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Factor a polynomial with rational coefficients into irreducibles
+    ///
+    /// - The rational content and common monomials are pulled out first
+    /// - Univariate polynomials are split into square-free parts, rational
+    ///   roots, and then fully by Zassenhaus' method modulo a large prime
+    /// - Multivariate polynomials are fully factored when they are
+    ///   homogeneous in two variables
+    /// - Other multivariate polynomials are split by their content in each
+    ///   variable, so `x*y + x + y + 1` gives `(x + 1)*(y + 1)`, and then
+    ///   fully by Kronecker's substitution, so `x^2*y + x*y^2 + x + y` gives
+    ///   `(x + y)*(x*y + 1)`
+    /// - A quotient has its numerator and denominator factored separately
+    /// - Anything else is returned simplified
+    pub fn factor(&self) -> Ex {
+        let e = self.simplified();
+        if let Ex::Infix(a, Bin::Op(Op::Div), b) = &e {
+            return product(&[a.factor(), power(b.factor(), Ex::from(-1))]);
+        }
+        match MPoly::from_ex(&e) {
+            Some(p) if !p.is_zero() => factor_mpoly(&p).unwrap_or(e),
+            _ => e,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+fn factor_mpoly(p: &MPoly) -> Option<Ex> {
+    let vars = p.vars();
+    let mut coefs = vec![];
+    for (_, c) in p.terms() {
+        match c {
+            Coef::Q(q) => coefs.push(*q),
+            Coef::F(_) => return None,
+        }
+    }
+
+    // rational content, signed so the leading term of the rest is positive
+    let mut den = 1i128;
+    let mut num = 0;
+    for q in &coefs {
+        den = (den / gcd(den, q.denom())).checked_mul(q.denom())?;
+    }
+    for q in &coefs {
+        num = gcd(num, q.checked_mul(Rat::int(den))?.numer());
+    }
+    let content = match coefs[0].is_neg() {
+        true => -Rat::new(num, den),
+        false => Rat::new(num, den),
+    };
+    let low: Vec<i32> = (0..vars.len())
+        .map(|i| p.terms().iter().map(|(m, _)| m[i]).min().unwrap())
+        .collect();
+    let terms: Vec<(Vec<i32>, Rat)> = p
+        .terms()
+        .iter()
+        .zip(&coefs)
+        .map(|((m, _), c)| {
+            Some((
+                m.iter().zip(&low).map(|(a, b)| a - b).collect(),
+                c.checked_div(content)?,
+            ))
+        })
+        .collect::<Option<_>>()?;
+
+    let mut out = vec![content.to_ex().simplified()];
+    for (v, &k) in vars.iter().zip(&low) {
+        out.push(power(v.clone(), Ex::from(k)));
+    }
+
+    let used: Vec<usize> = (0..vars.len())
+        .filter(|&i| terms.iter().any(|(m, _)| m[i] != 0))
+        .collect();
+    let rest = || {
+        let parts: Vec<Ex> = terms
+            .iter()
+            .map(|(m, c)| {
+                let mut xs = vec![c.to_ex().simplified()];
+                xs.extend(
+                    vars.iter()
+                        .zip(m)
+                        .map(|(v, &k)| power(v.clone(), Ex::from(k))),
+                );
+                product(&xs)
+            })
+            .collect();
+        sum(&parts)
+    };
+
+    match used.as_slice() {
+        [] => {}
+        [i] => {
+            let mut c = vec![Rat::ZERO; terms.iter().map(|(m, _)| m[*i] as usize).max()? + 1];
+            for (m, q) in &terms {
+                c[m[*i] as usize] = *q;
+            }
+            let (k, factors) = factor_upoly(&UPoly::new(c))?;
+            out.push(k.to_ex().simplified());
+            for (f, n) in factors {
+                let f = f.to_ex(T).replace(&Ex::from(T), &vars[*i]);
+                out.push(power(f, Ex::from(n as i32)));
+            }
+        }
+        [i, j] if homogeneous(&terms) => {
+            // f(x, y) = y^d * f(x/y, 1)
+            let d = terms[0].0.iter().sum::<i32>();
+            let mut c = vec![Rat::ZERO; d as usize + 1];
+            for (m, q) in &terms {
+                c[m[*i] as usize] = *q;
+            }
+            let (k, factors) = factor_upoly(&UPoly::new(c))?;
+            out.push(k.to_ex().simplified());
+            for (f, n) in factors {
+                let parts: Vec<Ex> = (0..=f.deg())
+                    .filter(|&e| !f.coeff(e).is_zero())
+                    .map(|e| {
+                        product(&[
+                            f.coeff(e).to_ex().simplified(),
+                            power(vars[*i].clone(), Ex::from(e as i32)),
+                            power(vars[*j].clone(), Ex::from((f.deg() - e) as i32)),
+                        ])
+                    })
+                    .collect();
+                out.push(power(sum(&parts), Ex::from(n as i32)));
+            }
+        }
+        _ => {
+            let used: Vec<&Ex> = used.iter().map(|&i| &vars[i]).collect();
+            out.push(
+                by_content(&rest(), &used)
+                    .or_else(|| kronecker(&rest()))
+                    .unwrap_or_else(rest),
+            );
+        }
+    }
+    Some(product(&out))
+}
+
+/// Split off the content of `e` as a polynomial in one of `vars`, the gcd
+/// of its coefficients, then factor both parts further
+fn by_content(e: &Ex, vars: &[&Ex]) -> Option<Ex> {
+    for v in vars {
+        let Some(x) = v.var() else {
+            continue;
+        };
+        let Some(c) = coeffs(e, x) else {
+            continue;
+        };
+        let mut c = c.into_iter().filter(|a| !a.is_zero());
+        let Some(mut g) = c.next() else {
+            continue;
+        };
+        for a in c {
+            g = g.gcd(&a)?;
+        }
+        let Some(y) = vars.iter().filter_map(|w| w.var()).find(|&y| g.has(y)) else {
+            continue;
+        };
+        if let Some((q, _)) = e.divide(&g, y).filter(|(_, r)| r.is_zero()) {
+            return Some(product(&[g.factor(), q.factor()]));
+        }
+    }
+    None
+}
+
+/// Factor by Kronecker's substitution, which maps the variables `x, y, ..`
+/// to `t, t^a, t^(a*b), ..`, with `a` and `b` past the degrees in `x` and
+/// `y`, so that no two terms meet
+///
+/// - The univariate image is factored, and the smallest products of its
+///   factors that map back to exact divisors are the irreducible factors
+/// - `None` if nothing splits, or the image is too large to factor
+fn kronecker(e: &Ex) -> Option<Ex> {
+    let f = MPoly::from_ex(e)?;
+    let vars = f.vars();
+    let mut weights = vec![1usize];
+    for i in 0..vars.len() {
+        let d = f.terms().iter().map(|(m, _)| m[i]).max()?;
+        weights.push(weights[i].checked_mul(usize::try_from(d).ok()? + 1)?);
+    }
+    let image = |m: &[i32]| m.iter().zip(&weights).map(|(&k, w)| k as usize * w).sum();
+    let deg: usize = f.terms().iter().map(|(m, _)| image(m)).max()?;
+    if deg > MAX_DEGREE {
+        return None;
+    }
+    let mut c = vec![Rat::ZERO; deg + 1];
+    for (m, q) in f.terms() {
+        match q {
+            Coef::Q(q) => c[image(m)] = *q,
+            Coef::F(_) => return None,
+        }
+    }
+    let (_, factors) = factor_upoly(&UPoly::new(c))?;
+    let mut parts: Vec<UPoly> = factors
+        .into_iter()
+        .flat_map(|(g, n)| (0..n).map(move |_| g.clone()))
+        .collect();
+
+    // each power of t back to the monomial whose image it is
+    let preimage = |g: &UPoly| {
+        let terms: Vec<Ex> = (0..=g.deg())
+            .filter(|&k| !g.coeff(k).is_zero())
+            .map(|k| {
+                let mut xs = vec![g.coeff(k).to_ex().simplified()];
+                for (i, v) in vars.iter().enumerate() {
+                    let d = k / weights[i] % (weights[i + 1] / weights[i]);
+                    xs.push(power(v.clone(), Ex::from(d as i32)));
+                }
+                product(&xs)
+            })
+            .collect();
+        MPoly::from_ex(&sum(&terms))
+    };
+
+    let (mut rest, mut out) = (f.clone(), vec![]);
+    let mut s = 1;
+    while 2 * s <= parts.len() {
+        let found = combinations(parts.len(), s).into_iter().find_map(|idx| {
+            let g = idx
+                .iter()
+                .try_fold(UPoly::constant(Rat::ONE), |acc, &i| acc.mul(&parts[i]))?;
+            let g = preimage(&g)?;
+            let g = match g.lead()?.1.is_neg() {
+                true => g.neg(),
+                false => g,
+            };
+            let q = exact_div(&rest, &g)?;
+            Some((idx, g, q))
+        });
+        match found {
+            Some((idx, g, q)) => {
+                out.push(g);
+                rest = q;
+                parts = (0..parts.len())
+                    .filter(|i| !idx.contains(i))
+                    .map(|i| parts[i].clone())
+                    .collect();
+            }
+            None => s += 1,
+        }
+    }
+    if out.is_empty() {
+        return None;
+    }
+    out.push(rest);
+    let out: Vec<Ex> = out.iter().map(|p| sum(&p.term_exs())).collect();
+    Some(product(&out))
+}
+
+/// `f / g` when `g` divides `f` exactly, dividing lexicographic leading
+/// terms
+fn exact_div(f: &MPoly, g: &MPoly) -> Option<MPoly> {
+    let g = g.clone().with_order(MonomialOrder::Lex);
+    let (gm, gc) = g.lead()?.clone();
+    // the quotient has no more terms than fit under the degrees of f
+    let steps: usize = (0..f.vars().len())
+        .map(|i| {
+            f.terms()
+                .iter()
+                .map(|(m, _)| m[i])
+                .max()
+                .unwrap_or(0)
+                .max(0) as usize
+                + 1
+        })
+        .product();
+    let mut r = f.clone().with_order(MonomialOrder::Lex);
+    let mut q = MPoly::zero();
+    for _ in 0..=steps {
+        let Some((rm, rc)) = r.lead().cloned() else {
+            return Some(q);
+        };
+        if g.vars()
+            .iter()
+            .zip(&gm)
+            .any(|(v, &k)| k != 0 && !r.vars().contains(v))
+        {
+            return None;
+        }
+        let c = match (rc, gc) {
+            (Coef::Q(a), Coef::Q(b)) => a.checked_div(b)?,
+            _ => return None,
+        };
+        let mut t = MPoly::constant(Coef::Q(c));
+        for (v, &k) in r.vars().iter().zip(&rm) {
+            let d = k - g.vars().iter().position(|u| u == v).map_or(0, |i| gm[i]);
+            if d < 0 {
+                return None;
+            }
+            t = t.mul(&MPoly::atom(v.clone(), d))?;
+        }
+        q = q.add(&t)?;
+        r = r.sub(&t.mul(&g)?)?;
+    }
+    None
+}
+
+fn homogeneous(terms: &[(Vec<i32>, Rat)]) -> bool {
+    let deg = |m: &Vec<i32>| m.iter().sum::<i32>();
+    terms.iter().all(|(m, _)| deg(m) == deg(&terms[0].0))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Factor a univariate polynomial over the rationals
+///
+/// - Returns a rational constant and primitive integer factors, each with
+///   a positive leading coefficient, with their multiplicities
+/// - `None` if the coefficients overflow along the way
+pub fn factor_upoly(f: &UPoly) -> Option<(Rat, Vec<(UPoly, u32)>)> {
+    if f.deg() == 0 {
+        return Some((f.lead(), vec![]));
+    }
+    let mut out: Vec<(UPoly, u32)> = vec![];
+    for (g, n) in square_free(f)? {
+        for h in factor_square_free(&g.primitive()?)? {
+            out.push((h, n));
+        }
+    }
+    out.sort_by(|a, b| {
+        (a.0.deg(), a.0.coeffs().to_vec())
+            .partial_cmp(&(b.0.deg(), b.0.coeffs().to_vec()))
+            .unwrap()
+    });
+    let lead = out.iter().try_fold(Rat::ONE, |acc, (g, n)| {
+        acc.checked_mul(g.lead().powi(*n as i128)?)
+    })?;
+    Some((f.lead().checked_div(lead)?, out))
+}
+
+/// Yun's square-free decomposition, as monic factors with multiplicities
+pub fn square_free(f: &UPoly) -> Option<Vec<(UPoly, u32)>> {
+    let mut out = vec![];
+    let d = f.derivative()?;
+    let a = f.gcd(&d)?;
+    let mut b = f.divrem(&a)?.0;
+    let mut c = d.divrem(&a)?.0;
+    let mut i = 1;
+    while b.deg() > 0 {
+        let dd = c.sub(&b.derivative()?)?;
+        let g = b.gcd(&dd)?;
+        b = b.divrem(&g)?.0;
+        c = dd.divrem(&g)?.0;
+        if g.deg() > 0 {
+            out.push((g.monic()?, i));
+        }
+        i += 1;
+    }
+    Some(out)
+}
+
+/// Irreducible factors of a square-free primitive integer polynomial
+fn factor_square_free(f: &UPoly) -> Option<Vec<UPoly>> {
+    let mut f = f.clone();
+    let mut out = vec![];
+    for r in f.rational_roots() {
+        let g = UPoly::linear(r).primitive()?;
+        f = f.divrem(&g)?.0.primitive()?;
+        out.push(g);
+    }
+    if f.deg() >= 4 {
+        out.extend(zassenhaus(&f)?);
+    } else if f.deg() > 0 {
+        out.push(f);
+    }
+    Some(out)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Factor a square-free primitive integer polynomial modulo a prime larger
+/// than twice the coefficient bound of any factor, then recombine
+///
+/// - Gives up, returning `f` itself, if the bound is too large
+fn zassenhaus(f: &UPoly) -> Option<Vec<UPoly>> {
+    let n = f.deg();
+    let c: Vec<i128> = f.coeffs().iter().map(|a| a.numer()).collect();
+    let lc = c[n];
+    let max = c.iter().map(|a| a.abs()).max().unwrap();
+    // Mignotte: every factor has coefficients below sqrt(n+1) * 2^n * max|c|
+    let bound =
+        (((n + 1) as f64).sqrt() * 2f64.powi(n as i32) * max as f64 * lc.abs() as f64).ceil();
+    if n > MAX_DEGREE || bound > MAX_BOUND as f64 {
+        return Some(vec![f.clone()]);
+    }
+
+    let mut p = 2 * bound as i128 + 1;
+    let (p, ff) = loop {
+        p = next_prime(p + 1);
+        if lc % p == 0 {
+            continue;
+        }
+        let fp = Zp::new(p).reduce(&c);
+        let zp = Zp::new(p);
+        if zp.gcd(&fp, &zp.derivative(&fp)).len() == 1 {
+            break (p, fp);
+        }
+    };
+    let zp = Zp::new(p);
+    let monic = zp.scale(&ff, zp.inv(lc.rem_euclid(p)));
+    let mut modular = vec![];
+    for (g, d) in zp.distinct_degree(&monic) {
+        modular.extend(zp.equal_degree(&g, d));
+    }
+    recombine(f, modular, &zp)
+}
+
+/// Find the subsets of modular factors whose product is a true factor
+fn recombine(f: &UPoly, mut modular: Vec<Vec<i128>>, zp: &Zp) -> Option<Vec<UPoly>> {
+    let mut f = f.clone();
+    let mut out = vec![];
+    let mut s = 1;
+    while 2 * s <= modular.len() {
+        let mut found = false;
+        for subset in combinations(modular.len(), s) {
+            let lc = f.lead().numer().rem_euclid(zp.p);
+            let g = subset
+                .iter()
+                .fold(vec![lc], |acc, &i| zp.mul(&acc, &modular[i]));
+            let g = UPoly::new(g.iter().map(|&a| Rat::int(zp.symmetric(a))).collect());
+            if let Some((g, q)) = divides(&g, &f) {
+                out.push(g);
+                f = q.primitive()?;
+                for &i in subset.iter().rev() {
+                    modular.remove(i);
+                }
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            s += 1;
+        }
+    }
+    if f.deg() > 0 {
+        out.push(f);
+    }
+    Some(out)
+}
+
+/// The primitive part of `g` and the quotient, if it divides `f`
+///
+/// - A candidate whose trial division overflows is not a factor, since true
+///   factors stay below the coefficient bound
+fn divides(g: &UPoly, f: &UPoly) -> Option<(UPoly, UPoly)> {
+    let g = g.primitive()?;
+    let (q, r) = f.divrem(&g)?;
+    r.is_zero().then_some((g, q))
+}
+
+/// Every `k` element subset of `0..n`, as increasing indices
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    fn go(start: usize, n: usize, k: usize, cur: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        if cur.len() == k {
+            out.push(cur.clone());
+            return;
+        }
+        for i in start..n {
+            cur.push(i);
+            go(i + 1, n, k, cur, out);
+            cur.pop();
+        }
+    }
+    let mut out = vec![];
+    go(0, n, k, &mut vec![], &mut out);
+    out
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Arithmetic on dense polynomials modulo a prime, lowest degree first
+struct Zp {
+    p: i128,
+}
+
+impl Zp {
+    fn new(p: i128) -> Self {
+        Zp { p }
+    }
+
+    fn trim(&self, mut a: Vec<i128>) -> Vec<i128> {
+        while a.len() > 1 && *a.last().unwrap() == 0 {
+            a.pop();
+        }
+        if a.is_empty() {
+            a.push(0);
+        }
+        a
+    }
+
+    fn reduce(&self, a: &[i128]) -> Vec<i128> {
+        self.trim(a.iter().map(|x| x.rem_euclid(self.p)).collect())
+    }
+
+    fn is_zero(&self, a: &[i128]) -> bool {
+        a.len() == 1 && a[0] == 0
+    }
+
+    /// Representative in `(-p/2, p/2]`
+    fn symmetric(&self, a: i128) -> i128 {
+        if a > self.p / 2 {
+            a - self.p
+        } else {
+            a
+        }
+    }
+
+    fn pow(&self, mut b: i128, mut e: i128) -> i128 {
+        let mut r = 1;
+        b = b.rem_euclid(self.p);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = r * b % self.p;
+            }
+            b = b * b % self.p;
+            e >>= 1;
+        }
+        r
+    }
+
+    fn inv(&self, a: i128) -> i128 {
+        self.pow(a, self.p - 2)
+    }
+
+    fn scale(&self, a: &[i128], k: i128) -> Vec<i128> {
+        self.trim(a.iter().map(|x| x * k % self.p).collect())
+    }
+
+    fn sub(&self, a: &[i128], b: &[i128]) -> Vec<i128> {
+        let n = a.len().max(b.len());
+        let at = |v: &[i128], i: usize| v.get(i).copied().unwrap_or(0);
+        self.trim(
+            (0..n)
+                .map(|i| (at(a, i) - at(b, i)).rem_euclid(self.p))
+                .collect(),
+        )
+    }
+
+    fn mul(&self, a: &[i128], b: &[i128]) -> Vec<i128> {
+        let mut c = vec![0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                c[i + j] = (c[i + j] + x * y) % self.p;
+            }
+        }
+        self.trim(c)
+    }
+
+    fn rem(&self, a: &[i128], b: &[i128]) -> Vec<i128> {
+        self.divrem(a, b).1
+    }
+
+    fn divrem(&self, a: &[i128], b: &[i128]) -> (Vec<i128>, Vec<i128>) {
+        let mut r = a.to_vec();
+        let n = b.len() - 1;
+        let inv = self.inv(b[n]);
+        let mut q = vec![0; a.len().saturating_sub(n).max(1)];
+        while r.len() > n && !self.is_zero(&r) {
+            let shift = r.len() - 1 - n;
+            let k = r[r.len() - 1] * inv % self.p;
+            q[shift] = k;
+            for (i, y) in b.iter().enumerate() {
+                r[shift + i] = (r[shift + i] - k * y).rem_euclid(self.p);
+            }
+            r = self.trim(r);
+            if r.len() - 1 < n || (r.len() == 1 && n == 0) {
+                break;
+            }
+        }
+        (self.trim(q), r)
+    }
+
+    /// Monic greatest common divisor
+    fn gcd(&self, a: &[i128], b: &[i128]) -> Vec<i128> {
+        let (mut a, mut b) = (a.to_vec(), b.to_vec());
+        while !self.is_zero(&b) {
+            let r = self.rem(&a, &b);
+            (a, b) = (b, r);
+        }
+        let lead = *a.last().unwrap();
+        match lead {
+            0 => a,
+            _ => self.scale(&a, self.inv(lead)),
+        }
+    }
+
+    fn derivative(&self, a: &[i128]) -> Vec<i128> {
+        let d: Vec<i128> = a
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, x)| x * i as i128 % self.p)
+            .collect();
+        self.trim(d)
+    }
+
+    /// `a^e mod m`
+    fn powmod(&self, a: &[i128], mut e: i128, m: &[i128]) -> Vec<i128> {
+        let mut r = vec![1];
+        let mut b = self.rem(a, m);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = self.rem(&self.mul(&r, &b), m);
+            }
+            b = self.rem(&self.mul(&b, &b), m);
+            e >>= 1;
+        }
+        r
+    }
+
+    /// Split a monic square-free polynomial into products of irreducibles of
+    /// equal degree
+    fn distinct_degree(&self, f: &[i128]) -> Vec<(Vec<i128>, usize)> {
+        let mut out = vec![];
+        let mut f = f.to_vec();
+        let x = vec![0, 1];
+        let mut h = x.clone();
+        let mut d = 1;
+        while f.len() > 2 * d {
+            h = self.powmod(&h, self.p, &f);
+            let g = self.gcd(&f, &self.sub(&h, &x));
+            if g.len() > 1 {
+                f = self.divrem(&f, &g).0;
+                h = self.rem(&h, &f);
+                out.push((g, d));
+            }
+            d += 1;
+        }
+        if f.len() > 1 {
+            let n = f.len() - 1;
+            out.push((f, n));
+        }
+        out
+    }
+
+    /// Cantor–Zassenhaus splitting of a product of irreducibles of degree `d`
+    fn equal_degree(&self, f: &[i128], d: usize) -> Vec<Vec<i128>> {
+        let n = f.len() - 1;
+        if n <= d {
+            return vec![f.to_vec()];
+        }
+        let mut rng = Lcg(0x2545_f491_4f6c_dd1d);
+        loop {
+            let a: Vec<i128> = (0..n).map(|_| rng.next() % self.p).collect();
+            let a = self.trim(a);
+            if a.len() < 2 {
+                continue;
+            }
+            // a * a^p * .. * a^(p^(d-1)), raised to (p - 1) / 2
+            let mut t = a.clone();
+            let mut s = a.clone();
+            for _ in 1..d {
+                t = self.powmod(&t, self.p, f);
+                s = self.rem(&self.mul(&s, &t), f);
+            }
+            let u = self.sub(&self.powmod(&s, (self.p - 1) / 2, f), &[1]);
+            let g = self.gcd(f, &u);
+            if g.len() > 1 && g.len() <= n {
+                let h = self.divrem(f, &g).0;
+                let mut out = self.equal_degree(&g, d);
+                out.extend(self.equal_degree(&h, d));
+                return out;
+            }
+        }
+    }
+}
+
+/// Deterministic source of pseudo-random coefficients
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> i128 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 1) as i128
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Smallest prime at least `n`
+fn next_prime(mut n: i128) -> i128 {
+    if n <= 3 {
+        return 3;
+    }
+    if n % 2 == 0 {
+        n += 1;
+    }
+    while !is_prime(n) {
+        n += 2;
+    }
+    n
+}
+
+/// Deterministic Miller–Rabin, exact below `3.3 * 10^24`
+fn is_prime(n: i128) -> bool {
+    const BASES: [i128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+    if n < 2 {
+        return false;
+    }
+    for b in BASES {
+        if n % b == 0 {
+            return n == b;
+        }
+    }
+    let zn = Zp::new(n);
+    let (mut d, mut s) = (n - 1, 0);
+    while d % 2 == 0 {
+        d /= 2;
+        s += 1;
+    }
+    'outer: for b in BASES {
+        let mut x = zn.pow(b, d);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x * x % n;
+            if x == n - 1 {
+                continue 'outer;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::simp;
+
+    fn factor(s: &str) -> Ex {
+        s.parse::<Ex>().unwrap().factor()
+    }
+
+    /// Factoring never changes the polynomial
+    fn check(s: &str) {
+        let f = factor(s);
+        assert_eq!(f.normal(), simp(s).normal(), "{:?}", f);
+    }
+
+    #[test]
+    fn ex3_factor_simple() {
+        assert_eq!(factor("x^2-1"), simp("(x+1)*(x-1)"));
+        assert_eq!(factor("2*x^2-2"), simp("2*(x+1)*(x-1)"));
+        assert_eq!(factor("x^3+2*x^2+x"), simp("x*(x+1)^2"));
+        assert_eq!(factor("x^2/2-1/2"), simp("(x+1)*(x-1)/2"));
+        assert_eq!(factor("6*x^2+5*x+1"), simp("(2*x+1)*(3*x+1)"));
+        assert_eq!(factor("x^2+1"), simp("x^2+1"));
+        for s in ["x^2-1", "x^3+2*x^2+x", "x^2/2-1/2", "-(x^2)+1", "4*x^4-1"] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn ex3_factor_zassenhaus() {
+        // no rational roots, but splits over the integers
+        assert_eq!(factor("x^4+4"), simp("(x^2-2*x+2)*(x^2+2*x+2)"));
+        assert_eq!(factor("x^4-1"), simp("(x-1)*(x+1)*(x^2+1)"));
+        assert_eq!(factor("x^6-1"), simp("(x-1)*(x+1)*(x^2-x+1)*(x^2+x+1)"));
+        // irreducible over the integers, but not modulo any prime
+        assert_eq!(factor("x^4+1"), simp("x^4+1"));
+        for s in ["x^4+4", "x^6-1", "x^5-x-1", "(x^3+x+1)*(x^2+x+1)", "x^8-1"] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn ex3_factor_multivariate() {
+        assert_eq!(factor("x^2-y^2"), simp("(x+y)*(x-y)"));
+        assert_eq!(factor("x^2*y+x*y^2"), simp("x*y*(x+y)"));
+        assert_eq!(factor("2*a*x+2*a*y"), simp("2*a*(x+y)"));
+        for s in ["x^3-y^3", "x^2+2*x*y+y^2", "x^2*y+x*y^2"] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn ex3_factor_content() {
+        assert_eq!(factor("x*y+x+y+1"), simp("(x+1)*(y+1)"));
+        assert_eq!(factor("(x+1)*(y+1)*(x+y)"), simp("(x+1)*(y+1)*(x+y)"));
+        assert_eq!(factor("a*x^2-a+b*x^2-b"), simp("(a+b)*(x-1)*(x+1)"));
+    }
+
+    #[test]
+    fn ex3_factor_kronecker() {
+        // factors involving every variable
+        assert_eq!(factor("x^2*y+x*y^2+x+y"), simp("(x+y)*(x*y+1)"));
+        assert_eq!(factor("(x+y)*(x*y+1)"), simp("(x+y)*(x*y+1)"));
+        assert_eq!(factor("(x*y+z)*(x+y*z)"), simp("(x*y+z)*(x+y*z)"));
+        assert_eq!(factor("(x^2+y+1)*(x-y^2+2)"), simp("-(x^2+y+1)*(y^2-x-2)"));
+        assert_eq!(factor("(x*y-1)^2*(x+y+1)"), simp("(x*y-1)^2*(x+y+1)"));
+        // irreducible
+        assert_eq!(factor("x^2*y+y^2+x"), simp("x^2*y+y^2+x"));
+        for s in ["(x+y)*(x*y+1)", "(2*x*y-3)*(x^2+y)", "(x*y-1)^2*(x+y+1)"] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn ex3_factor_overflow() {
+        // candidate roots whose powers overflow are skipped
+        assert_eq!(
+            factor("x^20 - 1000000"),
+            simp("(x^10 - 1000)*(x^10 + 1000)")
+        );
+        for s in ["x^13 - 999999", "3*x^25 - 7*x + 999999"] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn ex3_square_free() {
+        let p = UPoly::from_ex(&"(x-1)^3*(x+2)^2*(x+3)".parse().unwrap(), "x").unwrap();
+        let sf = square_free(&p).unwrap();
+        let degs: Vec<(usize, u32)> = sf.iter().map(|(g, n)| (g.deg(), *n)).collect();
+        assert_eq!(degs, vec![(1, 1), (1, 2), (1, 3)]);
+        assert!(is_prime(1_000_000_007));
+        assert!(!is_prime(561));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -4,7 +4,7 @@
 
 use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
 
-use super::ast::*;
+use super::{ast::*, bigfloat::BigFloat};
 
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
@@ -72,6 +72,12 @@ impl From<Tok> for Ex {
 
 impl From<i32> for Ex {
     fn from(value: i32) -> Self {
+        Num::Int(value.into()).into()
+    }
+}
+
+impl From<i128> for Ex {
+    fn from(value: i128) -> Self {
         Num::Int(value).into()
     }
 }
@@ -84,6 +90,12 @@ impl From<f64> for Ex {
     }
 }
 
+impl From<BigFloat> for Ex {
+    fn from(value: BigFloat) -> Self {
+        Num::Big(value).into()
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
@@ -196,6 +208,423 @@ pub fn ln(e: Ex) -> Ex {
     Fnc::Ln(Box::new(e)).into()
 }
 
+pub fn atan(e: Ex) -> Ex {
+    Fnc::Atan(Box::new(e)).into()
+}
+
+pub fn abs(e: Ex) -> Ex {
+    Fnc::Abs(Box::new(e)).into()
+}
+
+pub fn floor(e: Ex) -> Ex {
+    Fnc::Floor(Box::new(e)).into()
+}
+
+pub fn ceil(e: Ex) -> Ex {
+    Fnc::Ceil(Box::new(e)).into()
+}
+
+pub fn round(e: Ex) -> Ex {
+    Fnc::Round(Box::new(e)).into()
+}
+
+pub fn frac(e: Ex) -> Ex {
+    Fnc::Frac(Box::new(e)).into()
+}
+
+pub fn sign(e: Ex) -> Ex {
+    Fnc::Sign(Box::new(e)).into()
+}
+
+pub fn norm(e: Ex) -> Ex {
+    Fnc::Norm(Box::new(e)).into()
+}
+
+pub fn d(e: Ex, x: Ex) -> Ex {
+    Fnc::D(Box::new(e), Box::new(x)).into()
+}
+
+pub fn int(e: Ex, x: Ex) -> Ex {
+    Fnc::Int(Box::new(e), Box::new(x)).into()
+}
+
+pub fn defint(e: Ex, x: Ex, lo: Ex, hi: Ex) -> Ex {
+    Fnc::DefInt(Box::new(e), Box::new(x), Box::new(lo), Box::new(hi)).into()
+}
+
+pub fn nint(e: Ex, x: Ex, lo: Ex, hi: Ex) -> Ex {
+    Fnc::NInt(Box::new(e), Box::new(x), Box::new(lo), Box::new(hi)).into()
+}
+
+pub fn solve(e: Ex, x: Ex) -> Ex {
+    Fnc::Solve(Box::new(e), Box::new(x)).into()
+}
+
+pub fn csolve(e: Ex, x: Ex) -> Ex {
+    Fnc::CSolve(Box::new(e), Box::new(x)).into()
+}
+
+pub fn nsolve(e: Ex, x: Ex, guess: Ex) -> Ex {
+    Fnc::NSolve(Box::new(e), Box::new(x), Box::new(guess)).into()
+}
+
+pub fn zeros(e: Ex, x: Ex, lo: Ex, hi: Ex) -> Ex {
+    Fnc::Zeros(Box::new(e), Box::new(x), Box::new(lo), Box::new(hi)).into()
+}
+
+pub fn factor(e: Ex) -> Ex {
+    Fnc::Factor(Box::new(e)).into()
+}
+
+pub fn expand(e: Ex) -> Ex {
+    Fnc::Expand(Box::new(e)).into()
+}
+
+pub fn expand_with(e: Ex, opts: Ex) -> Ex {
+    Fnc::ExpandWith(Box::new(e), Box::new(opts)).into()
+}
+
+pub fn texpand(e: Ex) -> Ex {
+    Fnc::TExpand(Box::new(e)).into()
+}
+
+pub fn tcollect(e: Ex) -> Ex {
+    Fnc::TCollect(Box::new(e)).into()
+}
+
+pub fn approx(e: Ex) -> Ex {
+    Fnc::Approx(Box::new(e)).into()
+}
+
+pub fn approx_to(e: Ex, digits: Ex) -> Ex {
+    Fnc::ApproxTo(Box::new(e), Box::new(digits)).into()
+}
+
+pub fn quo(a: Ex, b: Ex, x: Ex) -> Ex {
+    Fnc::Quo(Box::new(a), Box::new(b), Box::new(x)).into()
+}
+
+pub fn rem(a: Ex, b: Ex, x: Ex) -> Ex {
+    Fnc::Rem(Box::new(a), Box::new(b), Box::new(x)).into()
+}
+
+pub fn propfrac(e: Ex, x: Ex) -> Ex {
+    Fnc::PropFrac(Box::new(e), Box::new(x)).into()
+}
+
+pub fn poly_gcd(a: Ex, b: Ex) -> Ex {
+    Fnc::Gcd(Box::new(a), Box::new(b)).into()
+}
+
+pub fn poly_lcm(a: Ex, b: Ex) -> Ex {
+    Fnc::Lcm(Box::new(a), Box::new(b)).into()
+}
+
+pub fn lim(e: Ex, x: Ex, p: Ex) -> Ex {
+    Fnc::Lim(Box::new(e), Box::new(x), Box::new(p)).into()
+}
+
+pub fn lim_side(e: Ex, x: Ex, p: Ex, side: Ex) -> Ex {
+    Fnc::LimSide(Box::new(e), Box::new(x), Box::new(p), Box::new(side)).into()
+}
+
+pub fn taylor(e: Ex, x: Ex, n: Ex, p: Ex) -> Ex {
+    Fnc::Taylor(Box::new(e), Box::new(x), Box::new(n), Box::new(p)).into()
+}
+
+pub fn sum_of(e: Ex, k: Ex, lo: Ex, hi: Ex) -> Ex {
+    Fnc::Sum(Box::new(e), Box::new(k), Box::new(lo), Box::new(hi)).into()
+}
+
+pub fn prod_of(e: Ex, k: Ex, lo: Ex, hi: Ex) -> Ex {
+    Fnc::Prod(Box::new(e), Box::new(k), Box::new(lo), Box::new(hi)).into()
+}
+
+pub fn desolve(e: Ex, x: Ex, y: Ex) -> Ex {
+    Fnc::DeSolve(Box::new(e), Box::new(x), Box::new(y)).into()
+}
+
+pub fn desolve_ivp(e: Ex, x: Ex, y: Ex, init: Ex) -> Ex {
+    Fnc::DeSolveIvp(Box::new(e), Box::new(x), Box::new(y), Box::new(init)).into()
+}
+
+pub fn ndesolve(e: Ex, x: Ex, y: Ex, init: Ex, end: Ex) -> Ex {
+    Fnc::NDeSolve(
+        Box::new(e),
+        Box::new(x),
+        Box::new(y),
+        Box::new(init),
+        Box::new(end),
+    )
+    .into()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Fnc {
+    /// Name as written in the grammar
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fnc::Ln(_) => "ln",
+            Fnc::Sin(_) => "sin",
+            Fnc::Cos(_) => "cos",
+            Fnc::Tan(_) => "tan",
+            Fnc::Atan(_) => "atan",
+            Fnc::Abs(_) => "abs",
+            Fnc::Floor(_) => "floor",
+            Fnc::Ceil(_) => "ceil",
+            Fnc::Round(_) => "round",
+            Fnc::Frac(_) => "frac",
+            Fnc::Sign(_) => "sign",
+            Fnc::Norm(_) => "norm",
+            Fnc::D(..) => "d",
+            Fnc::Int(..) | Fnc::DefInt(..) => "int",
+            Fnc::NInt(..) => "nInt",
+            Fnc::Solve(..) => "solve",
+            Fnc::CSolve(..) => "cSolve",
+            Fnc::NSolve(..) => "nSolve",
+            Fnc::Zeros(..) => "zeros",
+            Fnc::Factor(_) => "factor",
+            Fnc::Expand(_) | Fnc::ExpandWith(..) => "expand",
+            Fnc::TExpand(_) => "tExpand",
+            Fnc::TCollect(_) => "tCollect",
+            Fnc::Approx(_) | Fnc::ApproxTo(..) => "approx",
+            Fnc::Quo(..) => "quo",
+            Fnc::Rem(..) => "rem",
+            Fnc::PropFrac(..) => "propFrac",
+            Fnc::Gcd(..) => "gcd",
+            Fnc::Lcm(..) => "lcm",
+            Fnc::Lim(..) | Fnc::LimSide(..) => "lim",
+            Fnc::Taylor(..) => "taylor",
+            Fnc::Sum(..) => "sum",
+            Fnc::Prod(..) => "prod",
+            Fnc::DeSolve(..) | Fnc::DeSolveIvp(..) => "deSolve",
+            Fnc::NDeSolve(..) => "nDeSolve",
+        }
+    }
+
+    /// Arguments in order of appearance
+    pub fn args(&self) -> Vec<&Ex> {
+        match self {
+            Fnc::Ln(a)
+            | Fnc::Sin(a)
+            | Fnc::Cos(a)
+            | Fnc::Tan(a)
+            | Fnc::Atan(a)
+            | Fnc::Abs(a)
+            | Fnc::Floor(a)
+            | Fnc::Ceil(a)
+            | Fnc::Round(a)
+            | Fnc::Frac(a)
+            | Fnc::Sign(a)
+            | Fnc::Norm(a)
+            | Fnc::Factor(a)
+            | Fnc::Expand(a)
+            | Fnc::TExpand(a)
+            | Fnc::TCollect(a)
+            | Fnc::Approx(a) => {
+                vec![a]
+            }
+            Fnc::D(a, x)
+            | Fnc::Int(a, x)
+            | Fnc::Solve(a, x)
+            | Fnc::CSolve(a, x)
+            | Fnc::PropFrac(a, x)
+            | Fnc::Gcd(a, x)
+            | Fnc::Lcm(a, x)
+            | Fnc::ExpandWith(a, x)
+            | Fnc::ApproxTo(a, x) => vec![a, x],
+            Fnc::NSolve(a, x, g)
+            | Fnc::Quo(a, x, g)
+            | Fnc::Rem(a, x, g)
+            | Fnc::Lim(a, x, g)
+            | Fnc::DeSolve(a, x, g) => vec![a, x, g],
+            Fnc::DefInt(a, x, lo, hi)
+            | Fnc::NInt(a, x, lo, hi)
+            | Fnc::Zeros(a, x, lo, hi)
+            | Fnc::LimSide(a, x, lo, hi)
+            | Fnc::Taylor(a, x, lo, hi)
+            | Fnc::Sum(a, x, lo, hi)
+            | Fnc::Prod(a, x, lo, hi)
+            | Fnc::DeSolveIvp(a, x, lo, hi) => vec![a, x, lo, hi],
+            Fnc::NDeSolve(a, x, y, init, end) => vec![a, x, y, init, end],
+        }
+    }
+
+    /// Variables the function binds in its first argument, such as `x` in
+    /// `d(f, x)` or `k` in `sum(f, k, 1, n)`, always its next arguments
+    pub fn binders(&self) -> Vec<&Ex> {
+        match self {
+            Fnc::D(_, x)
+            | Fnc::Int(_, x)
+            | Fnc::Solve(_, x)
+            | Fnc::CSolve(_, x)
+            | Fnc::NSolve(_, x, _)
+            | Fnc::Lim(_, x, _)
+            | Fnc::LimSide(_, x, _, _)
+            | Fnc::Taylor(_, x, _, _)
+            | Fnc::DefInt(_, x, _, _)
+            | Fnc::NInt(_, x, _, _)
+            | Fnc::Zeros(_, x, _, _)
+            | Fnc::Sum(_, x, _, _)
+            | Fnc::Prod(_, x, _, _) => vec![x],
+            Fnc::DeSolve(_, x, y) | Fnc::DeSolveIvp(_, x, y, _) | Fnc::NDeSolve(_, x, y, _, _) => {
+                vec![x, y]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Rebuild with every argument mapped through `f`
+    pub fn map<F: FnMut(&Ex) -> Ex>(&self, mut f: F) -> Fnc {
+        let mut g = |a: &Ex| Box::new(f(a));
+        match self {
+            Fnc::Ln(a) => Fnc::Ln(g(a)),
+            Fnc::Sin(a) => Fnc::Sin(g(a)),
+            Fnc::Cos(a) => Fnc::Cos(g(a)),
+            Fnc::Tan(a) => Fnc::Tan(g(a)),
+            Fnc::Atan(a) => Fnc::Atan(g(a)),
+            Fnc::Abs(a) => Fnc::Abs(g(a)),
+            Fnc::Floor(a) => Fnc::Floor(g(a)),
+            Fnc::Ceil(a) => Fnc::Ceil(g(a)),
+            Fnc::Round(a) => Fnc::Round(g(a)),
+            Fnc::Frac(a) => Fnc::Frac(g(a)),
+            Fnc::Sign(a) => Fnc::Sign(g(a)),
+            Fnc::Norm(a) => Fnc::Norm(g(a)),
+            Fnc::D(a, x) => Fnc::D(g(a), g(x)),
+            Fnc::Int(a, x) => Fnc::Int(g(a), g(x)),
+            Fnc::DefInt(a, x, lo, hi) => Fnc::DefInt(g(a), g(x), g(lo), g(hi)),
+            Fnc::NInt(a, x, lo, hi) => Fnc::NInt(g(a), g(x), g(lo), g(hi)),
+            Fnc::Solve(a, x) => Fnc::Solve(g(a), g(x)),
+            Fnc::CSolve(a, x) => Fnc::CSolve(g(a), g(x)),
+            Fnc::NSolve(a, x, guess) => Fnc::NSolve(g(a), g(x), g(guess)),
+            Fnc::Zeros(a, x, lo, hi) => Fnc::Zeros(g(a), g(x), g(lo), g(hi)),
+            Fnc::Factor(a) => Fnc::Factor(g(a)),
+            Fnc::Expand(a) => Fnc::Expand(g(a)),
+            Fnc::TExpand(a) => Fnc::TExpand(g(a)),
+            Fnc::TCollect(a) => Fnc::TCollect(g(a)),
+            Fnc::ExpandWith(a, o) => Fnc::ExpandWith(g(a), g(o)),
+            Fnc::Approx(a) => Fnc::Approx(g(a)),
+            Fnc::ApproxTo(a, d) => Fnc::ApproxTo(g(a), g(d)),
+            Fnc::Quo(a, b, x) => Fnc::Quo(g(a), g(b), g(x)),
+            Fnc::Rem(a, b, x) => Fnc::Rem(g(a), g(b), g(x)),
+            Fnc::PropFrac(a, x) => Fnc::PropFrac(g(a), g(x)),
+            Fnc::Gcd(a, b) => Fnc::Gcd(g(a), g(b)),
+            Fnc::Lcm(a, b) => Fnc::Lcm(g(a), g(b)),
+            Fnc::Lim(a, x, p) => Fnc::Lim(g(a), g(x), g(p)),
+            Fnc::LimSide(a, x, p, s) => Fnc::LimSide(g(a), g(x), g(p), g(s)),
+            Fnc::Taylor(a, x, n, p) => Fnc::Taylor(g(a), g(x), g(n), g(p)),
+            Fnc::Sum(a, k, lo, hi) => Fnc::Sum(g(a), g(k), g(lo), g(hi)),
+            Fnc::Prod(a, k, lo, hi) => Fnc::Prod(g(a), g(k), g(lo), g(hi)),
+            Fnc::DeSolve(a, x, y) => Fnc::DeSolve(g(a), g(x), g(y)),
+            Fnc::DeSolveIvp(a, x, y, init) => Fnc::DeSolveIvp(g(a), g(x), g(y), g(init)),
+            Fnc::NDeSolve(a, x, y, init, end) => Fnc::NDeSolve(g(a), g(x), g(y), g(init), g(end)),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Rebuild with every direct child mapped through `f`
+    pub fn map<F: FnMut(&Ex) -> Ex>(&self, mut f: F) -> Ex {
+        match self {
+            Ex::Val(_) | Ex::Invalid => self.clone(),
+            Ex::Infix(a, j, b) => f(a).c(f(b), j.clone()),
+            Ex::Neg(a) => -f(a),
+            Ex::Fn(fnc) => fnc.map(f).into(),
+            Ex::Mat(xs) => Ex::Mat(xs.iter().map(f).collect()),
+        }
+    }
+
+    /// Direct children in order of appearance
+    pub fn children(&self) -> Vec<&Ex> {
+        match self {
+            Ex::Val(_) | Ex::Invalid => vec![],
+            Ex::Infix(a, _, b) => vec![a, b],
+            Ex::Neg(a) => vec![a],
+            Ex::Fn(fnc) => fnc.args(),
+            Ex::Mat(xs) => xs.iter().collect(),
+        }
+    }
+
+    /// Name of a plain variable
+    pub fn var(&self) -> Option<&str> {
+        match self {
+            Ex::Val(Val::Var(x)) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Ex::Val(Val::Num(Num::Int(0))))
+            || matches!(self, Ex::Val(Val::Num(Num::Flt(v))) if *v == 0.0)
+    }
+
+    pub fn is_one(&self) -> bool {
+        matches!(self, Ex::Val(Val::Num(Num::Int(1))))
+            || matches!(self, Ex::Val(Val::Num(Num::Flt(v))) if *v == 1.0)
+    }
+
+    /// Whether the variable `x` occurs anywhere in the expression
+    pub fn has(&self, x: &str) -> bool {
+        match self {
+            Ex::Val(Val::Var(y)) => x == y,
+            _ => self.children().into_iter().any(|a| a.has(x)),
+        }
+    }
+
+    /// Whether `sub` occurs anywhere in the expression
+    pub fn contains(&self, sub: &Ex) -> bool {
+        self == sub || self.children().into_iter().any(|a| a.contains(sub))
+    }
+
+    /// Replace every occurrence of the subterm `from` with `to`
+    pub fn replace(&self, from: &Ex, to: &Ex) -> Ex {
+        if self == from {
+            to.clone()
+        } else {
+            self.map(|a| a.replace(from, to))
+        }
+    }
+
+    /// Substitute `value` for every occurrence of the variable `x`
+    pub fn subs(&self, x: &str, value: &Ex) -> Ex {
+        self.replace(&Ex::from(x), value)
+    }
+
+    /// Substitute `value` for the free occurrences of the variable `x`
+    ///
+    /// - Inside a function that binds `x`, such as `d(x^2, x)`, the body and
+    ///   the binders are left alone and only the other arguments, such as
+    ///   the bounds of a sum, are substituted
+    pub fn subs_free(&self, x: &str, value: &Ex) -> Ex {
+        match self {
+            Ex::Val(Val::Var(y)) if y == x => value.clone(),
+            Ex::Fn(f) if f.binders().iter().any(|b| b.var() == Some(x)) => {
+                let kept = 1 + f.binders().len();
+                let mut i = 0;
+                f.map(|a| {
+                    i += 1;
+                    match i <= kept {
+                        true => a.clone(),
+                        false => a.subs_free(x, value),
+                    }
+                })
+                .into()
+            }
+            _ => self.map(|a| a.subs_free(x, value)),
+        }
+    }
+
+    /// Number of nodes in the tree
+    pub fn size(&self) -> usize {
+        1 + self.children().into_iter().map(Ex::size).sum::<usize>()
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
@@ -204,7 +633,7 @@ impl Add for Num {
     type Output = Num;
 
     fn add(self, rhs: Self) -> Self::Output {
-        self.either_wrap(rhs, |a, b| a + b, |a, b| a + b)
+        self.either_wrap(rhs, i128::checked_add, |a, b| a + b, |a, b| Some(a.add(b)))
     }
 }
 
@@ -212,7 +641,7 @@ impl Sub for Num {
     type Output = Num;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        self.either_wrap(rhs, |a, b| a - b, |a, b| a - b)
+        self.either_wrap(rhs, i128::checked_sub, |a, b| a - b, |a, b| Some(a.sub(b)))
     }
 }
 
@@ -220,7 +649,7 @@ impl Mul for Num {
     type Output = Num;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        self.either_wrap(rhs, |a, b| a * b, |a, b| a * b)
+        self.either_wrap(rhs, i128::checked_mul, |a, b| a * b, |a, b| Some(a.mul(b)))
     }
 }
 
@@ -228,7 +657,7 @@ impl Div for Num {
     type Output = Num;
 
     fn div(self, rhs: Self) -> Self::Output {
-        self.either_wrap(rhs, |a, b| a / b, |a, b| a / b)
+        self.either_wrap(rhs, i128::checked_div, |a, b| a / b, |a, b| a.div(b))
     }
 }
 
@@ -236,7 +665,7 @@ impl Rem for Num {
     type Output = Num;
 
     fn rem(self, rhs: Self) -> Self::Output {
-        self.either_wrap(rhs, |a, b| a % b, |a, b| a % b)
+        self.either_wrap(rhs, i128::checked_rem, |a, b| a % b, |_, _| None)
     }
 }
 
@@ -248,35 +677,61 @@ impl Num {
     pub fn pow(self, rhs: Self) -> Num {
         self.either_wrap(
             rhs,
-            |a, b| {
-                if b >= 0 {
-                    a.pow(b as u32)
-                } else {
-                    todo!()
-                }
-            },
+            |a, b| a.checked_pow(u32::try_from(b).ok()?),
             |a, b| a.powf(b),
+            |a, b| a.pow(b),
         )
     }
 
-    fn either_wrap<F: Fn(i32, i32) -> i32, G: Fn(f64, f64) -> f64>(
+    /// Apply `f` to two integers, `g` to floats, or `h` to arbitrary
+    /// precision numbers at the larger precision, falling back to `g` if `f`
+    /// or `h` has no result
+    fn either_wrap<
+        F: Fn(i128, i128) -> Option<i128>,
+        G: Fn(f64, f64) -> f64,
+        H: Fn(&BigFloat, &BigFloat) -> Option<BigFloat>,
+    >(
         self,
         rhs: Self,
         f: F,
         g: G,
+        h: H,
     ) -> Num {
         match (self, rhs) {
-            (Num::Int(a), Num::Int(b)) => Self::Int(f(a, b)),
-            (Num::Int(a), Num::Flt(b)) => Self::Flt(g(a.into(), b)),
-            (Num::Flt(a), Num::Int(b)) => Self::Flt(g(a, b.into())),
+            (Num::Int(a), Num::Int(b)) => match f(a, b) {
+                Some(v) => Self::Int(v),
+                None => Self::Flt(g(a as f64, b as f64)),
+            },
+            (Num::Int(a), Num::Flt(b)) => Self::Flt(g(a as f64, b)),
+            (Num::Flt(a), Num::Int(b)) => Self::Flt(g(a, b as f64)),
             (Num::Flt(a), Num::Flt(b)) => Self::Flt(g(a, b)),
+            (a, b) => {
+                let prec = match (&a, &b) {
+                    (Num::Big(x), Num::Big(y)) => x.prec().max(y.prec()),
+                    (Num::Big(x), _) | (_, Num::Big(x)) => x.prec(),
+                    _ => unreachable!(),
+                };
+                let big = |n: &Num| match n {
+                    Num::Int(v) => Some(BigFloat::int(*v, prec)),
+                    Num::Flt(v) => BigFloat::from_f64(*v, prec),
+                    Num::Big(v) => Some(v.clone()),
+                };
+                match (big(&a), big(&b)) {
+                    (Some(x), Some(y)) => match h(&x, &y) {
+                        Some(v) => Self::Big(v),
+                        None => Self::Flt(g(a.flt(), b.flt())),
+                    },
+                    _ => Self::Flt(g(a.flt(), b.flt())),
+                }
+            }
         }
     }
 
-    pub fn flt(self) -> f64 {
+    pub fn flt(&self) -> f64 {
         match self {
-            Num::Int(v) => v.into(),
-            Num::Flt(v) => v,
+            Num::Int(v) => *v as f64,
+            Num::Flt(v) => *v,
+            Num::Big(v) => v.to_f64(),
         }
     }
 }
@@ -0,0 +1,827 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::{fmt, str::FromStr};
+
+use super::{ast::*, bigfloat::BigFloat, rational::Rat};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Version of the encoding, written next to every top-level value
+///
+/// - Every node is an object with one key naming its kind, such as
+///   `{"var":"x"}` or `{"infix":[a,{"op":"add"},b]}`
+/// - Functions are `{"fn":{"<name>":[args]}}`, the name and the number of
+///   arguments telling the variants apart
+/// - `f64` is written in its shortest round-trip form, `"NaN"`, `"inf"` and
+///   `"-inf"` as strings
+/// - [`BigFloat`] is its exact `mant e exp` text with its precision, [`Rat`]
+///   its numerator and denominator as strings, so neither is limited to the
+///   integers a JSON reader can hold
+pub const VERSION: u64 = 1;
+
+/// JSON document, numbers kept as their text
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Num(String),
+    Str(String),
+    Arr(Vec<Value>),
+    Obj(Vec<(String, Value)>),
+}
+
+/// Stable JSON encoding, `from_json(to_json(x)) == x`
+pub trait Json: Sized {
+    fn to_value(&self) -> Value;
+
+    fn from_value(v: &Value) -> Result<Self, String>;
+
+    /// `{"version":1,"value":...}`
+    fn to_json(&self) -> String {
+        Value::Obj(vec![
+            ("version".to_owned(), Value::Num(VERSION.to_string())),
+            ("value".to_owned(), self.to_value()),
+        ])
+        .to_string()
+    }
+
+    /// Value of a document written by [`to_json`](Self::to_json), an error
+    /// for any other [`VERSION`]
+    fn from_json(s: &str) -> Result<Self, String> {
+        let v = s.parse::<Value>()?;
+        match (v.get("version"), v.get("value")) {
+            (Some(Value::Num(n)), Some(v)) if *n == VERSION.to_string() => Self::from_value(v),
+            (Some(n), Some(_)) => Err(format!("unsupported version {}", n)),
+            _ => Err("missing version or value".to_owned()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Value {
+    fn tag(key: &str, v: Value) -> Value {
+        Value::Obj(vec![(key.to_owned(), v)])
+    }
+
+    fn str(s: &str) -> Value {
+        Value::Str(s.to_owned())
+    }
+
+    /// Field `key` of an object
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Obj(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Key and value of a one-key object
+    fn untag(&self) -> Result<(&str, &Value), String> {
+        match self {
+            Value::Obj(fields) if fields.len() == 1 => Ok((&fields[0].0, &fields[0].1)),
+            _ => Err(format!("expected a tagged object, got {}", self)),
+        }
+    }
+
+    fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Value::Str(s) => Ok(s),
+            _ => Err(format!("expected a string, got {}", self)),
+        }
+    }
+
+    fn as_arr(&self) -> Result<&[Value], String> {
+        match self {
+            Value::Arr(items) => Ok(items),
+            _ => Err(format!("expected an array, got {}", self)),
+        }
+    }
+
+    fn parse_num<T: FromStr>(&self) -> Result<T, String> {
+        match self {
+            Value::Num(n) => n.parse().map_err(|_| format!("bad number {}", n)),
+            _ => Err(format!("expected a number, got {}", self)),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Json for Ex {
+    fn to_value(&self) -> Value {
+        match self {
+            Ex::Val(v) => v.to_value(),
+            Ex::Infix(a, op, b) => Value::tag(
+                "infix",
+                Value::Arr(vec![a.to_value(), op.to_value(), b.to_value()]),
+            ),
+            Ex::Neg(a) => Value::tag("neg", a.to_value()),
+            Ex::Fn(f) => Value::tag("fn", f.to_value()),
+            Ex::Mat(rows) => Value::tag("mat", Value::Arr(rows.iter().map(Ex::to_value).collect())),
+            Ex::Invalid => Value::tag("invalid", Value::Null),
+        }
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        let (tag, x) = v.untag()?;
+        Ok(match tag {
+            "infix" => match x.as_arr()? {
+                [a, op, b] => Ex::Infix(
+                    Box::new(Ex::from_value(a)?),
+                    Bin::from_value(op)?,
+                    Box::new(Ex::from_value(b)?),
+                ),
+                _ => return Err(format!("infix needs 3 items, got {}", x)),
+            },
+            "neg" => Ex::Neg(Box::new(Ex::from_value(x)?)),
+            "fn" => Ex::Fn(Fnc::from_value(x)?),
+            "mat" => Ex::Mat(
+                x.as_arr()?
+                    .iter()
+                    .map(Ex::from_value)
+                    .collect::<Result<_, _>>()?,
+            ),
+            "invalid" => Ex::Invalid,
+            _ => Ex::Val(Val::from_value(v)?),
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Json for Val {
+    fn to_value(&self) -> Value {
+        match self {
+            Val::Num(n) => n.to_value(),
+            Val::Var(x) => Value::tag("var", Value::str(x)),
+            Val::Bool(b) => Value::tag("bool", Value::Bool(*b)),
+            Val::Tok(t) => Value::tag("tok", t.to_value()),
+        }
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        let (tag, x) = v.untag()?;
+        Ok(match (tag, x) {
+            ("var", x) => Val::Var(x.as_str()?.to_owned()),
+            ("bool", Value::Bool(b)) => Val::Bool(*b),
+            ("tok", x) => Val::Tok(Tok::from_value(x)?),
+            _ => Val::Num(Num::from_value(v)?),
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Json for Num {
+    fn to_value(&self) -> Value {
+        match self {
+            Num::Int(n) => Value::tag("int", Value::Num(n.to_string())),
+            Num::Flt(x) if x.is_nan() => Value::tag("flt", Value::str("NaN")),
+            Num::Flt(x) if x.is_infinite() => {
+                Value::tag("flt", Value::str(if *x > 0.0 { "inf" } else { "-inf" }))
+            }
+            Num::Flt(x) => Value::tag("flt", Value::Num(format!("{:?}", x))),
+            Num::Big(b) => Value::tag("big", b.to_value()),
+        }
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        let (tag, x) = v.untag()?;
+        Ok(match (tag, x) {
+            ("int", x) => Num::Int(x.parse_num()?),
+            ("flt", Value::Str(s)) => match s.as_str() {
+                "NaN" => Num::Flt(f64::NAN),
+                "inf" => Num::Flt(f64::INFINITY),
+                "-inf" => Num::Flt(f64::NEG_INFINITY),
+                _ => return Err(format!("bad float {:?}", s)),
+            },
+            ("flt", x) => Num::Flt(x.parse_num()?),
+            ("big", x) => Num::Big(BigFloat::from_value(x)?),
+            _ => return Err(format!("unknown node {}", v)),
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Json for BigFloat {
+    fn to_value(&self) -> Value {
+        Value::Obj(vec![
+            ("digits".to_owned(), Value::Str(self.to_exact())),
+            ("prec".to_owned(), Value::Num(self.prec().to_string())),
+        ])
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        let (Some(digits), Some(prec)) = (v.get("digits"), v.get("prec")) else {
+            return Err(format!("expected digits and prec, got {}", v));
+        };
+        let digits = digits.as_str()?;
+        let prec = prec.parse_num::<usize>()?;
+        let b = digits
+            .parse::<BigFloat>()
+            .map_err(|_| format!("bad digits {:?}", digits))?;
+        Ok(b.with_prec(prec))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Json for Rat {
+    /// `{"rat":["n","d"]}`
+    fn to_value(&self) -> Value {
+        let (n, d) = (self.numer().to_string(), self.denom().to_string());
+        Value::tag("rat", Value::Arr(vec![Value::Str(n), Value::Str(d)]))
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        let (tag, x) = v.untag()?;
+        let int = |s: &Value| {
+            let s = s.as_str()?;
+            s.parse::<i128>()
+                .map_err(|_| format!("bad integer {:?}", s))
+        };
+        match (tag, x.as_arr()?) {
+            ("rat", [n, d]) => match (int(n)?, int(d)?) {
+                (_, 0) => Err("zero denominator".to_owned()),
+                (n, d) => Ok(Rat::new(n, d)),
+            },
+            _ => Err(format!("unknown node {}", v)),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+const TOKS: &[(Tok, &str)] = &[
+    (Tok::E, "e"),
+    (Tok::Pi, "pi"),
+    (Tok::I, "i"),
+    (Tok::Inf, "inf"),
+];
+
+const OPS: &[(Op, &str)] = &[
+    (Op::Add, "add"),
+    (Op::Sub, "sub"),
+    (Op::Mul, "mul"),
+    (Op::Div, "div"),
+    (Op::Pow, "pow"),
+    (Op::Mod, "mod"),
+];
+
+const RLS: &[(Rl, &str)] = &[
+    (Rl::Eqq, "eq"),
+    (Rl::Ltt, "lt"),
+    (Rl::Leq, "le"),
+    (Rl::Gtt, "gt"),
+    (Rl::Geq, "ge"),
+    (Rl::Neq, "ne"),
+    (Rl::Equiv, "equiv"),
+    (Rl::Cong, "cong"),
+    (Rl::Sim, "sim"),
+    (Rl::SimEq, "simeq"),
+    (Rl::Approx, "approx"),
+    (Rl::Asymp, "asymp"),
+    (Rl::Prop, "prop"),
+    (Rl::NEquiv, "nequiv"),
+    (Rl::NCong, "ncong"),
+    (Rl::NSim, "nsim"),
+    (Rl::NApprox, "napprox"),
+    (Rl::NLtt, "nlt"),
+    (Rl::NLeq, "nle"),
+    (Rl::NGtt, "ngt"),
+    (Rl::NGeq, "nge"),
+    (Rl::In, "in"),
+    (Rl::NotIn, "notin"),
+    (Rl::Sub, "sub"),
+    (Rl::SubEq, "subeq"),
+    (Rl::Sup, "sup"),
+    (Rl::SupEq, "supeq"),
+    (Rl::NSub, "nsub"),
+    (Rl::NSubEq, "nsubeq"),
+    (Rl::If, "if"),
+    (Rl::To, "to"),
+    (Rl::Implies, "implies"),
+    (Rl::MapsTo, "mapsto"),
+    (Rl::Iff, "iff"),
+    (Rl::Or, "or"),
+    (Rl::Where, "where"),
+];
+
+fn name<T: PartialEq>(table: &[(T, &'static str)], x: &T) -> &'static str {
+    table.iter().find(|(y, _)| y == x).unwrap().1
+}
+
+fn lookup<T: Clone>(table: &[(T, &str)], s: &str) -> Result<T, String> {
+    match table.iter().find(|(_, n)| *n == s) {
+        Some((x, _)) => Ok(x.clone()),
+        None => Err(format!("unknown name {:?}", s)),
+    }
+}
+
+impl Json for Tok {
+    fn to_value(&self) -> Value {
+        Value::str(name(TOKS, self))
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        lookup(TOKS, v.as_str()?)
+    }
+}
+
+impl Json for Bin {
+    /// `{"op":"add"}` or `{"rel":"approx"}`
+    fn to_value(&self) -> Value {
+        match self {
+            Bin::Op(o) => Value::tag("op", Value::str(name(OPS, o))),
+            Bin::Rl(r) => Value::tag("rel", Value::str(name(RLS, r))),
+        }
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        match v.untag()? {
+            ("op", x) => Ok(Bin::Op(lookup(OPS, x.as_str()?)?)),
+            ("rel", x) => Ok(Bin::Rl(lookup(RLS, x.as_str()?)?)),
+            _ => Err(format!("unknown operator {}", v)),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Json for Fnc {
+    /// `{"<name>":[args]}`
+    fn to_value(&self) -> Value {
+        let args = self.args().into_iter().map(Ex::to_value).collect();
+        Value::tag(self.name(), Value::Arr(args))
+    }
+
+    fn from_value(v: &Value) -> Result<Self, String> {
+        let (name, args) = v.untag()?;
+        let args = args
+            .as_arr()?
+            .iter()
+            .map(|a| Ex::from_value(a).map(Box::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = args.len();
+        let mut a = args.into_iter();
+        let mut x = || a.next().unwrap();
+        Ok(match (name, n) {
+            ("ln", 1) => Fnc::Ln(x()),
+            ("sin", 1) => Fnc::Sin(x()),
+            ("cos", 1) => Fnc::Cos(x()),
+            ("tan", 1) => Fnc::Tan(x()),
+            ("atan", 1) => Fnc::Atan(x()),
+            ("abs", 1) => Fnc::Abs(x()),
+            ("floor", 1) => Fnc::Floor(x()),
+            ("ceil", 1) => Fnc::Ceil(x()),
+            ("round", 1) => Fnc::Round(x()),
+            ("frac", 1) => Fnc::Frac(x()),
+            ("sign", 1) => Fnc::Sign(x()),
+            ("norm", 1) => Fnc::Norm(x()),
+            ("d", 2) => Fnc::D(x(), x()),
+            ("int", 2) => Fnc::Int(x(), x()),
+            ("int", 4) => Fnc::DefInt(x(), x(), x(), x()),
+            ("nInt", 4) => Fnc::NInt(x(), x(), x(), x()),
+            ("solve", 2) => Fnc::Solve(x(), x()),
+            ("cSolve", 2) => Fnc::CSolve(x(), x()),
+            ("nSolve", 3) => Fnc::NSolve(x(), x(), x()),
+            ("zeros", 4) => Fnc::Zeros(x(), x(), x(), x()),
+            ("factor", 1) => Fnc::Factor(x()),
+            ("expand", 1) => Fnc::Expand(x()),
+            ("expand", 2) => Fnc::ExpandWith(x(), x()),
+            ("tExpand", 1) => Fnc::TExpand(x()),
+            ("tCollect", 1) => Fnc::TCollect(x()),
+            ("approx", 1) => Fnc::Approx(x()),
+            ("approx", 2) => Fnc::ApproxTo(x(), x()),
+            ("quo", 3) => Fnc::Quo(x(), x(), x()),
+            ("rem", 3) => Fnc::Rem(x(), x(), x()),
+            ("propFrac", 2) => Fnc::PropFrac(x(), x()),
+            ("gcd", 2) => Fnc::Gcd(x(), x()),
+            ("lcm", 2) => Fnc::Lcm(x(), x()),
+            ("lim", 3) => Fnc::Lim(x(), x(), x()),
+            ("lim", 4) => Fnc::LimSide(x(), x(), x(), x()),
+            ("taylor", 4) => Fnc::Taylor(x(), x(), x(), x()),
+            ("sum", 4) => Fnc::Sum(x(), x(), x(), x()),
+            ("prod", 4) => Fnc::Prod(x(), x(), x(), x()),
+            ("deSolve", 3) => Fnc::DeSolve(x(), x(), x()),
+            ("deSolve", 4) => Fnc::DeSolveIvp(x(), x(), x(), x()),
+            ("nDeSolve", 5) => Fnc::NDeSolve(x(), x(), x(), x(), x()),
+            _ => return Err(format!("unknown function {} with {} arguments", name, n)),
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl fmt::Display for Value {
+    /// Compact JSON without whitespace
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Num(n) => f.write_str(n),
+            Value::Str(s) => write_str(f, s),
+            Value::Arr(items) => {
+                f.write_str("[")?;
+                for (i, v) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            Value::Obj(fields) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_str(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl FromStr for Value {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = Reader { s, i: 0 };
+        let v = r.value()?;
+        r.ws();
+        match r.peek() {
+            None => Ok(v),
+            Some(c) => Err(format!("trailing {:?} at {}", c, r.i)),
+        }
+    }
+}
+
+struct Reader<'a> {
+    s: &'a str,
+    i: usize,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.i..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.i += c.len_utf8();
+        Some(c)
+    }
+
+    fn ws(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.i += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> Result<(), String> {
+        self.ws();
+        match self.next() {
+            Some(d) if d == c => Ok(()),
+            d => Err(format!("expected {:?} at {}, got {:?}", c, self.i, d)),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, v: Value) -> Result<Value, String> {
+        match self.s[self.i..].strip_prefix(word) {
+            Some(_) => {
+                self.i += word.len();
+                Ok(v)
+            }
+            None => Err(format!("unexpected input at {}", self.i)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.ws();
+        match self.peek() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => Ok(Value::Str(self.string()?)),
+            Some('[') => self.list(']', Reader::value).map(Value::Arr),
+            Some('{') => self.list('}', Reader::field).map(Value::Obj),
+            Some('-' | '0'..='9') => self.number(),
+            c => Err(format!("unexpected {:?} at {}", c, self.i)),
+        }
+    }
+
+    /// Items separated by commas up to `end`, after the opening bracket
+    fn list<T>(
+        &mut self,
+        end: char,
+        item: fn(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        self.i += 1;
+        let mut items = vec![];
+        self.ws();
+        if self.peek() == Some(end) {
+            self.i += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            self.ws();
+            match self.next() {
+                Some(',') => {}
+                Some(c) if c == end => return Ok(items),
+                c => {
+                    return Err(format!(
+                        "expected ',' or {:?} at {}, got {:?}",
+                        end, self.i, c
+                    ))
+                }
+            }
+        }
+    }
+
+    fn field(&mut self) -> Result<(String, Value), String> {
+        self.ws();
+        if self.peek() != Some('"') {
+            return Err(format!("expected a key at {}", self.i));
+        }
+        let k = self.string()?;
+        self.eat(':')?;
+        Ok((k, self.value()?))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.i;
+        let digits = |r: &mut Self| {
+            let from = r.i;
+            while let Some('0'..='9') = r.peek() {
+                r.i += 1;
+            }
+            r.i > from
+        };
+        if self.peek() == Some('-') {
+            self.i += 1;
+        }
+        let mut ok = digits(self);
+        if self.peek() == Some('.') {
+            self.i += 1;
+            ok &= digits(self);
+        }
+        if let Some('e' | 'E') = self.peek() {
+            self.i += 1;
+            if let Some('+' | '-') = self.peek() {
+                self.i += 1;
+            }
+            ok &= digits(self);
+        }
+        match ok {
+            true => Ok(Value::Num(self.s[start..self.i].to_owned())),
+            false => Err(format!("bad number at {}", start)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.i += 1;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.next() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => out.push(self.unicode()?),
+                    c => return Err(format!("bad escape {:?} at {}", c, self.i)),
+                },
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(format!("control character in string at {}", self.i))
+                }
+                Some(c) => out.push(c),
+                None => return Err("unterminated string".to_owned()),
+            }
+        }
+    }
+
+    /// Code point of `\uXXXX`, joining a surrogate pair
+    fn unicode(&mut self) -> Result<char, String> {
+        let hi = self.hex()?;
+        let c = match hi {
+            0xd800..=0xdbff => {
+                if !self.s[self.i..].starts_with("\\u") {
+                    return Err(format!("unpaired surrogate at {}", self.i));
+                }
+                self.i += 2;
+                let lo = self.hex()?;
+                if !(0xdc00..=0xdfff).contains(&lo) {
+                    return Err(format!("unpaired surrogate at {}", self.i));
+                }
+                0x10000 + ((hi - 0xd800) << 10) + (lo - 0xdc00)
+            }
+            _ => hi,
+        };
+        char::from_u32(c).ok_or_else(|| format!("bad code point at {}", self.i))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let h = self.s.get(self.i..self.i + 4).unwrap_or("");
+        let v = match h.len() == 4 && h.bytes().all(|b| b.is_ascii_hexdigit()) {
+            true => u32::from_str_radix(h, 16).unwrap(),
+            false => return Err(format!("bad \\u escape at {}", self.i)),
+        };
+        self.i += 4;
+        Ok(v)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::parse;
+
+    fn round_trip<T: Json + PartialEq + fmt::Debug>(x: &T) {
+        let s = x.to_json();
+        assert_eq!(T::from_json(&s).as_ref(), Ok(x), "{}", s);
+    }
+
+    #[test]
+    fn ex3_json_format() {
+        assert_eq!(
+            parse("x^2 + 1").to_json(),
+            concat!(
+                r#"{"version":1,"value":{"infix":[{"infix":[{"var":"x"},{"op":"pow"},"#,
+                r#"{"int":2}]},{"op":"add"},{"int":1}]}}"#
+            )
+        );
+        assert_eq!(
+            parse("approx(-pi, 3)").to_value().to_string(),
+            r#"{"fn":{"approx":[{"neg":{"tok":"pi"}},{"int":3}]}}"#
+        );
+        assert_eq!(
+            Ex::from(Num::Flt(0.1)).to_value().to_string(),
+            r#"{"flt":0.1}"#
+        );
+        let big = "-0.00125".parse::<BigFloat>().unwrap().with_prec(40);
+        assert_eq!(
+            Ex::from(Num::Big(big)).to_value().to_string(),
+            r#"{"big":{"digits":"-125e-5","prec":40}}"#
+        );
+        assert_eq!(
+            Rat::new(-3, 12).to_value().to_string(),
+            r#"{"rat":["-1","4"]}"#
+        );
+    }
+
+    #[test]
+    fn ex3_json_round_trip() {
+        for s in [
+            "x^2 + 3*x - 1/2",
+            "-(a - b) % 7",
+            "[[1, 2], [3, 4]]",
+            "e^(i*pi) + inf",
+            "x ≈ 3.14 or y ∝ x ⇒ true",
+            "a ≤ b ≠ c ∉ A ⊈ B | x = 1",
+            "x ↦ x^2 <=> false",
+            "ln(x) + sin(x) + cos(x) + tan(x) + atan(x)",
+            "abs(x) + floor(x) + ceil(x) + round(x) + frac(x) + sign(x) + norm(x)",
+            "d(x^2, x) + int(x, x) + int(x, x, 0, 1) + nInt(x, x, 0, 1)",
+            "solve(x = 1, x) + cSolve(x = 1, x) + nSolve(x = 1, x, 0) + zeros(x, x, 0, 1)",
+            "factor(x) + expand(x) + expand(x, y) + tExpand(x) + tCollect(x)",
+            "approx(x) + approx(x, 50) + quo(x, y, x) + rem(x, y, x) + propFrac(x, y)",
+            "gcd(x, y) + lcm(x, y) + lim(f, x, 0) + lim(f, x, 0, 1)",
+            "taylor(f, x, 0, 3) + sum(k, k, 1, n) + prod(k, k, 1, n)",
+            "deSolve(y' = y, x, y) + deSolve(y' = y, x, y, 1)",
+            "nDeSolve(y' = y, x, y, 0, 1)",
+            "θ_1'' + 1.5",
+        ] {
+            round_trip(&parse(s));
+        }
+
+        for x in [
+            0.1,
+            -0.0,
+            1e300,
+            f64::MIN_POSITIVE,
+            f64::INFINITY,
+            -f64::INFINITY,
+        ] {
+            let e = Ex::from(Num::Flt(x));
+            round_trip(&e);
+            let Ok(Ex::Val(Val::Num(Num::Flt(y)))) = Ex::from_json(&e.to_json()) else {
+                panic!("{}", x);
+            };
+            assert_eq!(x.to_bits(), y.to_bits());
+        }
+        let nan = Ex::from_json(&Ex::from(Num::Flt(f64::NAN)).to_json());
+        assert!(matches!(nan, Ok(Ex::Val(Val::Num(Num::Flt(x)))) if x.is_nan()));
+
+        let pi = BigFloat::pi(60);
+        let Ok(Ex::Val(Val::Num(Num::Big(b)))) =
+            Ex::from_json(&Ex::from(Num::Big(pi.clone())).to_json())
+        else {
+            panic!();
+        };
+        assert_eq!((&b, b.prec()), (&pi, pi.prec()));
+        round_trip(&Ex::from(Num::Big(BigFloat::int(-10i128.pow(30), 5))));
+        round_trip(&Ex::from(Num::Big(BigFloat::zero(20))));
+
+        round_trip(&Ex::Invalid);
+        round_trip(&Ex::Mat(vec![]));
+        round_trip(&Ex::from("a \"quoted\"\\name\n\u{1}"));
+        round_trip(&Rat::new(i128::MAX, 7));
+        round_trip(&Rat::new(-1, i128::MAX));
+        round_trip(&Num::Int(i128::MIN));
+        round_trip(&Num::Int(3000000000));
+        round_trip(&Tok::Inf);
+        round_trip(&Bin::Rl(Rl::Where));
+    }
+
+    #[test]
+    fn ex3_json_errors() {
+        assert!(Ex::from_json(r#"{"version":2,"value":{"var":"x"}}"#)
+            .unwrap_err()
+            .contains("version"));
+        assert!(Ex::from_json(r#"{"value":{"var":"x"}}"#).is_err());
+        assert!(Ex::from_json(r#"{"version":1,"value":{"var":"x"}} x"#).is_err());
+        assert!(Ex::from_json(r#"{"version":1,"value":{"int":3000000000}}"#).is_ok());
+        assert!(Ex::from_json(
+            r#"{"version":1,"value":{"int":170141183460469231731687303715884105728}}"#
+        )
+        .is_err());
+        assert!(Ex::from_json(r#"{"version":1,"value":{"fn":{"ln":[]}}}"#).is_err());
+        assert!(Ex::from_json(r#"{"version":1,"value":{"fn":{"lim":[{"var":"x"}]}}}"#).is_err());
+        assert!(Ex::from_json(
+            r#"{"version":1,"value":{"infix":[{"int":1},{"op":"xor"},{"int":2}]}}"#
+        )
+        .is_err());
+        assert!(Rat::from_json(r#"{"version":1,"value":{"rat":["1","0"]}}"#).is_err());
+        assert!(Ex::from_json(r#"{"version":1,"value":{"flt":"1.5"}}"#).is_err());
+
+        assert_eq!(
+            Ex::from_json(" {\"value\" : {\"var\":\"\\u03b8\\ud83d\\ude00\"}, \"version\" : 1 } "),
+            Ok(Ex::from("θ😀"))
+        );
+        for bad in [
+            "",
+            "{",
+            "[1,]",
+            "01x",
+            "-",
+            "1.",
+            "\"\\x\"",
+            "\"\\ud800\"",
+            "tru",
+            "{1:2}",
+        ] {
+            assert!(bad.parse::<Value>().is_err(), "{}", bad);
+        }
+        assert_eq!("[]".parse::<Value>(), Ok(Value::Arr(vec![])));
+        assert_eq!(
+            "-1.5E+3".parse::<Value>(),
+            Ok(Value::Num("-1.5E+3".to_owned()))
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,551 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, helper::*, piecewise, poly::UPoly, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Deepest nesting of L'Hôpital steps and rewrites of indeterminate forms
+const MAX_DEPTH: usize = 6;
+
+/// Distances from a finite point at which one-sided signs are sampled
+const OFFSETS: [f64; 3] = [1e-3, 1e-6, 1e-9];
+
+/// Magnitudes at which signs towards infinity are sampled
+const FAR: [f64; 3] = [1e3, 1e6, 1e9];
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Direction from which a point is approached
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Both,
+    Left,
+    Right,
+}
+
+/// Limit value, also used for the point approached
+#[derive(Clone, Debug, PartialEq)]
+enum Lim {
+    Fin(Ex),
+    Pos,
+    Neg,
+    Undef,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Limit as `x` approaches `a` from `side`
+    ///
+    /// - `a` may be `inf` or `-inf`, which are approached from within
+    /// - Tries direct substitution, then cancellation for rational functions,
+    ///   then limits of the parts: `0/0`, `0*inf` and `inf/inf` by ranking
+    ///   the growth of exponentials over powers over logarithms towards
+    ///   infinity, otherwise by L'Hôpital's rule, and dominant-term comparison
+    ///   for `inf-inf`
+    /// - Returns `undef` if the limit does not exist, `None` if it could not
+    ///   be found
+    pub fn limit(&self, x: &str, a: &Ex, side: Side) -> Option<Ex> {
+        if let Ex::Mat(xs) = self {
+            return xs
+                .iter()
+                .map(|e| e.limit(x, a, side))
+                .collect::<Option<_>>()
+                .map(Ex::Mat);
+        }
+        let f = self.simplified();
+        let a = a.simplified();
+        let at = |pt: Lim, side: Side| At { x, pt, side };
+        let lim = match a.flt(&[]) {
+            Some(v) if v == f64::INFINITY => at(Lim::Pos, Side::Left).approach(&f, 0)?,
+            Some(v) if v == f64::NEG_INFINITY => at(Lim::Neg, Side::Right).approach(&f, 0)?,
+            _ if side == Side::Both => {
+                let l = at(Lim::Fin(a.clone()), Side::Left).approach(&f, 0)?;
+                let r = at(Lim::Fin(a), Side::Right).approach(&f, 0)?;
+                match same(&l, &r) {
+                    true => l,
+                    false => Lim::Undef,
+                }
+            }
+            _ => at(Lim::Fin(a), side).approach(&f, 0)?,
+        };
+        Some(match lim {
+            Lim::Fin(v) => v,
+            Lim::Pos => Ex::from(Tok::Inf),
+            Lim::Neg => -Ex::from(Tok::Inf),
+            Lim::Undef => Ex::Invalid,
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// One-sided approach of `x` to `pt`
+struct At<'a> {
+    x: &'a str,
+    pt: Lim,
+    side: Side,
+}
+
+impl At<'_> {
+    /// Limit of a simplified expression
+    fn approach(&self, f: &Ex, depth: usize) -> Option<Lim> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        if !f.has(self.x) {
+            return Some(Lim::Fin(f.clone()));
+        }
+        if let Lim::Fin(a) = &self.pt {
+            let v = f.subs(self.x, a).simplified();
+            if defined(&v) && !on_jump(f, self.x, a) {
+                return Some(Lim::Fin(v));
+            }
+        }
+        if let Some(l) = self.rational(f) {
+            return Some(l);
+        }
+
+        let s = Sum::of(f);
+        if s.terms.len() > 1 || !s.constant.is_zero() {
+            let mut parts = vec![s.constant.to_ex()];
+            parts.extend(
+                s.terms
+                    .iter()
+                    .map(|(c, t)| product(&[c.to_ex(), t.clone()])),
+            );
+            return self.sum(&parts, depth);
+        }
+        let p = Product::of(f);
+        match p.factors.as_slice() {
+            _ if p.undefined => None,
+            [(b, k)] if p.coef.is_one() && *k == Ex::from(1) => self.atom(b, depth),
+            _ => self.product(&p, depth),
+        }
+    }
+
+    /// Limit of a variable or function application
+    fn atom(&self, f: &Ex, depth: usize) -> Option<Lim> {
+        let Ex::Fn(fnc) = f else {
+            return (f.var() == Some(self.x)).then(|| self.pt.clone());
+        };
+        let arg = match fnc {
+            Fnc::Ln(a) | Fnc::Sin(a) | Fnc::Cos(a) | Fnc::Tan(a) | Fnc::Atan(a) => a,
+            Fnc::Abs(a)
+            | Fnc::Floor(a)
+            | Fnc::Ceil(a)
+            | Fnc::Round(a)
+            | Fnc::Frac(a)
+            | Fnc::Sign(a) => a,
+            _ => return None,
+        };
+        let l = self.approach(arg, depth)?;
+        Some(match (fnc, l) {
+            (_, Lim::Undef) => Lim::Undef,
+            (Fnc::Ln(_), Lim::Fin(v)) if is_zero(&v) => match self.sign(arg)? > 0.0 {
+                true => Lim::Neg,
+                false => Lim::Undef,
+            },
+            (Fnc::Ln(_), Lim::Pos) => Lim::Pos,
+            (Fnc::Ln(_), Lim::Neg) => Lim::Undef,
+            (Fnc::Sin(_) | Fnc::Cos(_) | Fnc::Tan(_), Lim::Pos | Lim::Neg) => Lim::Undef,
+            (Fnc::Tan(_), Lim::Fin(v)) if cos(v.clone()).flt(&[])?.abs() < 1e-12 => {
+                self.infinite(f)?
+            }
+            (Fnc::Atan(_), Lim::Pos) => {
+                Lim::Fin(product(&[Rat::new(1, 2).to_ex(), Tok::Pi.into()]))
+            }
+            (Fnc::Atan(_), Lim::Neg) => {
+                Lim::Fin(product(&[Rat::new(-1, 2).to_ex(), Tok::Pi.into()]))
+            }
+            (Fnc::Abs(_), Lim::Pos | Lim::Neg) => Lim::Pos,
+            (Fnc::Floor(_) | Fnc::Ceil(_) | Fnc::Round(_), l @ (Lim::Pos | Lim::Neg)) => l,
+            (Fnc::Sign(_), Lim::Pos) => Lim::Fin(Ex::from(1)),
+            (Fnc::Sign(_), Lim::Neg) => Lim::Fin(Ex::from(-1)),
+            (Fnc::Frac(_), Lim::Pos | Lim::Neg) => Lim::Undef,
+            (_, Lim::Fin(v)) if v.flt(&[]).is_some_and(|w| piecewise::jumps(fnc, w)) => {
+                let offset = sum(&[(**arg).clone(), product(&[Ex::from(-1), v.clone()])]);
+                Lim::Fin(piecewise::side(fnc, &v, self.sign(&offset)? > 0.0))
+            }
+            (_, Lim::Fin(v)) => Lim::Fin(Ex::from(fnc.map(|_| v.clone())).simplified()),
+            _ => return None,
+        })
+    }
+
+    /// Limit of `b^e`
+    fn power(&self, b: &Ex, e: &Ex, depth: usize) -> Option<Lim> {
+        if *e == Ex::from(1) {
+            return self.approach(b, depth);
+        }
+        let whole = power(b.clone(), e.clone());
+        Some(match (self.approach(b, depth)?, self.approach(e, depth)?) {
+            (Lim::Undef, _) | (_, Lim::Undef) => Lim::Undef,
+            (Lim::Fin(v), Lim::Fin(k)) if is_zero(&v) => match k.flt(&[])? {
+                k if k > 0.0 => Lim::Fin(Ex::from(0)),
+                k if k < 0.0 => self.infinite(&whole)?,
+                _ => self.exp_ln(b, e, depth)?,
+            },
+            (Lim::Fin(v), Lim::Fin(k)) => Lim::Fin(power(v, k)),
+            (Lim::Fin(v), k) => match v.flt(&[])? {
+                v if v < 0.0 => return None,
+                1.0 => self.exp_ln(b, e, depth)?,
+                v if v == 0.0 && k == Lim::Neg => self.infinite(&whole)?,
+                v if (v > 1.0) == (k == Lim::Pos) => Lim::Pos,
+                _ => Lim::Fin(Ex::from(0)),
+            },
+            (lb, Lim::Fin(k)) => match k.flt(&[])? {
+                0.0 => self.exp_ln(b, e, depth)?,
+                k if k < 0.0 => Lim::Fin(Ex::from(0)),
+                _ if lb == Lim::Pos => Lim::Pos,
+                _ => self.infinite(&whole)?,
+            },
+            (Lim::Pos, Lim::Pos) => Lim::Pos,
+            (Lim::Pos, Lim::Neg) => Lim::Fin(Ex::from(0)),
+            _ => return None,
+        })
+    }
+
+    /// Limit of `b^e` as `e^(e*ln(b))`, for `0^0`, `1^inf` and `inf^0`
+    fn exp_ln(&self, b: &Ex, e: &Ex, depth: usize) -> Option<Lim> {
+        let g = product(&[e.clone(), ln(b.clone())]);
+        Some(match self.approach(&g, depth + 1)? {
+            Lim::Fin(v) => Lim::Fin(power(Tok::E.into(), v)),
+            Lim::Pos => Lim::Pos,
+            Lim::Neg => Lim::Fin(Ex::from(0)),
+            Lim::Undef => Lim::Undef,
+        })
+    }
+
+    /// Limit of a product, resolving `0*inf` by growth classes or L'Hôpital's
+    /// rule
+    fn product(&self, p: &Product, depth: usize) -> Option<Lim> {
+        let (mut zeros, mut infs, mut rest) = (vec![], vec![], vec![p.coef.to_ex()]);
+        let mut parts = vec![];
+        let mut sign = 1.0;
+        for (b, e) in &p.factors {
+            let whole = power(b.clone(), e.clone());
+            match self.power(b, e, depth)? {
+                Lim::Undef => return Some(Lim::Undef),
+                Lim::Fin(v) if is_zero(&v) => zeros.push(whole),
+                Lim::Fin(v) => {
+                    rest.push(v);
+                    continue;
+                }
+                Lim::Pos => infs.push(whole),
+                Lim::Neg => {
+                    sign = -sign;
+                    infs.push(whole);
+                }
+            }
+            parts.push((b, e));
+        }
+        let rest = product(&rest);
+        match (zeros.is_empty(), infs.is_empty()) {
+            (_, true) if !zeros.is_empty() => Some(Lim::Fin(Ex::from(0))),
+            (_, true) => Some(Lim::Fin(rest)),
+            (true, false) => Some(match sign * rest.flt(&[])?.signum() > 0.0 {
+                true => Lim::Pos,
+                false => Lim::Neg,
+            }),
+            (false, false) => {
+                let (z, i) = (product(&zeros), product(&infs));
+                let inv = |e: Ex| power(e, Ex::from(-1));
+                let l = self
+                    .dominant(&parts, depth)
+                    .or_else(|| self.lhopital(&z, &inv(i.clone()), depth))
+                    .or_else(|| self.lhopital(&i, &inv(z), depth))?;
+                Some(match l {
+                    Lim::Fin(v) => Lim::Fin(product(&[rest, v])),
+                    Lim::Undef => Lim::Undef,
+                    l => {
+                        let neg = (l == Lim::Neg) != (rest.flt(&[])? < 0.0);
+                        if neg {
+                            Lim::Neg
+                        } else {
+                            Lim::Pos
+                        }
+                    }
+                })
+            }
+        }
+    }
+
+    /// Limit of a product of factors `b^e` that go to zero or infinity, by
+    /// ranking exponentials over powers over logarithms towards infinity
+    ///
+    /// - The exponents of the exponentials are added up first, then the
+    ///   degrees of the powers, then those of the logarithms, and the first
+    ///   total that does not cancel decides
+    /// - `None` if a factor is of none of these kinds or everything cancels
+    fn dominant(&self, parts: &[(&Ex, &Ex)], depth: usize) -> Option<Lim> {
+        if !matches!(self.pt, Lim::Pos | Lim::Neg) {
+            return None;
+        }
+        let (mut exps, mut pows, mut lns) = (vec![], Rat::ZERO, Rat::ZERO);
+        let mut neg = false;
+        for (b, e) in parts {
+            if !b.has(self.x) {
+                // c^g = e^(g*ln(c))
+                b.flt(&[]).filter(|c| *c > 0.0)?;
+                exps.push(product(&[(*e).clone(), ln((*b).clone())]));
+                continue;
+            }
+            let k = e.as_rat()?;
+            match (b, self.approach(b, depth + 1)?) {
+                (Ex::Fn(Fnc::Ln(u)), Lim::Pos) if UPoly::from_ex(u, self.x).is_some() => {
+                    lns = lns.checked_add(k)?
+                }
+                (_, l @ (Lim::Pos | Lim::Neg)) => {
+                    let d = UPoly::from_ex(b, self.x)?.deg();
+                    pows = pows.checked_add(k.checked_mul(Rat::int(d as i128))?)?;
+                    // a base going to -inf only has a sign under integer powers
+                    if l == Lim::Neg {
+                        k.is_int().then_some(())?;
+                        neg ^= k.numer().rem_euclid(2) == 1;
+                    }
+                }
+                _ => return None,
+            }
+        }
+        let grow = |r: Rat| match r.is_neg() {
+            true => Some(Lim::Fin(Ex::from(0))),
+            false => Some(Lim::Pos),
+        };
+        let l = match self.approach(&sum(&exps).simplified(), depth + 1)? {
+            Lim::Undef => return None,
+            Lim::Neg => Lim::Fin(Ex::from(0)),
+            Lim::Pos => Lim::Pos,
+            Lim::Fin(_) if !pows.is_zero() => grow(pows)?,
+            Lim::Fin(_) if !lns.is_zero() => grow(lns)?,
+            Lim::Fin(_) => return None,
+        };
+        Some(match l {
+            Lim::Pos if neg => Lim::Neg,
+            l => l,
+        })
+    }
+
+    /// Limit of `n/d` where both go to zero or both to infinity
+    fn lhopital(&self, n: &Ex, d: &Ex, depth: usize) -> Option<Lim> {
+        let (dn, dd) = (n.derivative(self.x), d.derivative(self.x));
+        self.approach(&product(&[dn, power(dd, Ex::from(-1))]), depth + 1)
+    }
+
+    /// Limit of a sum, comparing the dominant terms of `inf-inf`
+    fn sum(&self, parts: &[Ex], depth: usize) -> Option<Lim> {
+        let (mut fin, mut pos, mut neg) = (vec![], vec![], vec![]);
+        for e in parts {
+            match self.approach(e, depth)? {
+                Lim::Undef => return Some(Lim::Undef),
+                Lim::Fin(v) => fin.push(v),
+                Lim::Pos => pos.push(e.clone()),
+                Lim::Neg => neg.push(e.clone()),
+            }
+        }
+        Some(match (pos.is_empty(), neg.is_empty()) {
+            (true, true) => Lim::Fin(sum(&fin)),
+            (false, true) => Lim::Pos,
+            (true, false) => Lim::Neg,
+            (false, false) => {
+                let ratio = product(&[sum(&neg), power(sum(&pos), Ex::from(-1))]);
+                match self.approach(&ratio, depth + 1)? {
+                    Lim::Fin(r) => match 1.0 + r.flt(&[])? {
+                        s if s > 0.0 => Lim::Pos,
+                        s if s < 0.0 => Lim::Neg,
+                        _ => return None,
+                    },
+                    Lim::Pos | Lim::Neg => Lim::Neg,
+                    Lim::Undef => return None,
+                }
+            }
+        })
+    }
+
+    /// Exact limit of a rational function with rational coefficients
+    fn rational(&self, f: &Ex) -> Option<Lim> {
+        let (mut n, mut d) = UPoly::ratfn_from_ex(f, self.x)?;
+        let signed = |r: Rat, odd: bool| match r.is_neg() != odd {
+            true => Lim::Neg,
+            false => Lim::Pos,
+        };
+        match &self.pt {
+            Lim::Pos | Lim::Neg => {
+                let r = n.lead().checked_div(d.lead())?;
+                Some(match n.deg().cmp(&d.deg()) {
+                    std::cmp::Ordering::Less => Lim::Fin(Ex::from(0)),
+                    std::cmp::Ordering::Equal => Lim::Fin(r.to_ex()),
+                    std::cmp::Ordering::Greater => {
+                        signed(r, self.pt == Lim::Neg && (n.deg() - d.deg()) % 2 == 1)
+                    }
+                })
+            }
+            Lim::Fin(a) => {
+                let a = a.as_rat()?;
+                let root = UPoly::linear(a);
+                let mut k = 0;
+                while d.eval(a)?.is_zero() {
+                    match n.eval(a)?.is_zero() {
+                        true => n = n.divrem(&root)?.0,
+                        false => k += 1,
+                    }
+                    d = d.divrem(&root)?.0;
+                }
+                let r = n.eval(a)?.checked_div(d.eval(a)?)?;
+                Some(match k {
+                    0 => Lim::Fin(r.to_ex()),
+                    _ => signed(r, self.side == Side::Left && k % 2 == 1),
+                })
+            }
+            Lim::Undef => None,
+        }
+    }
+
+    /// Signed infinity for an expression known to blow up
+    fn infinite(&self, f: &Ex) -> Option<Lim> {
+        Some(match self.sign(f)? > 0.0 {
+            true => Lim::Pos,
+            false => Lim::Neg,
+        })
+    }
+
+    /// Sign of `f` close to the point, if every sample agrees
+    fn sign(&self, f: &Ex) -> Option<f64> {
+        let xs: Vec<f64> = match &self.pt {
+            Lim::Fin(a) => {
+                let a = a.flt(&[])?;
+                match self.side {
+                    Side::Left => OFFSETS.iter().map(|h| a - h).collect(),
+                    _ => OFFSETS.iter().map(|h| a + h).collect(),
+                }
+            }
+            Lim::Pos => FAR.to_vec(),
+            Lim::Neg => FAR.iter().map(|v| -v).collect(),
+            Lim::Undef => return None,
+        };
+        let signs: Vec<f64> = xs
+            .iter()
+            .map(|v| f.flt(&[(self.x, *v)]).filter(|v| *v != 0.0 && !v.is_nan()))
+            .collect::<Option<_>>()?;
+        let s = signs[0].signum();
+        signs.iter().all(|v| v.signum() == s).then_some(s)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Free of undefined parts, infinities and non-finite values
+fn defined(e: &Ex) -> bool {
+    match e {
+        Ex::Invalid | Ex::Val(Val::Tok(Tok::Inf)) => false,
+        // tan at its poles evaluates to a huge but finite float
+        Ex::Fn(Fnc::Tan(a)) if cos(*a.clone()).flt(&[]).is_some_and(|c| c.abs() < 1e-12) => false,
+        _ if e.flt(&[]).is_some_and(|v| !v.is_finite()) => false,
+        _ => e.children().into_iter().all(defined),
+    }
+}
+
+/// Whether a step function in `f` has its argument on a jump at `x = a`
+fn on_jump(f: &Ex, x: &str, a: &Ex) -> bool {
+    let here = match f {
+        Ex::Fn(g) => g.args().first().is_some_and(|b| {
+            b.has(x)
+                && b.subs(x, a)
+                    .flt(&[])
+                    .is_some_and(|v| piecewise::jumps(g, v))
+        }),
+        _ => false,
+    };
+    here || f.children().into_iter().any(|c| on_jump(c, x, a))
+}
+
+fn is_zero(e: &Ex) -> bool {
+    e.flt(&[]) == Some(0.0)
+}
+
+/// Whether two one-sided limits agree
+fn same(a: &Lim, b: &Lim) -> bool {
+    match (a, b) {
+        (Lim::Fin(u), Lim::Fin(v)) => {
+            u == v
+                || matches!((u.flt(&[]), v.flt(&[])), (Some(u), Some(v))
+                    if (u - v).abs() <= 1e-12 * u.abs().max(1.0))
+        }
+        _ => a == b && *a != Lim::Undef,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::Ex;
+    use crate::ex3::testing::simp;
+
+    fn lim(s: &str) -> Ex {
+        simp(s)
+    }
+
+    #[test]
+    fn ex3_limit_finite() {
+        assert_eq!(lim("lim(x^2, x, 3)"), simp("9"));
+        assert_eq!(lim("lim(x^2, x, a)"), simp("a^2"));
+        assert_eq!(lim("lim((x^2-1)/(x-1), x, 1)"), simp("2"));
+        assert_eq!(lim("lim(sin(x)/x, x, 0)"), simp("1"));
+        assert_eq!(lim("lim((1-cos(x))/x^2, x, 0)"), simp("1/2"));
+        assert_eq!(lim("lim(x*ln(x), x, 0, 1)"), simp("0"));
+        assert_eq!(lim("lim(x^x, x, 0, 1)"), simp("1"));
+    }
+
+    #[test]
+    fn ex3_limit_one_sided() {
+        assert_eq!(lim("lim(1/x, x, 0, 1)"), simp("inf"));
+        assert_eq!(lim("lim(1/x, x, 0, -1)"), simp("-inf"));
+        assert_eq!(lim("lim(1/x, x, 0)"), simp("undef"));
+        assert_eq!(lim("lim(1/x, x, 0)").to_text(), "undef");
+        assert_eq!(lim("lim(1/x^2, x, 0)"), simp("inf"));
+        assert_eq!(lim("lim(ln(x), x, 0, 1)"), simp("-inf"));
+        assert_eq!(lim("lim(tan(x), x, pi/2, -1)"), simp("inf"));
+        assert_eq!(lim("lim(tan(x), x, pi/2)"), simp("undef"));
+    }
+
+    #[test]
+    fn ex3_limit_infinite() {
+        assert_eq!(lim("lim((2*x^2+1)/(x^2-3), x, inf)"), simp("2"));
+        assert_eq!(lim("lim(x^3-x, x, -inf)"), simp("-inf"));
+        assert_eq!(lim("lim(e^x/x^2, x, inf)"), simp("inf"));
+        assert_eq!(lim("lim(ln(x)/x, x, inf)"), simp("0"));
+        assert_eq!(lim("lim(x^2-e^x, x, inf)"), simp("-inf"));
+        assert_eq!(lim("lim((1+1/x)^x, x, inf)"), simp("e"));
+        assert_eq!(lim("lim(atan(x), x, -inf)"), simp("-pi/2"));
+        assert_eq!(lim("lim(sin(x), x, inf)"), simp("undef"));
+    }
+
+    #[test]
+    fn ex3_limit_growth() {
+        // beyond the reach of repeated L'Hôpital steps
+        assert_eq!(lim("lim(x^10/e^x, x, inf)"), simp("0"));
+        assert_eq!(lim("lim(e^x/x^50, x, inf)"), simp("inf"));
+        assert_eq!(lim("lim(-(e^x)/x^50, x, inf)"), simp("-inf"));
+        assert_eq!(lim("lim(x/ln(x)^20, x, inf)"), simp("inf"));
+        assert_eq!(lim("lim(2^x/x^30, x, inf)"), simp("inf"));
+        assert_eq!(lim("lim(x^3*e^(-2*x)*ln(x)^4, x, inf)"), simp("0"));
+        assert_eq!(lim("lim(x^11*e^(-x), x, -inf)"), simp("-inf"));
+        assert_eq!(lim("lim(x^10*e^x, x, -inf)"), simp("0"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
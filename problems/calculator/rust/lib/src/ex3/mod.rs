@@ -5,10 +5,43 @@
 ///////////////////////////////////////////////////////////////////////////////
 
 pub mod ast;
+pub mod bigfloat;
+pub mod derivative;
 pub mod display;
+pub mod document;
+pub mod expand;
+pub mod factor;
 pub mod helper;
+pub mod integral;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod limit;
+pub mod mode;
+pub mod mpoly;
 pub mod numeric;
+pub mod ode;
 pub mod parser;
+pub mod piecewise;
+pub mod poly;
+pub mod polydiv;
+pub mod quadrature;
+pub mod rational;
+pub mod relation;
+pub mod render;
+pub mod rewrite;
+pub mod rk45;
+pub mod rootfind;
+pub mod search;
+pub mod series;
+pub mod simplify;
+pub mod solve;
+pub mod special;
+pub mod summation;
+pub mod symbols;
+#[cfg(test)]
+mod testing;
+pub mod trace;
+pub mod trig;
 
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
@@ -24,13 +57,7 @@ pub const TERMINAL_WIDTH: usize = 80;
 mod tests {
     use std::vec;
 
-    use pest::Parser;
-
-    use crate::ex3::{
-        ast::Ex,
-        parser::{ExParser, Rule},
-        TERMINAL_WIDTH,
-    };
+    use crate::ex3::{ast::Ex, TERMINAL_WIDTH};
 
     #[test]
     fn ex3_test_parser() {
@@ -80,6 +107,81 @@ mod tests {
             "x*(((x^2)/x)^1)",
             "x*((6/14)^2) | x = 5 + 7",
         ]);
+
+        eval(vec![
+            "d(x^2, x)",
+            "d(sin(x)*x, x)",
+            "int(x^2, x)",
+            "int(1/(x^2-1), x)",
+            "int(x*e^x, x)",
+            "int(2*x*cos(x^2), x)",
+            "int(e^(x^2), x)",
+            "int(x^2, x, 0, 3)",
+            "int(sin(x), x, 0, pi/2)",
+            "int(e^(-(x^2)), x, -inf, inf)",
+            "nInt(x^2, x, 0, 3)",
+        ]);
+
+        eval(vec![
+            "solve(2*x+3 = 7, x)",
+            "solve(x^2 = 4, x)",
+            "solve(x^2+1 = 0, x)",
+            "cSolve(x^2+1 = 0, x)",
+            "solve(x^3+x+1 = 0, x)",
+            "solve(x^3-3*x+1 = 0, x)",
+            "cSolve(x^3 = 8, x)",
+            "solve(e^x = 5, x)",
+            "solve(tan(x) = 1, x)",
+            "solve(a*x+b = c, x)",
+            "nSolve(cos(x) = x, x, 0)",
+            "nSolve(atan(x), x, 2)",
+            "zeros(sin(x), x, -1, 10)",
+        ]);
+
+        eval(vec![
+            "factor(x^2-1)",
+            "factor(2*x^3+4*x^2+2*x)",
+            "factor(x^4+4)",
+            "factor(x^6-1)",
+            "factor(x^2-y^2)",
+            "factor(x^2/2-1/2)",
+            "quo(x^3-2*x+5, x-1, x)",
+            "rem(x^3-2*x+5, x-1, x)",
+            "propFrac((x^2+1)/(x-1), x)",
+            "gcd(x^2-1, x^2+2*x+1)",
+            "lcm(x^2-1, x^2+2*x+1)",
+        ]);
+
+        eval(vec![
+            "lim(sin(x)/x, x, 0)",
+            "lim(1/x, x, 0, -1)",
+            "lim((1+1/x)^x, x, inf)",
+            "taylor(sin(x)/x, x, 4)",
+            "taylor(ln(x), x, 3, 1)",
+            "sum(k^2, k, 1, n)",
+            "sum((1/2)^k, k, 0, inf)",
+            "prod(2^k, k, 1, n)",
+            "deSolve(y'' + y = 0, x, y, [0, 0, 1])",
+            "deSolve(y' = x*y, x, y)",
+            "expand(2*(3+1*x))",
+            "expand((x+1)^3)",
+            "expand(sin(2*x) + ln(x*y), all)",
+            "sin(x)^2+cos(x)^2",
+            "tan(x)*cos(x)",
+            "tCollect(2*sin(x)*cos(x))",
+            "sin(pi/6)",
+            "cos(3*pi/4)",
+            "e^(i*pi)",
+            "approx(pi)",
+            "approx(2^(1/2) + x, 4)",
+            "approx(pi, 50)",
+            "α + β·θ",
+            "√(x+1) ≤ 2",
+            "pi ≈ 3.14159",
+            "solve(y ∝ x, y)",
+            "|-x| + ⌊7/2⌋",
+            "lim(floor(x), x, 1)",
+        ]);
     }
 
     fn eval(xs: Vec<&str>) {
@@ -97,10 +199,9 @@ mod tests {
             .unwrap_or(0);
 
         for (_, x) in xs.into_iter().enumerate() {
-            match ExParser::parse(Rule::program, x) {
-                Ok(mut pairs) => {
+            match x.parse::<Ex>() {
+                Ok(y) => {
                     // println!("{:-<80}\n", "");
-                    let y = Ex::from(pairs.next().unwrap().into_inner());
                     // let numeric = y.numeric_reducer();
 
                     let mut y1 = y.clone();
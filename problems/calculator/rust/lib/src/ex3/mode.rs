@@ -0,0 +1,245 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::cell::Cell;
+
+use super::{ast::*, rational::*, simplify::*, special};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Significant digits of `approx` without a second argument, as the TI-89
+/// displays them
+pub const DIGITS: u32 = 12;
+
+/// Most significant digits an `f64` holds, beyond which `approx` switches
+/// to [`BigFloat`](super::bigfloat::BigFloat)
+pub const MAX_DIGITS: u32 = 17;
+
+/// Most significant digits `approx` computes
+pub const MAX_PRECISION: u32 = 1000;
+
+/// Relative error below which a float is taken to be a fraction
+const TOL: f64 = 1e-12;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// How [`Ex::evaluated`] treats numbers, as the Exact/Approx setting of the
+/// TI-89
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Floats in the input are read as fractions and nothing is
+    /// approximated
+    Exact,
+    /// Every constant is evaluated to a float
+    Approximate,
+    /// Exact, unless a float was in the input
+    #[default]
+    Auto,
+}
+
+thread_local! {
+    static MODE: Cell<Mode> = const { Cell::new(Mode::Auto) };
+}
+
+/// Current mode
+pub fn get() -> Mode {
+    MODE.with(Cell::get)
+}
+
+/// Change the mode for every later evaluation on this thread
+pub fn set(mode: Mode) {
+    MODE.with(|m| m.set(mode));
+}
+
+/// Run `f` in `mode`, restoring the previous mode afterwards
+pub fn with<T>(mode: Mode, f: impl FnOnce() -> T) -> T {
+    let prev = MODE.with(|m| m.replace(mode));
+    let out = f();
+    set(prev);
+    out
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Simplify in the current [`Mode`]
+    pub fn evaluated(&self) -> Ex {
+        match get() {
+            Mode::Exact => self.exact().simplified(),
+            Mode::Approximate => self.approx(DIGITS),
+            Mode::Auto => self.simplified(),
+        }
+    }
+
+    /// Every float replaced by the fraction it stands for, such as `0.5` by
+    /// `1/2`
+    ///
+    /// - Floats that are not close to a fraction of reasonable size stay
+    pub fn exact(&self) -> Ex {
+        match self {
+            Ex::Val(Val::Num(Num::Flt(v))) => fraction(*v).map_or(self.clone(), Rat::to_ex),
+            _ => self.map(Ex::exact),
+        }
+    }
+
+    /// Simplify, then evaluate every constant subterm to a float rounded to
+    /// `digits` significant digits
+    ///
+    /// - Radicals, `pi`, `e`, fractions and functions of them all become
+    ///   floats, free variables stay
+    /// - Floats are combined and rounded once more after the first pass, so
+    ///   `x/4` ends up as `0.25*x`
+    /// - More than [`MAX_DIGITS`] digits give
+    ///   [`BigFloat`](super::bigfloat::BigFloat) numbers instead
+    /// - Functions at an exact pole, such as `tan(pi/2)`, are `undef`
+    pub fn approx(&self, digits: u32) -> Ex {
+        let digits = digits.clamp(1, MAX_PRECISION);
+        let e = poles(&self.simplified()).simplified();
+        if digits > MAX_DIGITS {
+            return big(&e, digits as usize);
+        }
+        approx(&approx(&e, digits).simplified(), digits)
+    }
+}
+
+/// Every function at an exact pole replaced by `undef`
+fn poles(e: &Ex) -> Ex {
+    match e {
+        Ex::Fn(f) if special::pole(f) => Ex::Invalid,
+        _ => e.map(poles),
+    }
+}
+
+fn big(e: &Ex, digits: usize) -> Ex {
+    match e.big(digits) {
+        Some(v) if v.is_neg() => -Ex::from(v.neg()),
+        Some(v) => Ex::from(v),
+        None => e.map(|a| big(a, digits)),
+    }
+}
+
+fn approx(e: &Ex, digits: u32) -> Ex {
+    if let Some(v) = e.flt(&[]).filter(|v| v.is_finite()) {
+        return Coef::F(round(v, digits)).to_ex();
+    }
+    e.map(|a| approx(a, digits))
+}
+
+/// `v` rounded to `digits` significant digits
+fn round(v: f64, digits: u32) -> f64 {
+    format!("{:.*e}", digits as usize - 1, v)
+        .parse()
+        .unwrap_or(v)
+}
+
+/// Best fraction for `v`, by continued fractions
+fn fraction(v: f64) -> Option<Rat> {
+    if !v.is_finite() {
+        return None;
+    }
+    let (mut h, mut h_prev, mut k, mut k_prev) = (1i128, 0i128, 0i128, 1i128);
+    let mut x = v;
+    for _ in 0..64 {
+        let a = x.floor();
+        if a.abs() > 1e15 {
+            return None;
+        }
+        let a = a as i128;
+        (h, h_prev) = (a.checked_mul(h)?.checked_add(h_prev)?, h);
+        (k, k_prev) = (a.checked_mul(k)?.checked_add(k_prev)?, k);
+        if (h as f64 / k as f64 - v).abs() <= TOL * v.abs().max(1.0) {
+            return Some(Rat::new(h, k));
+        }
+        x = 1.0 / (x - a as f64);
+    }
+    None
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::{parse, simp};
+
+    #[test]
+    fn ex3_mode_evaluate() {
+        let e = parse("0.5 + x/4 + sin(2)");
+        assert_eq!(
+            with(Mode::Exact, || e.evaluated()),
+            simp("1/2 + x/4 + sin(2)")
+        );
+        assert_eq!(
+            with(Mode::Approximate, || e.evaluated()),
+            simp("0.25*x + 1.40929742683")
+        );
+        assert_eq!(with(Mode::Auto, || parse("1/3").evaluated()), simp("1/3"));
+        assert_eq!(
+            with(Mode::Auto, || parse("1/3 + 0.5").evaluated()),
+            Ex::from(1.0 / 3.0 + 0.5)
+        );
+        assert_eq!(get(), Mode::Auto);
+
+        // the numeric step keeps exact arguments in exact mode
+        let mut e = parse("sin(2)");
+        with(Mode::Exact, || e.numeric());
+        assert_eq!(e, parse("sin(2)"));
+    }
+
+    #[test]
+    fn ex3_mode_numeric() {
+        let numeric = |mode: Mode, s: &str| {
+            let mut e = parse(s);
+            with(mode, || e.numeric());
+            e
+        };
+        assert_eq!(numeric(Mode::Auto, "ln(2)"), parse("ln(2)"));
+        assert_eq!(numeric(Mode::Auto, "ln(2.0)"), Ex::from(2f64.ln()));
+        assert_eq!(numeric(Mode::Auto, "1 + 0.5"), Ex::from(1.5));
+
+        assert_eq!(numeric(Mode::Approximate, "1/3"), Ex::from(0.333333333333));
+        assert_eq!(
+            numeric(Mode::Approximate, "2^(1/2)"),
+            simp("approx(2^(1/2))")
+        );
+        assert_eq!(
+            numeric(Mode::Approximate, "sin(pi/7)"),
+            Ex::from(0.433883739118)
+        );
+
+        assert!(!format!("{:?}", numeric(Mode::Exact, "1/3 + 0.5")).contains('.'));
+        assert_eq!(numeric(Mode::Exact, "ln(2)"), parse("ln(2)"));
+        assert_eq!(numeric(Mode::Exact, "1 + 0.5").simplified(), simp("3/2"));
+    }
+
+    #[test]
+    fn ex3_mode_approx() {
+        assert_eq!(simp("approx(1/7)"), Ex::from(0.142857142857));
+        assert_eq!(simp("approx(3*pi, 5)"), Ex::from(9.4248));
+        assert_eq!(simp("approx(2^(1/2), 4)"), Ex::from(1.414));
+        assert_eq!(simp("approx(e, 3)"), Ex::from(2.72));
+        assert_eq!(simp("approx(1/3, 5)"), Ex::from(0.33333));
+        assert_eq!(simp("approx(x + pi, 3)"), simp("x + 3.14"));
+        assert_eq!(simp("approx(x = 2/3, 2)"), simp("x = 0.67"));
+        assert_eq!(simp("approx(7)"), Ex::from(7.0));
+        assert_eq!(simp("approx(x, n)").to_text(), "approx(x, n)");
+        assert_eq!(simp("approx(tan(pi/2))"), Ex::Invalid);
+        assert_eq!(simp("approx(tan(pi/2), 30)"), Ex::Invalid);
+        assert_eq!(simp("approx(1 + tan(-3*pi/2), 30)"), Ex::Invalid);
+        assert_eq!(simp("approx(1/sin(pi), 30)"), Ex::Invalid);
+        assert_eq!(simp("approx(1/cos(5*pi/2))"), Ex::Invalid);
+        assert_eq!(simp("approx(tan(pi/4), 30)"), simp("approx(1, 30)"));
+
+        assert_eq!(parse("0.125").exact(), simp("1/8"));
+        assert_eq!(parse("0.1 + y").exact().simplified(), simp("1/10 + y"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,455 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::cmp::Ordering;
+
+use super::{ast::*, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Largest integer power of a sum that is multiplied out
+const MAX_POWER: i128 = 32;
+
+/// Most terms an expansion may produce before it is abandoned
+const MAX_TERMS: usize = 256;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Order of the monomials in a [`MPoly`], highest first
+///
+/// - Variables are ranked by [`cmp_ex`], so `x` comes before `y`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum MonomialOrder {
+    /// Lexicographic on the exponents
+    Lex,
+    /// Total degree, ties broken lexicographically
+    #[default]
+    GrLex,
+    /// Total degree, ties broken by the smallest power of the last variable
+    GRevLex,
+}
+
+impl MonomialOrder {
+    /// Compare two exponent vectors over the same variables
+    pub fn cmp(self, a: &[i32], b: &[i32]) -> Ordering {
+        let deg = |m: &[i32]| m.iter().map(|&e| e as i64).sum::<i64>();
+        match self {
+            MonomialOrder::Lex => a.cmp(b),
+            MonomialOrder::GrLex => deg(a).cmp(&deg(b)).then_with(|| a.cmp(b)),
+            MonomialOrder::GRevLex => deg(a)
+                .cmp(&deg(b))
+                .then_with(|| b.iter().rev().cmp(a.iter().rev())),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Sparse multivariate polynomial
+///
+/// - The variables are arbitrary atoms: symbols, constants like `pi`,
+///   function calls and sums that could not be expanded
+/// - Exponents may be negative, so `x * x^-1` cancels
+/// - Terms are kept merged, without zero coefficients and sorted highest
+///   first by `order`
+#[derive(Clone, Debug)]
+pub struct MPoly {
+    vars: Vec<Ex>,
+    terms: Vec<(Vec<i32>, Coef)>,
+    order: MonomialOrder,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl MPoly {
+    pub fn zero() -> Self {
+        MPoly {
+            vars: vec![],
+            terms: vec![],
+            order: MonomialOrder::default(),
+        }
+    }
+
+    pub fn constant(c: Coef) -> Self {
+        let mut p = MPoly::zero();
+        if !c.is_zero() {
+            p.terms.push((vec![], c));
+        }
+        p
+    }
+
+    /// The polynomial `v^k` in the single atom `v`
+    pub fn atom(v: Ex, k: i32) -> Self {
+        if k == 0 {
+            return MPoly::constant(Coef::Q(Rat::ONE));
+        }
+        let mut p = MPoly::zero();
+        p.vars.push(v);
+        p.terms.push((vec![k], Coef::Q(Rat::ONE)));
+        p
+    }
+
+    pub fn vars(&self) -> &[Ex] {
+        &self.vars
+    }
+
+    /// Exponent vectors over [`MPoly::vars`] with their coefficients
+    pub fn terms(&self) -> &[(Vec<i32>, Coef)] {
+        &self.terms
+    }
+
+    pub fn order(&self) -> MonomialOrder {
+        self.order
+    }
+
+    /// Re-sort the terms under another monomial order
+    pub fn with_order(mut self, order: MonomialOrder) -> Self {
+        self.order = order;
+        self.sorted()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Total degree of the highest degree term
+    pub fn degree(&self) -> i64 {
+        self.terms
+            .iter()
+            .map(|(m, _)| m.iter().map(|&e| e as i64).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Leading term under the monomial order
+    pub fn lead(&self) -> Option<&(Vec<i32>, Coef)> {
+        self.terms.first()
+    }
+
+    /// Every coefficient times `k`, `None` on overflow
+    pub fn scale(&self, k: Coef) -> Option<Self> {
+        MPoly {
+            terms: self
+                .terms
+                .iter()
+                .map(|(m, c)| Some((m.clone(), c.checked_mul(k)?)))
+                .collect::<Option<_>>()?,
+            ..self.clone()
+        }
+        .normalize()
+    }
+
+    pub fn neg(&self) -> Self {
+        MPoly {
+            terms: self
+                .terms
+                .iter()
+                .map(|(m, c)| (m.clone(), c.mul(Coef::Q(-Rat::ONE))))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Sum, `None` on overflow
+    pub fn add(&self, rhs: &Self) -> Option<Self> {
+        let (a, b) = unify(self, rhs);
+        let mut p = a;
+        p.terms.extend(b.terms);
+        p.normalize()
+    }
+
+    /// Difference, `None` on overflow
+    pub fn sub(&self, rhs: &Self) -> Option<Self> {
+        self.add(&rhs.neg())
+    }
+
+    /// Product, `None` on overflow of a coefficient or an exponent
+    pub fn mul(&self, rhs: &Self) -> Option<Self> {
+        let (a, b) = unify(self, rhs);
+        let mut terms = vec![];
+        for (m, c) in &a.terms {
+            for (n, d) in &b.terms {
+                let e = m
+                    .iter()
+                    .zip(n)
+                    .map(|(x, y)| x.checked_add(*y))
+                    .collect::<Option<_>>()?;
+                terms.push((e, c.checked_mul(*d)?));
+            }
+        }
+        MPoly { terms, ..a }.normalize()
+    }
+
+    /// `k`th power, `None` on overflow
+    pub fn powi(&self, k: u32) -> Option<Self> {
+        (0..k).try_fold(MPoly::constant(Coef::Q(Rat::ONE)), |acc, _| acc.mul(self))
+    }
+
+    /// Merge like terms, drop zeros and unused variables, and sort
+    ///
+    /// - `None` if merging overflows a coefficient
+    fn normalize(mut self) -> Option<Self> {
+        let mut merged: Vec<(Vec<i32>, Coef)> = vec![];
+        for (m, c) in self.terms.drain(..) {
+            match merged.iter_mut().find(|(n, _)| *n == m) {
+                Some((_, d)) => *d = d.checked_add(c)?,
+                None => merged.push((m, c)),
+            }
+        }
+        merged.retain(|(_, c)| !c.is_zero());
+
+        let used: Vec<usize> = (0..self.vars.len())
+            .filter(|&i| merged.iter().any(|(m, _)| m[i] != 0))
+            .collect();
+        self.vars = used.iter().map(|&i| self.vars[i].clone()).collect();
+        for (m, _) in merged.iter_mut() {
+            *m = used.iter().map(|&i| m[i]).collect();
+        }
+
+        self.terms = merged;
+        Some(self.sorted())
+    }
+
+    /// Sort the terms highest first under the monomial order
+    fn sorted(mut self) -> Self {
+        let order = self.order;
+        self.terms.sort_by(|a, b| order.cmp(&b.0, &a.0));
+        self
+    }
+}
+
+/// Rewrite both polynomials over the union of their variables
+fn unify(a: &MPoly, b: &MPoly) -> (MPoly, MPoly) {
+    let mut vars = a.vars.clone();
+    for v in &b.vars {
+        if !vars.contains(v) {
+            vars.push(v.clone());
+        }
+    }
+    vars.sort_by(cmp_ex);
+    let widen = |p: &MPoly| {
+        let terms = p
+            .terms
+            .iter()
+            .map(|(m, c)| {
+                let e = vars
+                    .iter()
+                    .map(|v| p.vars.iter().position(|u| u == v).map_or(0, |i| m[i]))
+                    .collect();
+                (e, *c)
+            })
+            .collect();
+        MPoly {
+            vars: vars.clone(),
+            terms,
+            order: a.order,
+        }
+    };
+    (widen(a), widen(b))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl MPoly {
+    /// Read a simplified expression, multiplying out products and integer
+    /// powers of sums
+    ///
+    /// - Anything that is not arithmetic becomes an atom
+    /// - `None` for relations and matrices, if the expansion grows past
+    ///   a fixed number of terms, or if a coefficient overflows
+    pub fn from_ex(e: &Ex) -> Option<Self> {
+        let p = match e {
+            Ex::Infix(_, Bin::Rl(_), _) | Ex::Mat(_) | Ex::Invalid => return None,
+            _ if Coef::of(e).is_some() => MPoly::constant(Coef::of(e)?),
+            Ex::Neg(a) => MPoly::from_ex(a)?.neg(),
+            Ex::Infix(a, Bin::Op(Op::Add), b) => MPoly::from_ex(a)?.add(&MPoly::from_ex(b)?)?,
+            Ex::Infix(a, Bin::Op(Op::Sub), b) => MPoly::from_ex(a)?.sub(&MPoly::from_ex(b)?)?,
+            Ex::Infix(a, Bin::Op(Op::Mul), b) => MPoly::from_ex(a)?.mul(&MPoly::from_ex(b)?)?,
+            Ex::Infix(a, Bin::Op(Op::Div), b) => {
+                let mut p = MPoly::from_ex(a)?;
+                for (base, exp) in Product::of(b).factors {
+                    let k = exp.as_rat().filter(Rat::is_int).map(|k| k.numer());
+                    let q = match k.and_then(|k| i32::try_from(-k).ok()) {
+                        Some(k) => power_of(&base, k)?,
+                        None => MPoly::atom(power(base, product(&[Ex::from(-1), exp])), 1),
+                    };
+                    p = p.mul(&q)?;
+                }
+                let c = Product::of(b).coef;
+                match c.is_zero() {
+                    true => return None,
+                    false => p.scale(c.recip())?,
+                }
+            }
+            Ex::Infix(a, Bin::Op(Op::Pow), b) => {
+                match b.as_rat().filter(Rat::is_int).map(|k| k.numer()) {
+                    Some(k) => power_of(a, i32::try_from(k).ok()?)?,
+                    None => MPoly::atom(e.clone(), 1),
+                }
+            }
+            _ => MPoly::atom(e.clone(), 1),
+        };
+        (p.terms.len() <= MAX_TERMS).then_some(p)
+    }
+
+    /// Rebuild as a sum of terms in the monomial order of this polynomial
+    pub fn to_ex(&self) -> Ex {
+        let mut out: Option<Ex> = None;
+        for (m, c) in &self.terms {
+            let mag = self.monomial(m, c.abs());
+            out = Some(match (out, c.is_neg()) {
+                (None, false) => mag,
+                (None, true) => -mag,
+                (Some(acc), false) => acc + mag,
+                (Some(acc), true) => acc - mag,
+            });
+        }
+        out.unwrap_or(Ex::from(0))
+    }
+
+    /// Each term as its own simplified expression
+    pub fn term_exs(&self) -> Vec<Ex> {
+        self.terms
+            .iter()
+            .map(|(m, c)| self.monomial(m, *c))
+            .collect()
+    }
+
+    fn monomial(&self, m: &[i32], c: Coef) -> Ex {
+        let mut xs = vec![c.to_ex().simplified()];
+        for (v, &k) in self.vars.iter().zip(m) {
+            if k != 0 {
+                xs.push(power(v.clone(), Ex::from(k)));
+            }
+        }
+        product(&xs)
+    }
+}
+
+/// `base^k` for an integer `k`, expanding positive powers of sums
+fn power_of(base: &Ex, k: i32) -> Option<MPoly> {
+    match base {
+        Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _) if k > 0 => {
+            if k as i128 > MAX_POWER {
+                return None;
+            }
+            let p = MPoly::from_ex(base)?;
+            let mut acc = MPoly::constant(Coef::Q(Rat::ONE));
+            for _ in 0..k {
+                acc = acc.mul(&p)?;
+                if acc.terms.len() > MAX_TERMS {
+                    return None;
+                }
+            }
+            Some(acc)
+        }
+        Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _) => {
+            Some(MPoly::atom(power(base.clone(), Ex::from(k)), 1))
+        }
+        _ => match Coef::of(base) {
+            Some(c) => Some(MPoly::constant(Coef::of(&power(
+                c.to_ex().simplified(),
+                Ex::from(k),
+            ))?)),
+            None => Some(MPoly::atom(base.clone(), k)),
+        },
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Fully expanded canonical form of a polynomial expression
+    ///
+    /// - Like terms are collected and products of sums multiplied out
+    /// - Equal polynomials always give equal expressions
+    /// - Expressions that are not polynomial are returned simplified
+    pub fn normal(&self) -> Ex {
+        let e = self.simplified();
+        match MPoly::from_ex(&e) {
+            Some(p) => sum(&p.term_exs()),
+            None => e,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::simp;
+
+    fn poly(s: &str) -> MPoly {
+        MPoly::from_ex(&s.parse::<Ex>().unwrap().simplified()).unwrap()
+    }
+
+    fn normal(s: &str) -> Ex {
+        s.parse::<Ex>().unwrap().normal()
+    }
+
+    #[test]
+    fn ex3_mpoly_collect() {
+        assert_eq!(poly("2*x+6*x").terms().len(), 1);
+        assert_eq!(normal("2*x+6*x"), simp("8*x"));
+        assert_eq!(normal("x*y - y*x"), simp("0"));
+        assert_eq!(normal("(x+y)^2"), normal("x^2 + 2*x*y + y^2"));
+        assert_eq!(normal("(x+1)*(x-1)"), simp("x^2-1"));
+        assert_eq!(normal("x*(1/x + 1)"), simp("x+1"));
+        assert_eq!(normal("(sin(x)+1)^2"), simp("sin(x)^2 + 2*sin(x) + 1"));
+    }
+
+    #[test]
+    fn ex3_mpoly_order() {
+        let p = poly("x*y^2 + x^2 + y^3");
+        let lead = |o: MonomialOrder| p.clone().with_order(o).lead().unwrap().0.clone();
+        // variables are [x, y]
+        assert_eq!(lead(MonomialOrder::Lex), vec![2, 0]);
+        assert_eq!(lead(MonomialOrder::GrLex), vec![1, 2]);
+        assert_eq!(lead(MonomialOrder::GRevLex), vec![1, 2]);
+
+        let p = poly("x^2*z + x*y^2");
+        // GrLex prefers more x, GRevLex less of the last variable
+        let lead = |o: MonomialOrder| p.clone().with_order(o).lead().unwrap().0.clone();
+        assert_eq!(lead(MonomialOrder::GrLex), vec![2, 0, 1]);
+        assert_eq!(lead(MonomialOrder::GRevLex), vec![1, 2, 0]);
+
+        let q = poly("y + x^2").with_order(MonomialOrder::Lex);
+        assert_eq!(q.to_ex(), "x^2+y".parse::<Ex>().unwrap());
+    }
+
+    #[test]
+    fn ex3_mpoly_simplify() {
+        // sums are collected when multiplying out makes them smaller
+        assert_eq!(simp("(x+1)^2 - x^2"), simp("2*x+1"));
+        assert_eq!(simp("2*(x+3) + x"), simp("3*x+6"));
+        assert_eq!(simp("(x+1)*(x-1) + 1"), simp("x^2"));
+        // but not when it would make them larger
+        assert_eq!(format!("{:?}", simp("(x+1)^2 + 1")), "((\"x\"+1)^2)+1");
+    }
+
+    #[test]
+    fn ex3_mpoly_overflow() {
+        let big = "170141183460469231731687303715884105727";
+        let e = simp(&format!("(2^126*x + {big})*(2*x + 1)"));
+        assert!(MPoly::from_ex(&e).is_none());
+        // used to panic while cancelling and dividing
+        let q = simp("quo(x^3, 3*x-100000000000000000000000, x)");
+        assert!(!matches!(q, Ex::Fn(_)), "{:?}", q);
+        let i = simp(&format!("int(x^{big}, x)"));
+        assert!(!matches!(i, Ex::Fn(_)), "{:?}", i);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -2,24 +2,46 @@
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
 
-use std::f64::consts::PI;
+use std::f64::consts::{E, PI};
 
-use super::ast::*;
+use super::{
+    ast::*,
+    mode::{self, Mode},
+    piecewise, quadrature,
+    rewrite::Rewrite,
+    simplify::Coef,
+    special,
+};
 
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
 
 impl Ex {
-    /// Numeric reduction step
+    /// Numeric reduction step in the current [`Mode`], as
+    /// [`Ex::evaluated`]
     ///
     /// - Collect adj numbers
     /// - Distribute non-adj numbers
+    /// - Exact mode reads floats as fractions first, approximate mode
+    ///   evaluates every constant to a float
+    /// - Functions of exact arguments only become floats in approximate mode
     pub fn numeric(&mut self) {
+        match mode::get() {
+            Mode::Exact => {
+                *self = self.exact();
+                self.reduce();
+            }
+            Mode::Approximate => *self = self.approx(mode::DIGITS),
+            Mode::Auto => self.reduce(),
+        }
+    }
+
+    fn reduce(&mut self) {
         match self {
             Ex::Val(_) => {}
             Ex::Neg(ex) => {
-                ex.numeric();
+                ex.reduce();
                 match ex.as_mut() {
                     Ex::Neg(ex) => *self = *ex.to_owned(),
                     Ex::Mat(exs) => *self = Ex::Mat(exs.iter().map(|ex| -ex.to_owned()).collect()),
@@ -32,8 +54,8 @@ impl Ex {
                 }
             }
             Ex::Infix(a, j, b) => {
-                a.numeric();
-                b.numeric();
+                a.reduce();
+                b.reduce();
                 match j {
                     Bin::Op(op) => match (a.as_ref(), &op, b.as_ref()) {
                         (Ex::Val(Val::Num(a)), _, Ex::Val(Val::Num(b))) => match op {
@@ -52,49 +74,67 @@ impl Ex {
                 }
             }
             Ex::Fn(f) => match f {
-                Fnc::Ln(e) => {
-                    e.numeric();
-                    match e.as_mut() {
-                        Ex::Val(v) => match v {
-                            Val::Num(v) => *self = Ex::from(v.flt().ln()),
-                            _ => {}
-                        },
-                        _ => {}
-                    }
-                }
-                Fnc::Sin(e) => {
-                    e.numeric();
-                    match e.as_mut() {
-                        Ex::Val(v) => match v {
-                            Val::Num(v) => *self = Ex::from(v.flt().sin()),
-                            Val::Tok(Tok::Pi) => *self = Ex::from(PI.sin()),
-                            _ => {}
-                        },
-                        _ => {}
+                Fnc::Ln(e) | Fnc::Sin(e) | Fnc::Cos(e) | Fnc::Tan(e) | Fnc::Atan(e) => {
+                    e.reduce();
+                    // exact values first, so that sin(pi) is 0 and not a float
+                    if let Some(v) = special::value(f) {
+                        *self = v;
+                        return;
                     }
-                }
-                Fnc::Cos(e) => {
-                    e.numeric();
-                    match e.as_mut() {
-                        Ex::Val(v) => match v {
-                            Val::Num(v) => *self = Ex::from(v.flt().cos()),
-                            Val::Tok(Tok::Pi) => *self = Ex::from(PI.cos()),
-                            _ => {}
-                        },
-                        _ => {}
-                    }
-                }
-                Fnc::Tan(e) => {
-                    e.numeric();
-                    match e.as_mut() {
-                        Ex::Val(v) => match v {
-                            Val::Num(v) => *self = Ex::from(v.flt().tan()),
-                            Val::Tok(Tok::Pi) => *self = Ex::from(PI.tan()),
+                    let g = match f {
+                        Fnc::Ln(_) => f64::ln,
+                        Fnc::Sin(_) => f64::sin,
+                        Fnc::Cos(_) => f64::cos,
+                        Fnc::Tan(_) => f64::tan,
+                        _ => f64::atan,
+                    };
+                    if let Ex::Fn(
+                        Fnc::Ln(e) | Fnc::Sin(e) | Fnc::Cos(e) | Fnc::Tan(e) | Fnc::Atan(e),
+                    ) = self
+                    {
+                        match e.as_ref() {
+                            // exact arguments stay exact
+                            Ex::Val(Val::Num(Num::Int(_))) => {}
+                            Ex::Val(Val::Num(v)) => *self = Ex::from(g(v.flt())),
                             _ => {}
-                        },
-                        _ => {}
+                        }
                     }
                 }
+                Fnc::Abs(..)
+                | Fnc::Floor(..)
+                | Fnc::Ceil(..)
+                | Fnc::Round(..)
+                | Fnc::Frac(..)
+                | Fnc::Sign(..)
+                | Fnc::Norm(..)
+                | Fnc::D(..)
+                | Fnc::Int(..)
+                | Fnc::DefInt(..)
+                | Fnc::NInt(..)
+                | Fnc::Solve(..)
+                | Fnc::CSolve(..)
+                | Fnc::NSolve(..)
+                | Fnc::Zeros(..)
+                | Fnc::Factor(..)
+                | Fnc::Expand(..)
+                | Fnc::ExpandWith(..)
+                | Fnc::TExpand(..)
+                | Fnc::TCollect(..)
+                | Fnc::Approx(..)
+                | Fnc::ApproxTo(..)
+                | Fnc::Quo(..)
+                | Fnc::Rem(..)
+                | Fnc::PropFrac(..)
+                | Fnc::Gcd(..)
+                | Fnc::Lcm(..)
+                | Fnc::Lim(..)
+                | Fnc::LimSide(..)
+                | Fnc::Taylor(..)
+                | Fnc::Sum(..)
+                | Fnc::Prod(..)
+                | Fnc::DeSolve(..)
+                | Fnc::DeSolveIvp(..)
+                | Fnc::NDeSolve(..) => self.simplify(),
             },
             Ex::Mat(_) => todo!(),
             Ex::Invalid => {}
@@ -103,13 +143,127 @@ impl Ex {
 
     /// Confluent reduction step
     ///
+    /// - Rewrites to the unique normal form under [`Rewrite::standard`]
     /// - Only complete for a small set of expressions
     pub fn confluent(&mut self) {
-        todo!()
+        if let Some(e) = Rewrite::standard().normalize(self) {
+            *self = e;
+        }
     }
 
+    /// Numeric root of `self` in its only variable, see [`Ex::nsolve`]
+    ///
+    /// - Starts from zero and becomes `x = root` once that converges
+    /// - Left alone otherwise, or with more than one variable
     pub fn gradient_descent(&mut self) {
-        todo!()
+        fn vars(e: &Ex, out: &mut Vec<String>) {
+            match e.var() {
+                Some(x) if !out.iter().any(|y| x == y) => out.push(x.to_owned()),
+                _ => e.children().into_iter().for_each(|a| vars(a, out)),
+            }
+        }
+        let mut xs = vec![];
+        vars(self, &mut xs);
+        if let [x] = xs.as_slice() {
+            let r = self.nsolve(x, 0.0);
+            if r.converged {
+                *self = Ex::from(x.as_str()).c(Coef::F(r.value).to_ex(), Rl::Eqq.into());
+            }
+        }
+    }
+
+    /// Evaluate to a float, reading free variables from `env`
+    ///
+    /// - `None` if anything is left that has no real value
+    pub fn flt(&self, env: &[(&str, f64)]) -> Option<f64> {
+        match self {
+            Ex::Val(v) => match v {
+                Val::Num(n) => Some(n.flt()),
+                Val::Var(x) => env.iter().find(|(y, _)| x == y).map(|(_, v)| *v),
+                Val::Bool(_) => None,
+                Val::Tok(Tok::E) => Some(E),
+                Val::Tok(Tok::Pi) => Some(PI),
+                Val::Tok(Tok::Inf) => Some(f64::INFINITY),
+                Val::Tok(Tok::I) => None,
+            },
+            Ex::Neg(a) => Some(-a.flt(env)?),
+            Ex::Infix(a, Bin::Op(op), b) => {
+                let (a, b) = (a.flt(env)?, b.flt(env)?);
+                Some(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Pow => a.powf(b),
+                    Op::Mod => a.rem_euclid(b),
+                })
+            }
+            Ex::Infix(_, Bin::Rl(_), _) => None,
+            Ex::Fn(f) => match f {
+                Fnc::Ln(a) => Some(a.flt(env)?.ln()),
+                Fnc::Sin(a) => Some(a.flt(env)?.sin()),
+                Fnc::Cos(a) => Some(a.flt(env)?.cos()),
+                Fnc::Tan(a) => Some(a.flt(env)?.tan()),
+                Fnc::Atan(a) => Some(a.flt(env)?.atan()),
+                Fnc::Norm(a) if matches!(**a, Ex::Mat(_)) => piecewise::entries(a)
+                    .into_iter()
+                    .map(|x| x.flt(env).map(|v| v * v))
+                    .sum::<Option<f64>>()
+                    .map(f64::sqrt),
+                Fnc::Abs(a)
+                | Fnc::Floor(a)
+                | Fnc::Ceil(a)
+                | Fnc::Round(a)
+                | Fnc::Frac(a)
+                | Fnc::Sign(a)
+                | Fnc::Norm(a) => piecewise::flt(f, a.flt(env)?),
+                Fnc::D(..) | Fnc::Int(..) => match self.simplified() {
+                    Ex::Fn(Fnc::D(..) | Fnc::Int(..)) => None,
+                    e => e.flt(env),
+                },
+                Fnc::DefInt(a, x, lo, hi) | Fnc::NInt(a, x, lo, hi) => {
+                    let (x, lo, hi) = (x.var()?, lo.flt(env)?, hi.flt(env)?);
+                    let f = |v: f64| {
+                        let mut env = env.to_vec();
+                        env.insert(0, (x, v));
+                        a.flt(&env).unwrap_or(f64::NAN)
+                    };
+                    let q = quadrature::integrate(f, lo, hi, 1e-10);
+                    q.converged.then_some(q.value)
+                }
+                Fnc::Factor(a)
+                | Fnc::Expand(a)
+                | Fnc::ExpandWith(a, _)
+                | Fnc::TExpand(a)
+                | Fnc::TCollect(a)
+                | Fnc::Approx(a)
+                | Fnc::ApproxTo(a, _)
+                | Fnc::PropFrac(a, _) => a.flt(env),
+                Fnc::Quo(..) | Fnc::Rem(..) | Fnc::Gcd(..) | Fnc::Lcm(..) => None,
+                Fnc::Lim(..)
+                | Fnc::LimSide(..)
+                | Fnc::Taylor(..)
+                | Fnc::Sum(..)
+                | Fnc::Prod(..) => match self.simplified() {
+                    Ex::Fn(
+                        Fnc::Lim(..)
+                        | Fnc::LimSide(..)
+                        | Fnc::Taylor(..)
+                        | Fnc::Sum(..)
+                        | Fnc::Prod(..),
+                    ) => None,
+                    e => e.flt(env),
+                },
+                Fnc::Solve(..)
+                | Fnc::CSolve(..)
+                | Fnc::NSolve(..)
+                | Fnc::Zeros(..)
+                | Fnc::DeSolve(..)
+                | Fnc::DeSolveIvp(..)
+                | Fnc::NDeSolve(..) => None,
+            },
+            Ex::Mat(_) | Ex::Invalid => None,
+        }
     }
 }
 
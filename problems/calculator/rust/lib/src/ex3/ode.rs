@@ -0,0 +1,371 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{
+    ast::*,
+    rational::*,
+    rk45,
+    search::{Budget, Cost},
+    simplify::*,
+    solve::{coeffs, Domain},
+};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Constants of integration, in the order initial conditions fix them, never
+/// produced by the parser
+const CONSTANTS: [&str; 2] = ["@1", "@2"];
+
+/// Error tolerance of [`Ex::nde_solve`]
+const TOL: f64 = 1e-10;
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Solve the ordinary differential equation `self` for `y` as a function
+    /// of `x`, where `y'` and `y''` are written as primed variables
+    ///
+    /// - First order linear equations by an integrating factor
+    /// - Separable first order equations, left implicit if the result can
+    ///   not be solved for `y`
+    /// - Second order linear equations with constant coefficients, by
+    ///   variation of parameters when there is a right side
+    /// - `init` holds `x0`, `y(x0)` and optionally `y'(x0)`, fixing the
+    ///   constants `@1` and `@2` in that order
+    pub fn de_solve(&self, x: &str, y: &str, init: &[Ex]) -> Option<Ex> {
+        let (d1, d2) = (format!("{}'", y), format!("{}''", y));
+        let f = residual(self);
+        let general = if f.has(&d2) {
+            second(&f, x, y, &d1, &d2)?
+        } else if f.has(&d1) {
+            first(&f, x, y, &d1)?
+        } else {
+            return None;
+        };
+        match init {
+            [] => Some(general),
+            [x0, rest @ ..] => conditions(&general, x, y, x0, rest),
+        }
+    }
+
+    /// Numeric solution of the ordinary differential equation `self` from
+    /// `init = [x0, y(x0), y'(x0)..]` to `x1`
+    ///
+    /// - The highest derivative must be solvable for
+    /// - Adaptive RK45 steps, see [`rk45::integrate`]
+    pub fn nde_solve(&self, x: &str, y: &str, init: &[f64], x1: f64) -> Option<rk45::Trajectory> {
+        let names = [y.to_owned(), format!("{}'", y), format!("{}''", y)];
+        let f = residual(self);
+        let order = (1..names.len()).rev().find(|&n| f.has(&names[n]))?;
+        let (x0, y0) = init.split_first()?;
+        if y0.len() != order {
+            return None;
+        }
+        let g = root(&f, &names[order])?;
+        let rhs = |t: f64, u: &[f64]| {
+            let mut env = vec![(x, t)];
+            env.extend(names.iter().map(String::as_str).zip(u.iter().copied()));
+            let mut du = u[1..].to_vec();
+            du.push(g.flt(&env).unwrap_or(f64::NAN));
+            du
+        };
+        Some(rk45::integrate(rhs, *x0, y0, x1, TOL))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// `y' = g(x, y)`
+fn first(f: &Ex, x: &str, y: &str, d1: &str) -> Option<Ex> {
+    let g = root(f, d1)?;
+    if g.has(d1) {
+        return None;
+    }
+    let c = Ex::from(CONSTANTS[0]);
+
+    // y' + p y = q
+    if let Some([q, p]) = coeffs(&g, y).as_deref() {
+        if !p.has(y) && !q.has(y) {
+            let p = product(&[Ex::from(-1), p.clone()]);
+            let mu = power(Ex::from(Tok::E), p.integrate(x)?);
+            let inner = product(&[mu.clone(), q.clone()]).integrate(x)?;
+            let sol = product(&[sum(&[inner, c]), power(mu, Ex::from(-1))]);
+            return Some(explicit(y, sol));
+        }
+    }
+
+    // y' = X(x) Y(y)
+    let (mut xs, mut ys) = (vec![], vec![]);
+    let p = Product::of(&g);
+    xs.push(p.coef.to_ex());
+    for (b, e) in p.factors {
+        match (b.has(x) || e.has(x), b.has(y) || e.has(y)) {
+            (_, false) => xs.push(power(b, e)),
+            (false, true) => ys.push(power(b, e)),
+            (true, true) => return None,
+        }
+    }
+    let lhs = power(product(&ys), Ex::from(-1)).integrate(y)?;
+    let rhs = sum(&[product(&xs).integrate(x)?, c]);
+    let eq = lhs.c(rhs, Rl::Eqq.into());
+    Some(match root(&eq, y) {
+        Some(r) => explicit(y, r),
+        None => eq,
+    })
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// `a y'' + b y' + c y = r(x)` with constant `a`, `b` and `c`
+fn second(f: &Ex, x: &str, y: &str, d1: &str, d2: &str) -> Option<Ex> {
+    let linear = |e: &Ex, v: &str| match coeffs(e, v)?.as_slice() {
+        [rest, k] => Some((rest.clone(), k.clone())),
+        [rest] => Some((rest.clone(), Ex::from(0))),
+        _ => None,
+    };
+    let (rest, a) = linear(f, d2)?;
+    let (rest, b) = linear(&rest, d1)?;
+    let (rest, c) = linear(&rest, y)?;
+    let vars = [x, y, d1, d2];
+    if a.is_zero() || [&a, &b, &c].iter().any(|k| vars.iter().any(|v| k.has(v))) {
+        return None;
+    }
+    if vars[1..].iter().any(|v| rest.has(v)) {
+        return None;
+    }
+    let r = product(&[Ex::from(-1), rest]);
+
+    // roots of a m^2 + b m + c, as m = alpha ± beta
+    let disc = sum(&[
+        power(b.clone(), Ex::from(2)),
+        product(&[Ex::from(-4), a.clone(), c]),
+    ]);
+    let half = power(product(&[Ex::from(2), a]), Ex::from(-1));
+    let alpha = product(&[Ex::from(-1), b, half.clone()]);
+    let exp = |m: Ex| power(Ex::from(Tok::E), product(&[m, Ex::from(x)]));
+    let (y1, y2, w) = match disc.flt(&[]) {
+        Some(d) if d > 0.0 => {
+            let beta = product(&[power(disc, Rat::new(1, 2).to_ex()), half.clone()]);
+            let m1 = sum(&[alpha.clone(), product(&[Ex::from(-1), beta.clone()])]);
+            let m2 = sum(&[alpha.clone(), beta.clone()]);
+            let w = product(&[Ex::from(2), beta, exp(product(&[Ex::from(2), alpha]))]);
+            (exp(m1), exp(m2), w)
+        }
+        Some(d) if d < 0.0 => {
+            let neg = product(&[Ex::from(-1), disc]);
+            let beta = product(&[power(neg, Rat::new(1, 2).to_ex()), half.clone()]);
+            let bx = product(&[beta.clone(), Ex::from(x)]);
+            let y1 = product(&[exp(alpha.clone()), Fnc::Cos(Box::new(bx.clone())).into()]);
+            let y2 = product(&[exp(alpha.clone()), Fnc::Sin(Box::new(bx)).into()]);
+            let w = product(&[beta, exp(product(&[Ex::from(2), alpha]))]);
+            (y1, y2, w)
+        }
+        Some(_) => {
+            let y1 = exp(alpha.clone());
+            let y2 = product(&[Ex::from(x), y1.clone()]);
+            (y1, y2, exp(product(&[Ex::from(2), alpha])))
+        }
+        None => return None,
+    };
+
+    let mut sol = vec![
+        product(&[Ex::from(CONSTANTS[0]), y1.clone()]),
+        product(&[Ex::from(CONSTANTS[1]), y2.clone()]),
+    ];
+    if !r.is_zero() {
+        // y_p = y2 ∫ y1 r / (a W) - y1 ∫ y2 r / (a W), with a folded into r
+        let r = product(&[r, half, Ex::from(2), power(w, Ex::from(-1))]);
+        let u1 = product(&[y2.clone(), r.clone()]).integrate(x)?;
+        let u2 = product(&[y1.clone(), r]).integrate(x)?;
+        sol.push(product(&[Ex::from(-1), y1, u1]));
+        sol.push(product(&[y2, u2]));
+    }
+    Some(explicit(y, sum(&sol)))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Fix the constants of `general` with `y(x0) = values[0]` and
+/// `y'(x0) = values[1]`
+///
+/// - The first condition also works on an implicit solution, which is then
+///   solved for `y` if a single branch passes through `(x0, y0)`
+fn conditions(general: &Ex, x: &str, y: &str, x0: &Ex, values: &[Ex]) -> Option<Ex> {
+    let constants: Vec<&str> = CONSTANTS.into_iter().filter(|c| general.has(c)).collect();
+    if values.is_empty() || values.len() > constants.len() {
+        return None;
+    }
+    let at = |e: &Ex| e.subs(x, x0).simplified();
+
+    let c = root(&at(&general.subs(y, &values[0])), constants[0])?;
+    let mut sol = general.subs(constants[0], &c).simplified();
+    if let Some(v) = values.get(1) {
+        let rhs = explicit_rhs(&sol, y)?;
+        let c = root(
+            &at(&rhs.derivative(x)).c(v.clone(), Rl::Eqq.into()),
+            constants[1],
+        )?;
+        sol = sol.subs(constants[1], &c).simplified();
+    }
+    if explicit_rhs(&sol, y).is_some() {
+        return Some(sol);
+    }
+
+    // pick the branch through the initial point
+    let branches = roots(&sol, y)?;
+    let y0 = values[0].flt(&[])?;
+    let mut through = branches.into_iter().filter(|r| {
+        at(r)
+            .flt(&[])
+            .is_some_and(|v| (v - y0).abs() <= 1e-9 * (1.0 + y0.abs()))
+    });
+    match (through.next(), through.next()) {
+        (Some(r), None) => Some(explicit(y, r)),
+        _ => Some(sol),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// `y = e`, in the simplest form a short search finds
+///
+/// - Clears leftovers such as `cos(x)^2 + sin(x)^2` from variation of
+///   parameters
+fn explicit(y: &str, e: Ex) -> Ex {
+    let e = e.search(&Cost::default(), &Budget::default()).best;
+    Ex::from(y).c(e, Rl::Eqq.into())
+}
+
+/// `e` of a solution `y = e`
+fn explicit_rhs<'a>(sol: &'a Ex, y: &str) -> Option<&'a Ex> {
+    match sol {
+        Ex::Infix(a, Bin::Rl(Rl::Eqq), e) if a.var() == Some(y) && !e.has(y) => Some(e),
+        _ => None,
+    }
+}
+
+/// Left side minus right side of an equation
+fn residual(e: &Ex) -> Ex {
+    match e {
+        Ex::Infix(_, Bin::Rl(Rl::Prop), _) => residual(&e.as_equation()),
+        Ex::Infix(a, Bin::Rl(Rl::Eqq), b) => {
+            sum(&[a.simplified(), product(&[Ex::from(-1), b.simplified()])])
+        }
+        _ => e.simplified(),
+    }
+}
+
+/// Every real solution of `e` for `v`
+fn roots(e: &Ex, v: &str) -> Option<Vec<Ex>> {
+    fn walk(e: Ex, v: &str, out: &mut Vec<Ex>) -> Option<()> {
+        match e {
+            Ex::Infix(a, Bin::Rl(Rl::Or), b) => {
+                walk(*a, v, out)?;
+                walk(*b, v, out)
+            }
+            Ex::Infix(a, Bin::Rl(Rl::Eqq), r) if a.var() == Some(v) => {
+                out.push(*r);
+                Some(())
+            }
+            _ => None,
+        }
+    }
+    let mut out = vec![];
+    walk(e.solve(v, Domain::Real)?, v, &mut out)?;
+    Some(out)
+}
+
+/// The only real solution of `e` for `v`
+fn root(e: &Ex, v: &str) -> Option<Ex> {
+    match roots(e, v)?.as_slice() {
+        [r] => Some(r.clone()),
+        _ => None,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::*;
+    use crate::ex3::testing::simp;
+
+    fn text(s: &str) -> String {
+        simp(s).to_text()
+    }
+
+    #[test]
+    fn ex3_ode_first_order() {
+        assert_eq!(text("deSolve(y' = y, x, y)"), "y = e^x*@1");
+        assert_eq!(text("deSolve(y' = 2*x, x, y, [0, 3])"), "y = x^2 + 3");
+        assert_eq!(text("deSolve(y' = -y/x, x, y)"), "y = @1/x");
+        assert_eq!(text("deSolve(y' = y^2, x, y)"), "y = -(1/(@1 + x))");
+        assert_eq!(
+            simp("deSolve(y' = x/y, x, y, [0, 1])"),
+            simp("y = (x^2 + 1)^(1/2)")
+        );
+        assert_eq!(
+            simp("deSolve(y' + y = 1, x, y, [0, 0])"),
+            simp("y = 1 - e^(-x)")
+        );
+        assert_eq!(text("deSolve(y' = x/y, x, y)"), "y^2/2 = x^2/2 + @1");
+    }
+
+    #[test]
+    fn ex3_ode_second_order() {
+        assert_eq!(
+            simp("deSolve(y'' + y = 0, x, y, [0, 0, 1])"),
+            simp("y = sin(x)")
+        );
+        assert_eq!(
+            simp("deSolve(y'' - 3*y' + 2*y = 0, x, y, [0, 2, 3])"),
+            simp("y = e^x + e^(2*x)")
+        );
+        assert_eq!(
+            simp("deSolve(y'' - 2*y' + y = 0, x, y, [0, 1, 2])"),
+            simp("y = (x + 1)*e^x")
+        );
+        assert_eq!(
+            simp("deSolve(y'' = 2, x, y, [0, 1, 0])"),
+            simp("y = x^2 + 1")
+        );
+        assert_eq!(
+            text("deSolve(y'' + y = x, x, y)"),
+            "y = x + @1*cos(x) + @2*sin(x)"
+        );
+        assert_eq!(
+            text("deSolve(y'' - y = e^(2*x), x, y)"),
+            "y = e^x*@2 + e^(-x)*@1 + e^(2*x)/3"
+        );
+    }
+
+    #[test]
+    fn ex3_ode_numeric() {
+        // no closed form, left for nDeSolve
+        assert!(matches!(
+            simp("deSolve(y' = sin(x*y), x, y)"),
+            Ex::Fn(Fnc::DeSolve(..))
+        ));
+        let Ex::Mat(rows) = simp("nDeSolve(y'' = -y, x, y, [0, 0, 1], 1)") else {
+            panic!("no table");
+        };
+        let Some(Ex::Mat(last)) = rows.last() else {
+            panic!("no rows");
+        };
+        assert_eq!(last[0].flt(&[]), Some(1.0));
+        assert!((last[1].flt(&[]).unwrap() - 1f64.sin()).abs() < 1e-8);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -2,10 +2,16 @@
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
 
-use pest::{iterators::Pairs, pratt_parser::PrattParser};
+use std::str::FromStr;
+
+use pest::{
+    iterators::{Pair, Pairs},
+    pratt_parser::PrattParser,
+    Parser,
+};
 use pest_derive::Parser;
 
-use super::{ast::*, helper::*};
+use super::{ast::*, helper::*, symbols};
 
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
@@ -25,10 +31,28 @@ lazy_static::lazy_static! {
         // Precedence is defined lowest to highest
         PrattParser::new()
         .op(Op::infix(r#where, Left))
+        .op(Op::infix(iff, Left))
+        .op(
+            Op::infix(implied, Right) | Op::infix(implies, Right)
+            | Op::infix(to, Right) | Op::infix(mapsto, Right)
+        )
+        .op(Op::infix(or, Left))
         .op(
             Op::infix(eq, Left) | Op::infix(neq, Left)
             | Op::infix(ltt, Left) | Op::infix(leq, Left)
             | Op::infix(gtt, Left) | Op::infix(geq, Left)
+            | Op::infix(equiv, Left) | Op::infix(cong, Left)
+            | Op::infix(sim, Left) | Op::infix(simeq, Left)
+            | Op::infix(approxeq, Left) | Op::infix(asymp, Left)
+            | Op::infix(prop, Left)
+            | Op::infix(nequiv, Left) | Op::infix(ncong, Left)
+            | Op::infix(nsim, Left) | Op::infix(napprox, Left)
+            | Op::infix(nltt, Left) | Op::infix(nleq, Left)
+            | Op::infix(ngtt, Left) | Op::infix(ngeq, Left)
+            | Op::infix(elem, Left) | Op::infix(nelem, Left)
+            | Op::infix(subset, Left) | Op::infix(subseteq, Left)
+            | Op::infix(supset, Left) | Op::infix(supseteq, Left)
+            | Op::infix(nsubset, Left) | Op::infix(nsubseteq, Left)
         )
         .op(Op::infix(add, Left) | Op::infix(sub, Left))
         .op(Op::infix(mul, Left) | Op::infix(div, Left) | Op::infix(r#mod, Left))
@@ -45,14 +69,150 @@ impl<'a> From<Pairs<'a, Rule>> for Ex {
         PRATT_PARSER
             .map_primary(|primary| match primary.as_rule() {
                 Rule::tok => todo!(),
-                Rule::int => Ex::from(primary.as_str().parse::<i32>().unwrap()),
+                Rule::bool => Ex::from(primary.as_str() == "true"),
+                Rule::undef => Ex::Invalid,
+                // in range, see `in_range`
+                Rule::int => Ex::from(primary.as_str().parse::<i128>().unwrap()),
                 Rule::flt => Ex::from(primary.as_str().parse::<f64>().unwrap()),
-                Rule::var => Ex::from(primary.as_str()),
+                Rule::var => Ex::from(symbols::ascii(primary.as_str()).as_str()),
+                Rule::sqrt => Ex::from(primary.into_inner()).pow(Ex::from(1) / Ex::from(2)),
                 Rule::sin => sin(Ex::from(primary.into_inner())),
                 Rule::cos => cos(Ex::from(primary.into_inner())),
                 Rule::tan => tan(Ex::from(primary.into_inner())),
                 Rule::ln => ln(Ex::from(primary.into_inner())),
-                Rule::expr => Ex::from(primary.into_inner()),
+                Rule::atan => atan(Ex::from(primary.into_inner())),
+                Rule::abs => abs(Ex::from(primary.into_inner())),
+                Rule::floor => floor(Ex::from(primary.into_inner())),
+                Rule::ceil => ceil(Ex::from(primary.into_inner())),
+                Rule::round => round(Ex::from(primary.into_inner())),
+                Rule::frac => frac(Ex::from(primary.into_inner())),
+                Rule::sign => sign(Ex::from(primary.into_inner())),
+                Rule::norm => norm(Ex::from(primary.into_inner())),
+                Rule::d => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    d(args.next().unwrap(), args.next().unwrap())
+                }
+                Rule::integral => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let (a, x) = (args.next().unwrap(), args.next().unwrap());
+                    match (args.next(), args.next()) {
+                        (Some(lo), Some(hi)) => defint(a, x, lo, hi),
+                        _ => int(a, x),
+                    }
+                }
+                Rule::nint => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    nint(arg(), arg(), arg(), arg())
+                }
+                Rule::solve => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    solve(args.next().unwrap(), args.next().unwrap())
+                }
+                Rule::csolve => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    csolve(args.next().unwrap(), args.next().unwrap())
+                }
+                Rule::nsolve => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    nsolve(arg(), arg(), arg())
+                }
+                Rule::zeros => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    zeros(arg(), arg(), arg(), arg())
+                }
+                Rule::factor => factor(Ex::from(primary.into_inner())),
+                Rule::texpand => texpand(Ex::from(primary.into_inner())),
+                Rule::tcollect => tcollect(Ex::from(primary.into_inner())),
+                Rule::expand => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let a = args.next().unwrap();
+                    match args.next() {
+                        Some(opts) => expand_with(a, opts),
+                        None => expand(a),
+                    }
+                }
+                Rule::approx => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let a = args.next().unwrap();
+                    match args.next() {
+                        Some(digits) => approx_to(a, digits),
+                        None => approx(a),
+                    }
+                }
+                Rule::quo => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    quo(arg(), arg(), arg())
+                }
+                Rule::rem => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    rem(arg(), arg(), arg())
+                }
+                Rule::propfrac => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    propfrac(args.next().unwrap(), args.next().unwrap())
+                }
+                Rule::gcd => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    poly_gcd(args.next().unwrap(), args.next().unwrap())
+                }
+                Rule::lcm => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    poly_lcm(args.next().unwrap(), args.next().unwrap())
+                }
+                Rule::lim => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let (a, x, p) = (
+                        args.next().unwrap(),
+                        args.next().unwrap(),
+                        args.next().unwrap(),
+                    );
+                    match args.next() {
+                        Some(side) => lim_side(a, x, p, side),
+                        None => lim(a, x, p),
+                    }
+                }
+                Rule::taylor => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let (a, x, n) = (
+                        args.next().unwrap(),
+                        args.next().unwrap(),
+                        args.next().unwrap(),
+                    );
+                    taylor(a, x, n, args.next().unwrap_or(Ex::from(0)))
+                }
+                Rule::sum => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    sum_of(arg(), arg(), arg(), arg())
+                }
+                Rule::prod => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    prod_of(arg(), arg(), arg(), arg())
+                }
+                Rule::desolve => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let (a, x, y) = (
+                        args.next().unwrap(),
+                        args.next().unwrap(),
+                        args.next().unwrap(),
+                    );
+                    match args.next() {
+                        Some(init) => desolve_ivp(a, x, y, init),
+                        None => desolve(a, x, y),
+                    }
+                }
+                Rule::ndesolve => {
+                    let mut args = primary.into_inner().map(|x| Ex::from(x.into_inner()));
+                    let mut arg = || args.next().unwrap();
+                    ndesolve(arg(), arg(), arg(), arg(), arg())
+                }
+                Rule::expr | Rule::bars => Ex::from(primary.into_inner()),
                 Rule::matrix => Ex::Mat(
                     primary
                         .into_inner()
@@ -87,7 +247,37 @@ impl<'a> From<Pairs<'a, Rule>> for Ex {
                 Rule::leq => lhs.c(rhs, Rl::Leq.into()),
                 Rule::gtt => lhs.c(rhs, Rl::Gtt.into()),
                 Rule::geq => lhs.c(rhs, Rl::Geq.into()),
+                Rule::equiv => lhs.c(rhs, Rl::Equiv.into()),
+                Rule::cong => lhs.c(rhs, Rl::Cong.into()),
+                Rule::sim => lhs.c(rhs, Rl::Sim.into()),
+                Rule::simeq => lhs.c(rhs, Rl::SimEq.into()),
+                Rule::approxeq => lhs.c(rhs, Rl::Approx.into()),
+                Rule::asymp => lhs.c(rhs, Rl::Asymp.into()),
+                Rule::prop => lhs.c(rhs, Rl::Prop.into()),
+                Rule::nequiv => lhs.c(rhs, Rl::NEquiv.into()),
+                Rule::ncong => lhs.c(rhs, Rl::NCong.into()),
+                Rule::nsim => lhs.c(rhs, Rl::NSim.into()),
+                Rule::napprox => lhs.c(rhs, Rl::NApprox.into()),
+                Rule::nltt => lhs.c(rhs, Rl::NLtt.into()),
+                Rule::nleq => lhs.c(rhs, Rl::NLeq.into()),
+                Rule::ngtt => lhs.c(rhs, Rl::NGtt.into()),
+                Rule::ngeq => lhs.c(rhs, Rl::NGeq.into()),
+                Rule::elem => lhs.c(rhs, Rl::In.into()),
+                Rule::nelem => lhs.c(rhs, Rl::NotIn.into()),
+                Rule::subset => lhs.c(rhs, Rl::Sub.into()),
+                Rule::subseteq => lhs.c(rhs, Rl::SubEq.into()),
+                Rule::supset => lhs.c(rhs, Rl::Sup.into()),
+                Rule::supseteq => lhs.c(rhs, Rl::SupEq.into()),
+                Rule::nsubset => lhs.c(rhs, Rl::NSub.into()),
+                Rule::nsubseteq => lhs.c(rhs, Rl::NSubEq.into()),
+
+                Rule::implied => lhs.c(rhs, Rl::If.into()),
+                Rule::to => lhs.c(rhs, Rl::To.into()),
+                Rule::implies => lhs.c(rhs, Rl::Implies.into()),
+                Rule::mapsto => lhs.c(rhs, Rl::MapsTo.into()),
+                Rule::iff => lhs.c(rhs, Rl::Iff.into()),
 
+                Rule::or => lhs.c(rhs, Rl::Or.into()),
                 Rule::r#where => lhs.c(rhs, Rl::Where.into()),
                 _ => unreachable!(),
             })
@@ -95,6 +285,30 @@ impl<'a> From<Pairs<'a, Rule>> for Ex {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+
+impl FromStr for Ex {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut pairs = ExParser::parse(Rule::program, value).map_err(|e| e.to_string())?;
+        // the program holds the expression and the end of input
+        let expr = pairs.next().unwrap().into_inner().next().unwrap();
+        in_range(expr.clone().into_inner())?;
+        Ok(Ex::from(expr.into_inner()))
+    }
+}
+
+/// Reject integer literals beyond an `i128`, which no [`Num`] holds exactly
+pub fn in_range(pairs: Pairs<Rule>) -> Result<(), String> {
+    let too_large =
+        |p: &Pair<Rule>| p.as_rule() == Rule::int && p.as_str().parse::<i128>().is_err();
+    match pairs.flatten().find(too_large) {
+        Some(p) => Err(format!("integer {} is out of range", p.as_str())),
+        None => Ok(()),
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 //---------------------------------------------------------------------------//
 ///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,335 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, rational::*, simplify::*, trig};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Distance from a jump within which the float value of an exact constant
+/// does not decide a step function
+const TOL: f64 = 1e-9;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Reduce `abs`, `sign`, `floor`, `ceil`, `round`, `frac` or `norm` of a
+/// simplified argument
+///
+/// - Numbers are evaluated, to floats only from floats, and other real
+///   constants by their float value where it is clear of a jump
+/// - `abs(c*x) = |c|*abs(x)` and `sign(c*x) = sign(c)*sign(x)`, so
+///   `abs(-x) = abs(x)`
+/// - `abs(x^(2k)) = x^(2k)` and `abs(abs(x)) = abs(x)` for real `x`
+/// - Integers move out of `floor`, `ceil` and `round` and drop from `frac`,
+///   and `floor(-x) = -ceil(x)`
+/// - `round` takes halves away from zero, `frac(x) = x - floor(x)`
+/// - `norm` of a vector or matrix is the square root of the sum of the
+///   squares of its real entries, of anything else `abs`
+/// - `None` if nothing applies
+pub fn simplify(f: &Fnc) -> Option<Ex> {
+    match f {
+        Fnc::Abs(a) => abs(a),
+        Fnc::Sign(a) => sign(a),
+        Fnc::Floor(a) | Fnc::Ceil(a) | Fnc::Round(a) => step(f, a),
+        Fnc::Frac(a) => frac(a),
+        Fnc::Norm(a) => Some(norm(a)),
+        _ => None,
+    }
+}
+
+/// Float value of the step function `f` at `v`
+pub fn flt(f: &Fnc, v: f64) -> Option<f64> {
+    Some(match f {
+        Fnc::Abs(_) | Fnc::Norm(_) => v.abs(),
+        Fnc::Sign(_) if v == 0.0 => 0.0,
+        Fnc::Sign(_) => v.signum(),
+        Fnc::Floor(_) => v.floor(),
+        Fnc::Ceil(_) => v.ceil(),
+        Fnc::Round(_) => v.round(),
+        Fnc::Frac(_) => v - v.floor(),
+        _ => return None,
+    })
+}
+
+/// Entries of a vector or matrix, rows flattened
+pub fn entries(e: &Ex) -> Vec<&Ex> {
+    match e {
+        Ex::Mat(xs) => xs.iter().flat_map(entries).collect(),
+        _ => vec![e],
+    }
+}
+
+/// Whether `f` jumps where its argument is `v`
+pub fn jumps(f: &Fnc, v: f64) -> bool {
+    let whole = |w: f64| (w - w.round()).abs() < TOL;
+    match f {
+        Fnc::Floor(_) | Fnc::Ceil(_) | Fnc::Frac(_) => whole(v),
+        Fnc::Round(_) => whole(v - 0.5),
+        Fnc::Sign(_) => v == 0.0,
+        _ => false,
+    }
+}
+
+/// Value of `f` just above the jump at `v` if `above`, just below otherwise
+pub fn side(f: &Fnc, v: &Ex, above: bool) -> Ex {
+    let shift = |q: Rat| sum(&[v.clone(), q.to_ex()]);
+    let half = Rat::new(1, 2);
+    match (f, above) {
+        (Fnc::Floor(_), true) | (Fnc::Ceil(_), false) => v.clone(),
+        (Fnc::Floor(_), false) => shift(-Rat::ONE),
+        (Fnc::Ceil(_), true) => shift(Rat::ONE),
+        (Fnc::Round(_), true) => shift(half),
+        (Fnc::Round(_), false) => shift(-half),
+        (Fnc::Frac(_), true) => Ex::from(0),
+        (Fnc::Frac(_), false) => Ex::from(1),
+        (_, true) => Ex::from(1),
+        (_, false) => Rat::int(-1).to_ex(),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+fn abs(a: &Ex) -> Option<Ex> {
+    if let Some(c) = Coef::of(a) {
+        return Some(c.abs().to_ex());
+    }
+    match real_sign(a) {
+        // subtracting from zero multiplies out `-(1 - pi)`
+        Some(-1) => return Some((Ex::from(0) - a.clone()).simplified()),
+        Some(_) => return Some(a.clone()),
+        None => {}
+    }
+    match a {
+        Ex::Fn(Fnc::Abs(_) | Fnc::Norm(_)) => return Some(a.clone()),
+        Ex::Infix(_, Bin::Op(Op::Pow), k) if even(k) => return Some(a.clone()),
+        _ => {}
+    }
+    let (c, rest) = coefficient(a)?;
+    Some(product(&[
+        c.abs().to_ex(),
+        Ex::from(Fnc::Abs(Box::new(rest))).simplified(),
+    ]))
+}
+
+fn sign(a: &Ex) -> Option<Ex> {
+    match Coef::of(a) {
+        Some(Coef::Q(q)) => return Some(Rat::int(q.numer().signum()).to_ex()),
+        Some(Coef::F(0.0)) => return Some(Ex::from(0.0)),
+        Some(Coef::F(v)) => return Some(Coef::F(v.signum()).to_ex()),
+        None => {}
+    }
+    if let Some(s) = real_sign(a) {
+        return Some(Rat::int(s as i128).to_ex());
+    }
+    let (c, rest) = coefficient(a)?;
+    let s = if c.is_neg() { -1 } else { 1 };
+    Some(product(&[
+        Ex::from(s),
+        Ex::from(Fnc::Sign(Box::new(rest))).simplified(),
+    ]))
+}
+
+/// `floor`, `ceil` or `round`
+fn step(f: &Fnc, a: &Ex) -> Option<Ex> {
+    match Coef::of(a) {
+        Some(Coef::Q(q)) => return Some(exact(f, q).to_ex()),
+        Some(Coef::F(v)) => return Some(Coef::F(flt(f, v)?).to_ex()),
+        None => {}
+    }
+    if integral(a) {
+        return Some(a.clone());
+    }
+    if let Some(v) = a.flt(&[]).filter(|v| v.is_finite() && !jumps(f, *v)) {
+        return Some(Rat::int(flt(f, v)? as i128).to_ex());
+    }
+    if let Some((n, rest)) = integer_part(a) {
+        let g = f.map(|_| rest.clone());
+        return Some(sum(&[n.to_ex(), Ex::from(g).simplified()]));
+    }
+    if trig::negated(a) {
+        let b = Box::new(product(&[Ex::from(-1), a.clone()]));
+        let g = match f {
+            Fnc::Floor(_) => Fnc::Ceil(b),
+            Fnc::Ceil(_) => Fnc::Floor(b),
+            _ => Fnc::Round(b),
+        };
+        return Some(product(&[Ex::from(-1), Ex::from(g).simplified()]));
+    }
+    None
+}
+
+fn frac(a: &Ex) -> Option<Ex> {
+    let f = Fnc::Frac(Box::new(a.clone()));
+    match Coef::of(a) {
+        Some(Coef::Q(q)) => return Some(exact(&f, q).to_ex()),
+        Some(Coef::F(v)) => return Some(Coef::F(flt(&f, v)?).to_ex()),
+        None => {}
+    }
+    if integral(a) {
+        return Some(Ex::from(0));
+    }
+    if let Some(v) = a.flt(&[]).filter(|v| v.is_finite() && !jumps(&f, *v)) {
+        return Some(sum(&[a.clone(), Rat::int(-v.floor() as i128).to_ex()]));
+    }
+    let (_, rest) = integer_part(a)?;
+    Some(Ex::from(Fnc::Frac(Box::new(rest))).simplified())
+}
+
+fn norm(a: &Ex) -> Ex {
+    match a {
+        Ex::Mat(_) => {
+            let squares: Vec<Ex> = entries(a)
+                .into_iter()
+                .map(|x| power(x.clone(), Ex::from(2)))
+                .collect();
+            power(sum(&squares), Rat::new(1, 2).to_ex())
+        }
+        _ => Ex::from(Fnc::Abs(Box::new(a.clone()))).simplified(),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Exact value of a step function at a rational
+fn exact(f: &Fnc, q: Rat) -> Rat {
+    let floor = |q: Rat| Rat::int(q.floor());
+    match f {
+        Fnc::Floor(_) => floor(q),
+        Fnc::Ceil(_) => -floor(-q),
+        Fnc::Round(_) if q.is_neg() => -floor(-q + Rat::new(1, 2)),
+        Fnc::Round(_) => floor(q + Rat::new(1, 2)),
+        _ => q - floor(q),
+    }
+}
+
+/// `1` or `-1` for a nonzero real constant that is not a number
+fn real_sign(a: &Ex) -> Option<i32> {
+    match a.flt(&[]) {
+        Some(v) if v.is_finite() && v.abs() > TOL => Some(if v < 0.0 { -1 } else { 1 }),
+        _ => None,
+    }
+}
+
+/// Numeric coefficient and the rest of a product, when the coefficient is
+/// not one
+fn coefficient(a: &Ex) -> Option<(Coef, Ex)> {
+    let p = Product::of(a);
+    if p.undefined || p.coef.is_one() || p.factors.is_empty() {
+        return None;
+    }
+    let rest: Vec<Ex> = p.factors.into_iter().map(|(b, k)| power(b, k)).collect();
+    Some((p.coef, product(&rest)))
+}
+
+/// Integer constant and the rest of a sum, when the constant is not zero
+fn integer_part(a: &Ex) -> Option<(Rat, Ex)> {
+    let s = Sum::of(a);
+    match s.constant {
+        Coef::Q(n) if n.is_int() && !n.is_zero() && !s.terms.is_empty() => {
+            Some((n, sum(&[a.clone(), (-n).to_ex()])))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `a` only takes integer values
+fn integral(a: &Ex) -> bool {
+    matches!(a, Ex::Fn(Fnc::Floor(_) | Fnc::Ceil(_) | Fnc::Round(_)))
+}
+
+fn even(k: &Ex) -> bool {
+    k.as_rat()
+        .is_some_and(|k| k.is_int() && k.numer().rem_euclid(2) == 0)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::*;
+    use crate::ex3::testing::{parse, simp};
+
+    #[test]
+    fn ex3_piecewise_parse() {
+        assert_eq!(parse("|x|"), parse("abs(x)"));
+        assert_eq!(parse("|x - 1| + |y|"), parse("abs(x - 1) + abs(y)"));
+        assert_eq!(parse("|x| - 1"), parse("abs(x) - 1"));
+        assert_eq!(parse("|x - |y||"), parse("abs(x - abs(y))"));
+        assert_eq!(parse("|x|^2 | x = -3"), parse("abs(x)^2 | x = -3"));
+        assert_eq!(parse("x^2 | x = |y|"), parse("x^2 | x = abs(y)"));
+        assert_eq!(parse("⌊x/2⌋ + ⌈x⌉"), parse("floor(x/2) + ceil(x)"));
+        assert_eq!(parse("‖[3, 4]‖"), parse("norm([3, 4])"));
+        assert_eq!(parse("|x|").to_latex(), "\\left|x\\right|");
+        assert_eq!(
+            parse("⌊x⌋ + ‖v‖").to_latex(),
+            "\\left\\lfloor x \\right\\rfloor + \\left\\| v \\right\\|"
+        );
+    }
+
+    #[test]
+    fn ex3_piecewise_simplify() {
+        assert_eq!(simp("|-3|"), simp("3"));
+        assert_eq!(simp("abs(-2.5)"), Ex::from(2.5));
+        assert_eq!(simp("abs(-x)"), simp("abs(x)"));
+        assert_eq!(simp("abs(-2*x)"), simp("2*abs(x)"));
+        assert_eq!(simp("abs(abs(x))"), simp("abs(x)"));
+        assert_eq!(simp("abs(x^2)"), simp("x^2"));
+        assert_eq!(simp("abs(1 - pi)"), simp("pi - 1"));
+        assert_eq!(simp("|x|^2 | x = -3"), simp("9"));
+        assert_eq!(simp("sign(-7/2)"), simp("-1"));
+        assert_eq!(simp("sign(-3*x)"), simp("-sign(x)"));
+        assert_eq!(simp("sign(0)"), simp("0"));
+
+        assert_eq!(simp("floor(7/2)"), simp("3"));
+        assert_eq!(simp("floor(-7/2)"), simp("-4"));
+        assert_eq!(simp("ceil(7/2)"), simp("4"));
+        assert_eq!(simp("round(5/2)"), simp("3"));
+        assert_eq!(simp("round(-5/2)"), simp("-3"));
+        assert_eq!(simp("frac(7/2)"), simp("1/2"));
+        assert_eq!(simp("frac(-1/4)"), simp("3/4"));
+        assert_eq!(simp("floor(2.7)"), Ex::from(2.0));
+        assert_eq!(simp("⌊pi⌋ + ⌈e⌉"), simp("6"));
+        assert_eq!(simp("floor(x + 2)"), simp("floor(x) + 2"));
+        assert_eq!(simp("floor(-x)"), simp("-ceil(x)"));
+        assert_eq!(simp("ceil(floor(x))"), simp("floor(x)"));
+        assert_eq!(simp("frac(x + 3)"), simp("frac(x)"));
+        assert_eq!(simp("frac(round(x))"), simp("0"));
+        assert_eq!(simp("frac(pi)"), simp("pi - 3"));
+
+        assert_eq!(simp("norm([3, 4])"), simp("5"));
+        assert_eq!(simp("‖[[1, 2], [2, 4]]‖"), simp("5"));
+        assert_eq!(simp("norm(-x)"), simp("abs(x)"));
+    }
+
+    #[test]
+    fn ex3_piecewise_calculus() {
+        assert_eq!(simp("d(abs(x^2 - 1), x)"), simp("2*x*sign(x^2 - 1)"));
+        assert_eq!(simp("d(floor(x) + frac(3*x), x)"), simp("3"));
+
+        let mut e = parse("abs(x) + floor(x) + sign(x)");
+        assert_eq!(e.flt(&[("x", -1.5)]), Some(1.5 - 2.0 - 1.0));
+        e.numeric();
+        assert_eq!(e, simp("abs(x) + floor(x) + sign(x)"));
+
+        // direct substitution stops at jumps
+        assert_eq!(simp("lim(floor(x), x, 1, 1)"), simp("1"));
+        assert_eq!(simp("lim(floor(x), x, 1, -1)"), simp("0"));
+        assert_eq!(simp("lim(floor(x), x, 1)"), simp("undef"));
+        assert_eq!(simp("lim(frac(x), x, 2, -1)"), simp("1"));
+        assert_eq!(simp("lim(x*sign(x), x, 0)"), simp("0"));
+        assert_eq!(simp("lim(sign(x), x, 0, 1)"), simp("1"));
+        assert_eq!(simp("lim(round(x), x, 1/2, -1)"), simp("0"));
+        assert_eq!(simp("lim(floor(x), x, 3/2)"), simp("1"));
+        assert_eq!(simp("lim(abs(x), x, -inf)"), simp("inf"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,412 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Largest power of a sum read from an expression, which is multiplied out
+const MAX_POWER: u32 = 64;
+
+/// Highest degree of a power read from an expression
+const MAX_DEGREE: usize = 4096;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Dense univariate polynomial with rational coefficients
+///
+/// - `c[i]` is the coefficient of `x^i`
+/// - Never has trailing zero coefficients
+/// - Arithmetic is checked and returns `None` once a coefficient leaves
+///   the range of [`Rat`]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct UPoly {
+    c: Vec<Rat>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl UPoly {
+    pub fn new(mut c: Vec<Rat>) -> Self {
+        while c.last().is_some_and(Rat::is_zero) {
+            c.pop();
+        }
+        UPoly { c }
+    }
+
+    pub fn zero() -> Self {
+        UPoly { c: vec![] }
+    }
+
+    pub fn constant(a: Rat) -> Self {
+        UPoly::new(vec![a])
+    }
+
+    /// The polynomial `x`
+    pub fn x() -> Self {
+        UPoly::new(vec![Rat::ZERO, Rat::ONE])
+    }
+
+    /// `x - r`
+    pub fn linear(r: Rat) -> Self {
+        UPoly::new(vec![-r, Rat::ONE])
+    }
+
+    pub fn coeffs(&self) -> &[Rat] {
+        &self.c
+    }
+
+    pub fn coeff(&self, i: usize) -> Rat {
+        self.c.get(i).copied().unwrap_or(Rat::ZERO)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.c.is_empty()
+    }
+
+    /// Degree, with the zero polynomial at degree 0
+    pub fn deg(&self) -> usize {
+        self.c.len().saturating_sub(1)
+    }
+
+    pub fn lead(&self) -> Rat {
+        self.c.last().copied().unwrap_or(Rat::ZERO)
+    }
+
+    pub fn scale(&self, k: Rat) -> Option<Self> {
+        let c = self.c.iter().map(|a| a.checked_mul(k));
+        Some(UPoly::new(c.collect::<Option<_>>()?))
+    }
+
+    pub fn monic(&self) -> Option<Self> {
+        match self.is_zero() {
+            true => Some(self.clone()),
+            false => self.scale(Rat::ONE.checked_div(self.lead())?),
+        }
+    }
+
+    pub fn add(&self, rhs: &Self) -> Option<Self> {
+        let n = self.c.len().max(rhs.c.len());
+        let c = (0..n).map(|i| self.coeff(i).checked_add(rhs.coeff(i)));
+        Some(UPoly::new(c.collect::<Option<_>>()?))
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Option<Self> {
+        self.add(&rhs.scale(-Rat::ONE)?)
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Option<Self> {
+        if self.is_zero() || rhs.is_zero() {
+            return Some(UPoly::zero());
+        }
+        let mut c = vec![Rat::ZERO; self.c.len() + rhs.c.len() - 1];
+        for (i, a) in self.c.iter().enumerate() {
+            for (j, b) in rhs.c.iter().enumerate() {
+                c[i + j] = c[i + j].checked_add(a.checked_mul(*b)?)?;
+            }
+        }
+        Some(UPoly::new(c))
+    }
+
+    /// `k`th power, directly for a single term
+    pub fn powi(&self, k: u32) -> Option<Self> {
+        if self.c.iter().filter(|a| !a.is_zero()).count() == 1 {
+            let d = self.deg().checked_mul(k as usize)?;
+            let mut c = vec![Rat::ZERO; d + 1];
+            c[d] = self.lead().powi(k as i128)?;
+            return Some(UPoly::new(c));
+        }
+        (0..k).try_fold(UPoly::constant(Rat::ONE), |acc, _| acc.mul(self))
+    }
+
+    /// `k`th power of a polynomial read from an expression
+    ///
+    /// - `None` past [`MAX_POWER`] for a sum, or past [`MAX_DEGREE`]
+    fn power(&self, k: i128) -> Option<Self> {
+        let k = u32::try_from(k).ok()?;
+        let sum = self.c.iter().filter(|a| !a.is_zero()).count() > 1;
+        if (sum && k > MAX_POWER) || self.deg().checked_mul(k as usize)? > MAX_DEGREE {
+            return None;
+        }
+        self.powi(k)
+    }
+
+    /// Long division, `self = q * rhs + r` with `deg r < deg rhs`
+    pub fn divrem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        assert!(!rhs.is_zero(), "polynomial division by zero");
+        let mut q = vec![Rat::ZERO; self.c.len().saturating_sub(rhs.deg())];
+        let mut r = self.clone();
+        while !r.is_zero() && r.deg() >= rhs.deg() {
+            let k = r.lead().checked_div(rhs.lead())?;
+            let shift = r.deg() - rhs.deg();
+            q[shift] = k;
+            let mut t = vec![Rat::ZERO; shift];
+            t.extend(rhs.scale(k)?.c);
+            r = r.sub(&UPoly::new(t))?;
+        }
+        Some((UPoly::new(q), r))
+    }
+
+    /// Monic greatest common divisor
+    ///
+    /// - By the primitive remainder sequence, so the coefficients stay as
+    ///   small as the remainders allow instead of growing at every step
+    pub fn gcd(&self, rhs: &Self) -> Option<Self> {
+        let (mut a, mut b) = (self.primitive()?, rhs.primitive()?);
+        while !b.is_zero() {
+            let r = a.divrem(&b)?.1;
+            (a, b) = (b, r.primitive()?);
+        }
+        a.monic()
+    }
+
+    pub fn eval(&self, x: Rat) -> Option<Rat> {
+        let mut acc = Rat::ZERO;
+        for a in self.c.iter().rev() {
+            acc = acc.checked_mul(x)?.checked_add(*a)?;
+        }
+        Some(acc)
+    }
+
+    pub fn derivative(&self) -> Option<Self> {
+        let c = self.c.iter().enumerate().skip(1);
+        let c = c.map(|(i, a)| a.checked_mul(Rat::int(i as i128)));
+        Some(UPoly::new(c.collect::<Option<_>>()?))
+    }
+
+    pub fn antiderivative(&self) -> Option<Self> {
+        let mut c = vec![Rat::ZERO];
+        for (i, a) in self.c.iter().enumerate() {
+            c.push(a.checked_div(Rat::int(i as i128 + 1))?);
+        }
+        Some(UPoly::new(c))
+    }
+
+    /// Distinct rational roots, found with the rational root theorem
+    ///
+    /// - Candidates whose evaluation overflows are skipped, so very large
+    ///   polynomials may report fewer roots than they have
+    pub fn rational_roots(&self) -> Vec<Rat> {
+        let Some(p) = self.primitive() else {
+            return vec![];
+        };
+        if p.is_zero() {
+            return vec![];
+        }
+        let mut roots = vec![];
+        if p.coeff(0).is_zero() {
+            roots.push(Rat::ZERO);
+        }
+        let low = p.c.iter().find(|a| !a.is_zero()).unwrap().numer();
+        let high = p.lead().numer();
+        if low.abs() > 1_000_000 || high.abs() > 1_000_000 {
+            return roots;
+        }
+        for n in divisors(low) {
+            for d in divisors(high) {
+                for r in [Rat::new(n, d), Rat::new(-n, d)] {
+                    if !roots.contains(&r) && p.eval(r).is_some_and(|v| v.is_zero()) {
+                        roots.push(r);
+                    }
+                }
+            }
+        }
+        roots
+    }
+
+    /// Number of distinct real roots strictly between `lo` and `hi`, by
+    /// Sturm's theorem
+    ///
+    /// - `None` for the zero polynomial or if the coefficients overflow
+    pub fn roots_between(&self, lo: Rat, hi: Rat) -> Option<usize> {
+        if self.is_zero() {
+            return None;
+        }
+        let d = self.derivative()?;
+        let p = self.divrem(&self.gcd(&d)?)?.0;
+        let mut chain = vec![p.clone(), p.derivative()?];
+        while !chain[chain.len() - 1].is_zero() {
+            let n = chain.len();
+            let r = chain[n - 2].divrem(&chain[n - 1])?.1;
+            chain.push(r.scale(-Rat::ONE)?);
+        }
+        chain.pop();
+        let changes = |v: Rat| {
+            let mut signs = vec![];
+            for q in &chain {
+                let y = q.eval(v)?;
+                if !y.is_zero() {
+                    signs.push(y.is_neg());
+                }
+            }
+            Some(signs.windows(2).filter(|w| w[0] != w[1]).count())
+        };
+        // Sturm counts the half-open interval (lo, hi]
+        let at_hi = p.eval(hi)?.is_zero() as usize;
+        Some((changes(lo)? - changes(hi)?).saturating_sub(at_hi))
+    }
+
+    /// Scale to integer coefficients with unit content and positive lead
+    pub fn primitive(&self) -> Option<Self> {
+        if self.is_zero() {
+            return Some(self.clone());
+        }
+        let mut den = 1i128;
+        for a in &self.c {
+            den = (den / gcd(den, a.denom())).checked_mul(a.denom())?;
+        }
+        let mut num = 0;
+        for a in &self.c {
+            num = gcd(num, a.checked_mul(Rat::int(den))?.numer());
+        }
+        let k = Rat::new(den, num);
+        self.scale(if self.lead().is_neg() { -k } else { k })
+    }
+
+    /// Read a polynomial in `x` with rational coefficients
+    pub fn from_ex(e: &Ex, x: &str) -> Option<Self> {
+        match e {
+            Ex::Val(Val::Var(y)) if y == x => Some(UPoly::x()),
+            Ex::Neg(a) => UPoly::from_ex(a, x)?.scale(-Rat::ONE),
+            Ex::Infix(a, Bin::Op(op), b) => {
+                let a = UPoly::from_ex(a, x)?;
+                match op {
+                    Op::Add => a.add(&UPoly::from_ex(b, x)?),
+                    Op::Sub => a.sub(&UPoly::from_ex(b, x)?),
+                    Op::Mul => a.mul(&UPoly::from_ex(b, x)?),
+                    Op::Div => a.scale(Rat::ONE.checked_div(b.as_rat()?)?),
+                    Op::Pow => a.power(b.as_rat().filter(Rat::is_int)?.numer()),
+                    Op::Mod => None,
+                }
+            }
+            _ => e.as_rat().map(UPoly::constant),
+        }
+    }
+
+    /// Read a rational function in `x` as `(numerator, denominator)`
+    pub fn ratfn_from_ex(e: &Ex, x: &str) -> Option<(Self, Self)> {
+        let one = || UPoly::constant(Rat::ONE);
+        match e {
+            Ex::Val(Val::Var(y)) if y == x => Some((UPoly::x(), one())),
+            Ex::Neg(a) => {
+                let (n, d) = UPoly::ratfn_from_ex(a, x)?;
+                Some((n.scale(-Rat::ONE)?, d))
+            }
+            Ex::Infix(a, Bin::Op(op), b) => {
+                let (an, ad) = UPoly::ratfn_from_ex(a, x)?;
+                let (bn, bd) = match op {
+                    Op::Pow => (one(), one()),
+                    _ => UPoly::ratfn_from_ex(b, x)?,
+                };
+                match op {
+                    Op::Add => Some((an.mul(&bd)?.add(&bn.mul(&ad)?)?, ad.mul(&bd)?)),
+                    Op::Sub => Some((an.mul(&bd)?.sub(&bn.mul(&ad)?)?, ad.mul(&bd)?)),
+                    Op::Mul => Some((an.mul(&bn)?, ad.mul(&bd)?)),
+                    Op::Div if bn.is_zero() => None,
+                    Op::Div => Some((an.mul(&bd)?, ad.mul(&bn)?)),
+                    Op::Pow => {
+                        let k = b.as_rat().filter(Rat::is_int)?;
+                        let e = k.numer().checked_abs()?;
+                        match k.is_neg() {
+                            false => Some((an.power(e)?, ad.power(e)?)),
+                            true if an.is_zero() => None,
+                            true => Some((ad.power(e)?, an.power(e)?)),
+                        }
+                    }
+                    Op::Mod => None,
+                }
+            }
+            _ => e.as_rat().map(|k| (UPoly::constant(k), one())),
+        }
+    }
+
+    /// Rebuild as a simplified expression in `x`, highest degree first
+    pub fn to_ex(&self, x: &str) -> Ex {
+        let terms: Vec<Ex> = self
+            .c
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| !a.is_zero())
+            .map(|(i, a)| {
+                product(&[
+                    a.to_ex().simplified(),
+                    power(Ex::from(x), Ex::from(i as i32)),
+                ])
+            })
+            .collect();
+        sum(&terms)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Solve the square linear system `a * v = b` over the rationals
+pub fn solve_linear(mut a: Vec<Vec<Rat>>, mut b: Vec<Rat>) -> Option<Vec<Rat>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| !a[r][col].is_zero())?;
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for r in 0..n {
+            if r != col && !a[r][col].is_zero() {
+                let k = a[r][col].checked_div(a[col][col])?;
+                let pivot = a[col].clone();
+                for (x, p) in a[r].iter_mut().zip(pivot).skip(col) {
+                    *x = x.checked_sub(k.checked_mul(p)?)?;
+                }
+                b[r] = b[r].checked_sub(k.checked_mul(b[col])?)?;
+            }
+        }
+    }
+    (0..n).map(|i| b[i].checked_div(a[i][i])).collect()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(s: &str) -> UPoly {
+        UPoly::from_ex(&s.parse().unwrap(), "x").unwrap()
+    }
+
+    #[test]
+    fn ex3_upoly() {
+        let p = poly("(x+1)^2*(x-2)");
+        assert_eq!(p.deg(), 3);
+        assert_eq!(p.rational_roots(), vec![Rat::int(-1), Rat::int(2)]);
+
+        let (q, r) = poly("x^2-1").divrem(&poly("x-1")).unwrap();
+        assert_eq!(q, poly("x+1"));
+        assert!(r.is_zero());
+
+        assert_eq!(poly("x^2-1").gcd(&poly("x^2+2*x+1")), Some(poly("x+1")));
+        assert_eq!(poly("3*x^2").derivative(), Some(poly("6*x")));
+
+        let (lo, hi) = (Rat::ZERO, Rat::ONE);
+        assert_eq!(poly("(x-1/3)^2*(x^2-1/2)").roots_between(lo, hi), Some(2));
+        assert_eq!(poly("x*(x-1)").roots_between(lo, hi), Some(0));
+        assert_eq!(poly("x^2+1").roots_between(-hi, hi), Some(0));
+    }
+
+    #[test]
+    fn ex3_upoly_overflow() {
+        let p = poly("x+1000");
+        assert!(p.powi(12).is_some());
+        assert_eq!(p.powi(20), None);
+        assert_eq!(UPoly::from_ex(&"(x+1000)^20".parse().unwrap(), "x"), None);
+        assert_eq!(poly("x^13-2").eval(Rat::int(1_000_000_000)), None);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,324 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, mpoly::*, poly::*, rational::*, simplify::*, solve::coeffs};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Polynomial long division in `x`, as `(quotient, remainder)`
+    ///
+    /// - Coefficients may involve other symbols
+    /// - Fails if either side is not a polynomial in `x`, or on division by
+    ///   zero
+    pub fn divide(&self, b: &Ex, x: &str) -> Option<(Ex, Ex)> {
+        let mut r = coeffs(&self.simplified(), x)?;
+        let b = coeffs(&b.simplified(), x)?;
+        let n = b.len() - 1;
+        if b[n].is_zero() {
+            return None;
+        }
+        let lead = power(b[n].clone(), Ex::from(-1));
+
+        let mut q = vec![Ex::from(0); r.len().saturating_sub(n).max(1)];
+        while r.len() > n && !(r.len() == 1 && r[0].is_zero()) {
+            let shift = r.len() - 1 - n;
+            let t = cancel(product(&[r[r.len() - 1].clone(), lead.clone()]));
+            for (i, c) in b.iter().enumerate().take(n) {
+                let k = product(&[Ex::from(-1), t.clone(), c.clone()]);
+                r[shift + i] = sum(&[r[shift + i].clone(), k]).normal();
+            }
+            r.pop();
+            while r.len() > 1 && r.last().is_some_and(Ex::is_zero) {
+                r.pop();
+            }
+            q[shift] = t;
+        }
+        Some((rebuild(&q, x), rebuild(&r, x)))
+    }
+
+    /// Quotient of polynomial long division in `x`
+    pub fn quotient(&self, b: &Ex, x: &str) -> Option<Ex> {
+        self.divide(b, x).map(|(q, _)| q)
+    }
+
+    /// Remainder of polynomial long division in `x`
+    pub fn remainder(&self, b: &Ex, x: &str) -> Option<Ex> {
+        self.divide(b, x).map(|(_, r)| r)
+    }
+
+    /// Proper fraction form `q + r / d`, with `r` of lower degree than `d`
+    ///
+    /// - Rational numbers split into an integer part, truncated towards zero,
+    ///   and a fraction
+    /// - The sum is built without simplifying, which would merge it again
+    pub fn prop_frac(&self, x: &str) -> Option<Ex> {
+        let e = self.simplified();
+        if let Some(k) = e.as_rat() {
+            let q = Rat::int(k.numer() / k.denom());
+            return Some(join(q.to_ex().simplified(), (k - q).to_ex().simplified()));
+        }
+
+        let (num, den) = split(&Product::of(&e));
+        if !den.has(x) {
+            return Some(e);
+        }
+        let (q, r) = num.divide(&den, x)?;
+        Some(join(q, product(&[r, power(den, Ex::from(-1))])))
+    }
+
+    /// Greatest common divisor of two polynomials, or of two rationals
+    ///
+    /// - The result has a positive leading coefficient and the largest
+    ///   rational content dividing both
+    pub fn gcd(&self, b: &Ex) -> Option<Ex> {
+        common(&self.simplified(), &b.simplified()).map(|(g, _, _)| g)
+    }
+
+    /// Least common multiple of two polynomials, or of two rationals
+    pub fn lcm(&self, b: &Ex) -> Option<Ex> {
+        let (g, a, b) = common(&self.simplified(), &b.simplified())?;
+        Some(product(&[g, a, b]).normal())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Cancel common polynomial factors between the numerator and denominator of
+/// a simplified product
+pub fn cancel(e: Ex) -> Ex {
+    let p = Product::of(&e);
+    let over_sum = p.factors.iter().any(|(b, k)| {
+        k.as_rat().is_some_and(|k| k.is_neg())
+            && matches!(b, Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _))
+    });
+    if !over_sum {
+        return e;
+    }
+    let (num, den) = split(&Product {
+        coef: Coef::Q(Rat::ONE),
+        ..p.clone()
+    });
+    if num.as_rat().is_some() {
+        return e;
+    }
+    match common(&num, &den) {
+        Some((g, a, b)) if g.as_rat().is_none() => {
+            product(&[p.coef.to_ex(), a, power(b, Ex::from(-1))])
+        }
+        _ => e,
+    }
+}
+
+/// Numerator and denominator of a product, the latter with positive powers
+fn split(p: &Product) -> (Ex, Ex) {
+    let mut num = vec![p.coef.to_ex()];
+    let mut den = vec![];
+    for (base, exp) in &p.factors {
+        match exp.as_rat() {
+            Some(k) if k.is_neg() => den.push(power(base.clone(), (-k).to_ex().simplified())),
+            _ => num.push(power(base.clone(), exp.clone())),
+        }
+    }
+    (product(&num), product(&den))
+}
+
+/// Sum `q + r` kept apart, written as a difference when `r` is negative
+fn join(q: Ex, r: Ex) -> Ex {
+    match (q.is_zero(), r.is_zero()) {
+        (_, true) => q,
+        (true, _) => r,
+        _ if Product::of(&r).coef.is_neg() => q - product(&[Ex::from(-1), r]),
+        _ => q + r,
+    }
+}
+
+/// Expanded polynomial in `x` from coefficients, lowest degree first
+fn rebuild(c: &[Ex], x: &str) -> Ex {
+    let terms: Vec<Ex> = c
+        .iter()
+        .enumerate()
+        .map(|(i, a)| product(&[a.clone(), power(Ex::from(x), Ex::from(i as i32))]))
+        .collect();
+    sum(&terms).normal()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Greatest common divisor `g` with cofactors, so `a = g * a'` and
+/// `b = g * b'`, all expanded
+///
+/// - Polynomials in one variable use Euclid's algorithm
+/// - Otherwise both sides are factored and the common factors kept
+fn common(a: &Ex, b: &Ex) -> Option<(Ex, Ex, Ex)> {
+    if let (Some(p), Some(q)) = (a.as_rat(), b.as_rat()) {
+        let g = rat_gcd(p, q)?;
+        let ex = |k: Rat| k.to_ex().simplified();
+        return Some((ex(g), ex(p.checked_div(g)?), ex(q.checked_div(g)?)));
+    }
+
+    let (pa, pb) = (MPoly::from_ex(a)?, MPoly::from_ex(b)?);
+    let mut atoms: Vec<&Ex> = pa.vars().iter().chain(pb.vars()).collect();
+    atoms.dedup();
+    let rational = |p: &MPoly| p.terms().iter().all(|(_, c)| matches!(c, Coef::Q(_)));
+    if !rational(&pa) || !rational(&pb) {
+        return None;
+    }
+    match atoms.as_slice() {
+        [Ex::Val(Val::Var(x))] => common_upoly(a, b, x),
+        _ => common_factors(a, b),
+    }
+}
+
+fn common_upoly(a: &Ex, b: &Ex, x: &str) -> Option<(Ex, Ex, Ex)> {
+    let (ka, pa) = content(&UPoly::from_ex(a, x)?)?;
+    let (kb, pb) = content(&UPoly::from_ex(b, x)?)?;
+    let k = rat_gcd(ka, kb)?;
+    let g = match (pa.is_zero(), pb.is_zero()) {
+        (true, true) => return None,
+        (true, _) => pb.clone(),
+        (_, true) => pa.clone(),
+        _ => pa.gcd(&pb)?.primitive()?,
+    };
+    let cofactor = |p: &UPoly, c: Rat| match p.is_zero() {
+        true => Some(Ex::from(0)),
+        false => Some(p.divrem(&g)?.0.scale(c.checked_div(k)?)?.to_ex(x)),
+    };
+    Some((g.scale(k)?.to_ex(x), cofactor(&pa, ka)?, cofactor(&pb, kb)?))
+}
+
+fn common_factors(a: &Ex, b: &Ex) -> Option<(Ex, Ex, Ex)> {
+    let (ka, fa) = factors(a)?;
+    let (kb, fb) = factors(b)?;
+    let k = rat_gcd(ka, kb)?;
+    let mut g = vec![k.to_ex().simplified()];
+    let mut ra = vec![ka.checked_div(k)?.to_ex().simplified()];
+    let mut rb = vec![kb.checked_div(k)?.to_ex().simplified()];
+    for (base, n) in &fa {
+        let m = fb.iter().find(|(c, _)| c == base).map_or(0, |(_, m)| *m);
+        g.push(power(base.clone(), Ex::from(*n.min(&m))));
+        ra.push(power(base.clone(), Ex::from(n - n.min(&m))));
+    }
+    for (base, m) in &fb {
+        let n = fa.iter().find(|(c, _)| c == base).map_or(0, |(_, n)| *n);
+        rb.push(power(base.clone(), Ex::from(m - n.min(*m))));
+    }
+    Some((
+        product(&g).normal(),
+        product(&ra).normal(),
+        product(&rb).normal(),
+    ))
+}
+
+/// Rational content and irreducible factors with multiplicities
+fn factors(e: &Ex) -> Option<(Rat, Vec<(Ex, i32)>)> {
+    let p = Product::of(&e.factor());
+    let k = match p.coef {
+        Coef::Q(k) => k,
+        Coef::F(_) => return None,
+    };
+    let mut out = vec![];
+    for (base, exp) in p.factors {
+        match exp.as_rat() {
+            Some(n) if n.is_int() && !n.is_neg() => out.push((base, n.numer() as i32)),
+            _ => return None,
+        }
+    }
+    Some((k, out))
+}
+
+/// Signed content and primitive part, the zero polynomial has content zero
+fn content(p: &UPoly) -> Option<(Rat, UPoly)> {
+    match p.is_zero() {
+        true => Some((Rat::ZERO, p.clone())),
+        false => {
+            let q = p.primitive()?;
+            Some((p.lead().checked_div(q.lead())?, q))
+        }
+    }
+}
+
+/// Largest rational dividing both into integers, always non-negative
+fn rat_gcd(p: Rat, q: Rat) -> Option<Rat> {
+    let d = (p.denom() / gcd(p.denom(), q.denom())).checked_mul(q.denom())?;
+    Some(Rat::new(gcd(p.numer(), q.numer()), d))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::{parse, simp};
+
+    #[test]
+    fn ex3_polydiv_divide() {
+        let (q, r) = parse("x^3-2*x+5").divide(&parse("x-1"), "x").unwrap();
+        assert_eq!((q, r), (simp("x^2+x-1"), simp("4")));
+        let (q, r) = parse("x^2+a*x+b").divide(&parse("x+a"), "x").unwrap();
+        assert_eq!((q, r), (simp("x"), simp("b")));
+        assert_eq!(
+            parse("x^2").quotient(&parse("2*x+1"), "x"),
+            Some(simp("x/2-1/4"))
+        );
+        assert_eq!(
+            parse("x^2").remainder(&parse("2*x+1"), "x"),
+            Some(simp("1/4"))
+        );
+        assert_eq!(parse("x").divide(&parse("0"), "x"), None);
+    }
+
+    #[test]
+    fn ex3_polydiv_prop_frac() {
+        assert_eq!(parse("7/3").prop_frac("x"), Some(parse("2+1/3")));
+        assert_eq!(parse("-7/3").prop_frac("x"), Some(parse("-2-1/3")));
+        let e = parse("(x^2+1)/(x-1)").prop_frac("x").unwrap();
+        assert_eq!(e, simp("x+1") + simp("2/(x-1)"));
+    }
+
+    #[test]
+    fn ex3_polydiv_gcd() {
+        assert_eq!(parse("x^2-1").gcd(&parse("x^2+2*x+1")), Some(simp("x+1")));
+        assert_eq!(parse("2*x^2-2").gcd(&parse("4*x-4")), Some(simp("2*x-2")));
+        assert_eq!(
+            parse("x^2-1").lcm(&parse("x^2+2*x+1")),
+            Some(simp("x^3+x^2-x-1"))
+        );
+        assert_eq!(
+            parse("x^2-y^2").gcd(&parse("x^2+2*x*y+y^2")),
+            Some(simp("x+y"))
+        );
+        assert_eq!(parse("12").gcd(&parse("18")), Some(simp("6")));
+        assert_eq!(parse("4").lcm(&parse("6")), Some(simp("12")));
+        assert_eq!(parse("x+1").gcd(&parse("x-1")), Some(simp("1")));
+        // high degree, and remainders whose coefficients grow under Euclid
+        assert_eq!(parse("x^50-1").gcd(&parse("x^75-1")), Some(simp("x^25-1")));
+        assert_eq!(
+            parse("x^8+x^6-3*x^4-3*x^3+8*x^2+2*x-5").gcd(&parse("3*x^6+5*x^4-4*x^2-9*x+21")),
+            Some(simp("1"))
+        );
+        assert_eq!(simp("gcd(x^50-1, x^75-1)"), simp("x^25-1"));
+    }
+
+    #[test]
+    fn ex3_polydiv_cancel() {
+        assert_eq!(simp("(x^2-1)/(x-1)"), simp("x+1"));
+        assert_eq!(simp("(x^2+2*x+1)/(x^2-1)"), simp("(x+1)/(x-1)"));
+        assert_eq!(simp("(x^3-x)/(2*x+2)"), simp("(x^2-x)/2"));
+        assert_eq!(simp("(x^2-y^2)/(x+y)"), simp("x-y"));
+        assert_eq!(simp("x/(x+1)"), parse("x") / parse("x+1"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,252 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::ast::*;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Kronrod nodes on `[0, 1]`, odd entries are shared with the Gauss rule
+const XGK: [f64; 8] = [
+    0.991_455_371_120_812_6,
+    0.949_107_912_342_758_5,
+    0.864_864_423_359_769_1,
+    0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1,
+    0.405_845_151_377_397_2,
+    0.207_784_955_007_898_5,
+    0.0,
+];
+
+/// 15 point Kronrod weights
+const WGK: [f64; 8] = [
+    0.022_935_322_010_529_22,
+    0.063_092_092_629_978_55,
+    0.104_790_010_322_250_2,
+    0.140_653_259_715_525_9,
+    0.169_004_726_639_267_9,
+    0.190_350_578_064_785_4,
+    0.204_432_940_075_298_9,
+    0.209_482_141_084_727_8,
+];
+
+/// 7 point Gauss weights, for `XGK[1]`, `XGK[3]`, `XGK[5]` and `XGK[7]`
+const WG: [f64; 4] = [
+    0.129_484_966_168_869_7,
+    0.279_705_391_489_276_7,
+    0.381_830_050_505_118_9,
+    0.417_959_183_673_469_4,
+];
+
+/// Most subintervals before giving up on the requested tolerance
+const MAX_INTERVALS: usize = 2000;
+
+/// Width beyond which a finite interval is mapped like an infinite one
+const WIDE: f64 = 1e12;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Result of a numeric integration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quadrature {
+    /// Approximate value of the integral
+    pub value: f64,
+    /// Estimated absolute error
+    pub error: f64,
+    /// Whether the requested tolerance was reached
+    pub converged: bool,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Numeric integral of `self` over `x` from `a` to `b`
+    ///
+    /// - Adaptive 7-15 point Gauss–Kronrod quadrature
+    /// - Infinite bounds, and very wide finite intervals, are mapped onto
+    ///   finite intervals first
+    pub fn nint(&self, x: &str, a: f64, b: f64) -> Quadrature {
+        let f = |v: f64| self.flt(&[(x, v)]).unwrap_or(f64::NAN);
+        integrate(f, a, b, 1e-10)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Integrate `f` from `a` to `b`, either of which may be infinite
+pub fn integrate<F: Fn(f64) -> f64>(f: F, a: f64, b: f64, tol: f64) -> Quadrature {
+    if a == b {
+        return Quadrature {
+            value: 0.0,
+            error: 0.0,
+            converged: true,
+        };
+    }
+    if a > b {
+        let q = integrate(f, b, a, tol);
+        return Quadrature {
+            value: -q.value,
+            ..q
+        };
+    }
+    match (a.is_finite(), b.is_finite()) {
+        (true, true) if b - a > WIDE => {
+            // evenly spread nodes would miss everything near the origin, so
+            // split there and map x = c ± t/(1-t) onto each side
+            let (f, c) = (&f, 0.0_f64.clamp(a, b));
+            let end = |w: f64| w / (1.0 + w);
+            let map = |s: f64| move |t: f64| f(c + s * t / (1.0 - t)) / ((1.0 - t) * (1.0 - t));
+            let l = adaptive(&map(-1.0), 0.0, end(c - a), tol);
+            let r = adaptive(&map(1.0), 0.0, end(b - c), tol);
+            Quadrature {
+                value: l.value + r.value,
+                error: l.error + r.error,
+                converged: l.converged && r.converged,
+            }
+        }
+        (true, true) => adaptive(&f, a, b, tol),
+        // x = a + t/(1-t)
+        (true, false) => adaptive(
+            &|t: f64| f(a + t / (1.0 - t)) / ((1.0 - t) * (1.0 - t)),
+            0.0,
+            1.0,
+            tol,
+        ),
+        // x = b - (1-t)/t
+        (false, true) => adaptive(&|t: f64| f(b - (1.0 - t) / t) / (t * t), 0.0, 1.0, tol),
+        // x = t/(1-t^2)
+        (false, false) => adaptive(
+            &|t: f64| {
+                let s = 1.0 - t * t;
+                f(t / s) * (1.0 + t * t) / (s * s)
+            },
+            -1.0,
+            1.0,
+            tol,
+        ),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Bisect the interval with the largest error estimate until the total error
+/// is within tolerance
+fn adaptive<F: Fn(f64) -> f64>(f: &F, a: f64, b: f64, tol: f64) -> Quadrature {
+    let mut parts = vec![(a, b, kronrod(f, a, b))];
+    loop {
+        let value: f64 = parts.iter().map(|p| p.2 .0).sum();
+        let error: f64 = parts.iter().map(|p| p.2 .1).sum();
+        let done = error <= tol.max(tol * value.abs());
+        if done || parts.len() >= MAX_INTERVALS || !value.is_finite() {
+            return Quadrature {
+                value,
+                error,
+                converged: done && value.is_finite(),
+            };
+        }
+
+        let (i, _) = parts
+            .iter()
+            .enumerate()
+            .max_by(|x, y| x.1 .2 .1.total_cmp(&y.1 .2 .1))
+            .unwrap();
+        let (a, b, _) = parts[i];
+        let m = 0.5 * (a + b);
+        if m <= a || m >= b {
+            // interval can no longer be split in floating point, so its
+            // error stays as estimated and the tolerance is out of reach
+            return Quadrature {
+                value,
+                error,
+                converged: false,
+            };
+        }
+        parts.swap_remove(i);
+        parts.push((a, m, kronrod(f, a, m)));
+        parts.push((m, b, kronrod(f, m, b)));
+    }
+}
+
+/// Single 15 point Kronrod estimate, with the 7 point Gauss difference as its
+/// error
+fn kronrod<F: Fn(f64) -> f64>(f: &F, a: f64, b: f64) -> (f64, f64) {
+    let c = 0.5 * (a + b);
+    let h = 0.5 * (b - a);
+    let mut k = 0.0;
+    let mut g = 0.0;
+    for (i, (x, w)) in XGK.iter().zip(WGK).enumerate() {
+        let y = match *x == 0.0 {
+            true => f(c),
+            false => f(c - h * x) + f(c + h * x),
+        };
+        k += w * y;
+        if i % 2 == 1 {
+            g += WG[i / 2] * y;
+        }
+    }
+    (k * h, ((k - g) * h).abs())
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn close(q: Quadrature, want: f64) {
+        assert!(q.converged, "{:?}", q);
+        assert!((q.value - want).abs() < 1e-8, "{:?} != {}", q, want);
+        assert!(q.error < 1e-6, "{:?}", q);
+    }
+
+    #[test]
+    fn ex3_quadrature_finite() {
+        close(integrate(|x| x * x, 0.0, 3.0, 1e-10), 9.0);
+        close(integrate(f64::sin, 0.0, PI, 1e-10), 2.0);
+        close(integrate(f64::sin, PI, 0.0, 1e-10), -2.0);
+        close(integrate(|x| x.sqrt(), 0.0, 1.0, 1e-10), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn ex3_quadrature_unsplittable() {
+        // an interval between neighbouring floats cannot be bisected
+        let b = 1.0 + f64::EPSILON;
+        let q = integrate(|x| (x - 1.0) * 1e16, 1.0, b, 0.0);
+        assert!(!q.converged, "{:?}", q);
+        assert!(q.error > 0.0, "{:?}", q);
+    }
+
+    #[test]
+    fn ex3_quadrature_wide() {
+        close(
+            integrate(|x| 1.0 / (1.0 + x * x), -1e40, 1.0, 1e-10),
+            0.75 * PI,
+        );
+        close(integrate(|x| (-x).exp(), 0.0, 1e30, 1e-10), 1.0);
+    }
+
+    #[test]
+    fn ex3_quadrature_infinite() {
+        close(integrate(|x| (-x).exp(), 0.0, f64::INFINITY, 1e-10), 1.0);
+        close(
+            integrate(|x| 1.0 / (1.0 + x * x), f64::NEG_INFINITY, 0.0, 1e-10),
+            PI / 2.0,
+        );
+        close(
+            integrate(|x| (-x * x).exp(), f64::NEG_INFINITY, f64::INFINITY, 1e-10),
+            PI.sqrt(),
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
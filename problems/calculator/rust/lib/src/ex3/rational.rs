@@ -0,0 +1,379 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::{
+    cmp::Ordering,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use super::ast::*;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Exact rational number, always kept in lowest terms with a positive
+/// denominator.
+///
+/// - the `checked_*` methods and [`powi`](Rat::powi) return `None` when a
+///   numerator or denominator would leave `i128`
+/// - the operators panic in that case, so use them only on values known to
+///   be small
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Rat {
+    n: i128,
+    d: i128,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Rat {
+    pub const ZERO: Rat = Rat { n: 0, d: 1 };
+    pub const ONE: Rat = Rat { n: 1, d: 1 };
+
+    pub fn new(n: i128, d: i128) -> Self {
+        assert!(d != 0, "zero denominator");
+        Rat::reduce(n, d).expect("rational overflow")
+    }
+
+    /// Lowest terms, or `None` if a part cannot be negated
+    fn reduce(n: i128, d: i128) -> Option<Self> {
+        if d == 0 || n == i128::MIN || d == i128::MIN {
+            return None;
+        }
+        let g = gcd(n, d);
+        let s = d.signum();
+        Some(Rat {
+            n: s * n / g,
+            d: s * d / g,
+        })
+    }
+
+    pub fn int(n: i128) -> Self {
+        Rat { n, d: 1 }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.n
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.d
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.n == 1 && self.d == 1
+    }
+
+    pub fn is_int(&self) -> bool {
+        self.d == 1
+    }
+
+    pub fn is_neg(&self) -> bool {
+        self.n < 0
+    }
+
+    pub fn abs(self) -> Self {
+        Rat {
+            n: self.n.abs(),
+            d: self.d,
+        }
+    }
+
+    pub fn recip(self) -> Self {
+        Rat::new(self.d, self.n)
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let g = gcd(self.d, rhs.d);
+        let n = (self.n.checked_mul(rhs.d / g)?).checked_add(rhs.n.checked_mul(self.d / g)?)?;
+        Rat::reduce(n, (self.d / g).checked_mul(rhs.d)?)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(Rat::reduce(rhs.n.checked_neg()?, rhs.d)?)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let g1 = gcd(self.n, rhs.d);
+        let g2 = gcd(rhs.n, self.d);
+        Rat::reduce(
+            (self.n / g1).checked_mul(rhs.n / g2)?,
+            (self.d / g2).checked_mul(rhs.d / g1)?,
+        )
+    }
+
+    /// `None` also for division by zero
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.is_zero() {
+            return None;
+        }
+        self.checked_mul(Rat::reduce(rhs.d, rhs.n)?)
+    }
+
+    /// Integer power, negative exponents invert; `None` on overflow or for
+    /// a negative power of zero
+    pub fn powi(self, e: i128) -> Option<Self> {
+        let k = u32::try_from(e.unsigned_abs()).ok()?;
+        let n = self.n.checked_pow(k)?;
+        let d = self.d.checked_pow(k)?;
+        if e < 0 {
+            Rat::reduce(d, n)
+        } else {
+            Rat::reduce(n, d)
+        }
+    }
+
+    /// Exact `k`th root, if there is one
+    pub fn root(self, k: u32) -> Option<Self> {
+        if self.n < 0 && k.is_multiple_of(2) {
+            return None;
+        }
+        let n = iroot(self.n.abs(), k)?;
+        let d = iroot(self.d, k)?;
+        Some(Rat::new(if self.n < 0 { -n } else { n }, d))
+    }
+
+    pub fn floor(self) -> i128 {
+        self.n.div_euclid(self.d)
+    }
+
+    pub fn flt(self) -> f64 {
+        self.n as f64 / self.d as f64
+    }
+
+    /// Rebuild as an expression: `n`, `-n`, `n/d` or `-(n/d)`
+    pub fn to_ex(self) -> Ex {
+        let ex = match self.d {
+            1 => Ex::from(self.n.abs()),
+            d => Ex::from(self.n.abs()) / Ex::from(d),
+        };
+        if self.n < 0 {
+            -ex
+        } else {
+            ex
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Read an exact rational constant: `n`, `-n`, `n/d` and nested forms
+    pub fn as_rat(&self) -> Option<Rat> {
+        match self {
+            Ex::Val(Val::Num(Num::Int(n))) => Rat::reduce(*n, 1),
+            Ex::Neg(a) => a.as_rat().map(|a| -a),
+            Ex::Infix(a, Bin::Op(Op::Div), b) => {
+                let b = b.as_rat()?;
+                if b.is_zero() {
+                    None
+                } else {
+                    Some(a.as_rat()? / b)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl From<i32> for Rat {
+    fn from(value: i32) -> Self {
+        Rat::int(value.into())
+    }
+}
+
+impl From<i128> for Rat {
+    fn from(value: i128) -> Self {
+        Rat::int(value)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Add for Rat {
+    type Output = Rat;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("rational overflow")
+    }
+}
+
+impl Sub for Rat {
+    type Output = Rat;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).expect("rational overflow")
+    }
+}
+
+impl Mul for Rat {
+    type Output = Rat;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.checked_mul(rhs).expect("rational overflow")
+    }
+}
+
+impl Div for Rat {
+    type Output = Rat;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        assert!(!rhs.is_zero(), "zero denominator");
+        self.checked_div(rhs).expect("rational overflow")
+    }
+}
+
+impl Neg for Rat {
+    type Output = Rat;
+
+    fn neg(self) -> Self::Output {
+        Rat {
+            n: -self.n,
+            d: self.d,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl PartialOrd for Rat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rat {
+    /// Cross-multiplies when that fits, otherwise compares integer parts and
+    /// then the reciprocals of the fractional parts
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Some(a), Some(b)) = (self.n.checked_mul(other.d), other.n.checked_mul(self.d)) {
+            return a.cmp(&b);
+        }
+        match self.floor().cmp(&other.floor()) {
+            Ordering::Equal => {
+                let a = self.n.rem_euclid(self.d);
+                let b = other.n.rem_euclid(other.d);
+                match (a, b) {
+                    (0, 0) => Ordering::Equal,
+                    (0, _) => Ordering::Less,
+                    (_, 0) => Ordering::Greater,
+                    _ => Rat { n: other.d, d: b }.cmp(&Rat { n: self.d, d: a }),
+                }
+            }
+            o => o,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    if a == 0 {
+        1
+    } else {
+        a
+    }
+}
+
+pub fn lcm(a: i128, b: i128) -> i128 {
+    (a / gcd(a, b) * b).abs()
+}
+
+/// Exact integer `k`th root of a non-negative integer
+pub fn iroot(n: i128, k: u32) -> Option<i128> {
+    if n < 2 || k == 1 {
+        return Some(n);
+    }
+    let guess = (n as f64).powf(1.0 / k as f64).round() as i128;
+    (guess.saturating_sub(1)..=guess + 1).find(|r| r.checked_pow(k) == Some(n))
+}
+
+/// All positive divisors, in increasing order
+pub fn divisors(n: i128) -> Vec<i128> {
+    let n = n.abs();
+    let mut small = vec![];
+    let mut large = vec![];
+    let mut i = 1;
+    while i * i <= n {
+        if n % i == 0 {
+            small.push(i);
+            if i * i != n {
+                large.push(n / i);
+            }
+        }
+        i += 1;
+    }
+    small.extend(large.into_iter().rev());
+    small
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ex3_rat_arithmetic() {
+        let a = Rat::new(1, 2);
+        let b = Rat::new(1, 3);
+        assert_eq!(a + b, Rat::new(5, 6));
+        assert_eq!(a - b, Rat::new(1, 6));
+        assert_eq!(a * b, Rat::new(1, 6));
+        assert_eq!(a / b, Rat::new(3, 2));
+        assert_eq!(Rat::new(4, -8), Rat::new(-1, 2));
+        assert_eq!(Rat::new(4, 9).root(2), Some(Rat::new(2, 3)));
+        assert_eq!(Rat::new(2, 1).root(2), None);
+        assert_eq!(Rat::new(-7, 2).floor(), -4);
+        assert_eq!(Rat::new(2, 3).powi(-2), Some(Rat::new(9, 4)));
+        assert_eq!(Rat::ZERO.powi(-1), None);
+    }
+
+    #[test]
+    fn ex3_rat_overflow() {
+        let big = Rat::int(i128::MAX / 2);
+        assert_eq!(big.checked_mul(Rat::int(3)), None);
+        assert_eq!(big.checked_add(big), Some(Rat::int(i128::MAX - 1)));
+        assert_eq!(Rat::int(1000).powi(20), None);
+        let a = Rat::new(i128::MAX - 1, i128::MAX - 2);
+        let b = Rat::new(i128::MAX - 2, i128::MAX - 3);
+        assert!(a < b && Rat::ONE < a);
+    }
+
+    #[test]
+    fn ex3_rat_roundtrip() {
+        let big = Rat::new(i128::MAX - 1, 3);
+        for r in [
+            Rat::new(3, 4),
+            Rat::new(-5, 2),
+            Rat::int(7),
+            Rat::int(-1),
+            big,
+            -big,
+        ] {
+            assert_eq!(r.to_ex().as_rat(), Some(r));
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
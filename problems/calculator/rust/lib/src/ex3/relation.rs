@@ -0,0 +1,258 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Constant of proportionality of `a ∝ b`, never produced by the parser
+pub const K: &str = "@k";
+
+/// Relative difference below which `a ≈ b` holds
+pub const TOL: f64 = 1e-6;
+
+/// Relations and their negations
+const NEGATED: [(Rl, Rl); 10] = [
+    (Rl::Eqq, Rl::Neq),
+    (Rl::Ltt, Rl::Geq),
+    (Rl::Gtt, Rl::Leq),
+    (Rl::Equiv, Rl::NEquiv),
+    (Rl::Cong, Rl::NCong),
+    (Rl::Sim, Rl::NSim),
+    (Rl::Approx, Rl::NApprox),
+    (Rl::In, Rl::NotIn),
+    (Rl::Sub, Rl::NSub),
+    (Rl::SubEq, Rl::NSubEq),
+];
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// `a ∝ b` as the equation `a = @k*b`, anything else unchanged
+    pub fn as_equation(&self) -> Ex {
+        match self {
+            Ex::Infix(a, Bin::Rl(Rl::Prop), b) => (**a)
+                .clone()
+                .c(product(&[Ex::from(K), b.simplified()]), Rl::Eqq.into()),
+            _ => self.clone(),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Value of the relation `a r b` between simplified sides, where it is known
+///
+/// - `=`, `≠`, `<`, `≤`, `>` and `≥` compare numbers, see [`compare`]
+/// - `≈` and `≉` compare numbers up to [`TOL`]
+/// - `⇒`, `⇐` and `⇔` are the boolean connectives, reducing with one known
+///   side where possible, and a side comparing numbers is known
+/// - `p ⇔ false` and `p ⇒ false` are the negated relation of `p`, such as
+///   `x ≥ 1` for `x < 1`, and `p ⇔ false` is `p ⇒ false` where there is
+///   none
+/// - `None` for everything else, which stays as written
+pub fn evaluate(a: &Ex, r: &Rl, b: &Ex) -> Option<Ex> {
+    match r {
+        Rl::Eqq | Rl::Neq | Rl::Ltt | Rl::Leq | Rl::Gtt | Rl::Geq => compare(a, r, b).map(Ex::from),
+        Rl::Approx => approx(a, b).map(Ex::from),
+        Rl::NApprox => approx(a, b).map(|v| Ex::from(!v)),
+        Rl::Implies => implies(a, b),
+        Rl::If => implies(b, a),
+        Rl::Iff => match (truth(a), truth(b)) {
+            (Some(p), Some(q)) => Some(Ex::from(p == q)),
+            (Some(true), _) => Some(b.clone()),
+            (_, Some(true)) => Some(a.clone()),
+            (Some(false), _) => Some(negation(b).unwrap_or_else(|| not(b))),
+            (_, Some(false)) => Some(negation(a).unwrap_or_else(|| not(a))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn approx(a: &Ex, b: &Ex) -> Option<bool> {
+    let (x, y) = (a.flt(&[])?, b.flt(&[])?);
+    if !x.is_finite() || !y.is_finite() {
+        return None;
+    }
+    Some((x - y).abs() <= TOL * x.abs().max(y.abs()).max(1.0))
+}
+
+fn implies(a: &Ex, b: &Ex) -> Option<Ex> {
+    match (truth(a), truth(b)) {
+        (Some(false), _) | (_, Some(true)) => Some(Ex::from(true)),
+        (Some(true), q) => Some(q.map_or_else(|| b.clone(), Ex::from)),
+        (_, Some(false)) => negation(a),
+        _ => None,
+    }
+}
+
+fn not(e: &Ex) -> Ex {
+    e.clone().c(Ex::from(false), Rl::Implies.into())
+}
+
+/// `a r' b` for `a r b`, where `r'` is the negation of `r`
+fn negation(e: &Ex) -> Option<Ex> {
+    let Ex::Infix(a, Bin::Rl(r), b) = e else {
+        return None;
+    };
+    let n = NEGATED.iter().find_map(|(p, q)| match r {
+        _ if r == p => Some(q),
+        _ if r == q => Some(p),
+        _ => None,
+    })?;
+    Some((**a).clone().c((**b).clone(), n.clone().into()))
+}
+
+fn truth(e: &Ex) -> Option<bool> {
+    match e {
+        Ex::Val(Val::Bool(v)) => Some(*v),
+        Ex::Infix(a, Bin::Rl(r), b) => compare(a, r, b),
+        _ => None,
+    }
+}
+
+/// Comparison between numbers, exact for rationals and otherwise only when
+/// the values are not within [`TOL`] of each other
+fn compare(a: &Ex, r: &Rl, b: &Ex) -> Option<bool> {
+    let ord = match (a.as_rat(), b.as_rat()) {
+        (Some(p), Some(q)) => p.cmp(&q),
+        _ if approx(a, b)? => return None,
+        _ => a.flt(&[])?.total_cmp(&b.flt(&[])?),
+    };
+    Some(match r {
+        Rl::Eqq => ord.is_eq(),
+        Rl::Neq => ord.is_ne(),
+        Rl::Ltt => ord.is_lt(),
+        Rl::Leq => ord.is_le(),
+        Rl::Gtt => ord.is_gt(),
+        Rl::Geq => ord.is_ge(),
+        _ => return None,
+    })
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::testing::{parse, simp};
+    use crate::ex3::{ast::*, solve::Domain};
+
+    #[test]
+    fn ex3_relation_parse() {
+        let rel = |s: &str| match parse(s) {
+            Ex::Infix(_, Bin::Rl(r), _) => r,
+            e => panic!("{} is {:?}", s, e),
+        };
+        for (s, r) in [
+            ("a ≡ b", Rl::Equiv),
+            ("a ≅ b", Rl::Cong),
+            ("a ∼ b", Rl::Sim),
+            ("a ≃ b", Rl::SimEq),
+            ("a ≈ b", Rl::Approx),
+            ("a ≍ b", Rl::Asymp),
+            ("a ∝ b", Rl::Prop),
+            ("a ≢ b", Rl::NEquiv),
+            ("a ≉ b", Rl::NApprox),
+            ("a ≰ b", Rl::NLeq),
+            ("a ∈ b", Rl::In),
+            ("a ⊈ b", Rl::NSubEq),
+            ("a ⇐ b", Rl::If),
+            ("a <== b", Rl::If),
+            ("a → b", Rl::To),
+            ("a -> b", Rl::To),
+            ("a => b", Rl::Implies),
+            ("a |-> b", Rl::MapsTo),
+            ("a <=> b", Rl::Iff),
+        ] {
+            assert_eq!(rel(s), r, "{}", s);
+        }
+
+        // arrows bind looser than `or` and group to the right
+        assert_eq!(
+            parse("a or b ⇒ c ⇒ d ⇔ f"),
+            Ex::from("a")
+                .c(Ex::from("b"), Rl::Or.into())
+                .c(
+                    Ex::from("c").c(Ex::from("d"), Rl::Implies.into()),
+                    Rl::Implies.into()
+                )
+                .c(Ex::from("f"), Rl::Iff.into())
+        );
+        assert_eq!(parse("x ↦ x^2 - 1"), parse("x |-> (x^2 - 1)"));
+    }
+
+    #[test]
+    fn ex3_relation_render() {
+        for s in [
+            "x ≈ 3.14",
+            "a ∝ b",
+            "x ∈ A",
+            "(a => b) => c",
+            "a => b => c",
+            "a <=> (b or c)",
+            "x |-> x^2",
+            "a ≢ b + 1",
+        ] {
+            assert_eq!(parse(&parse(s).to_text()), parse(s), "{}", s);
+        }
+        assert_eq!(parse("(a => b) => c").to_text(), "(a => b) => c");
+        assert_eq!(parse("a => (b => c)").to_text(), "a => b => c");
+        assert_eq!(parse("x ≈ y").to_latex(), "x \\approx y");
+        assert_eq!(parse("y ∝ x^2").to_latex(), "y \\propto x^{2}");
+        assert_eq!(parse("p ⇔ q").to_latex(), "p \\Leftrightarrow q");
+        assert_eq!(parse("A ⊈ B").to_latex(), "A \\nsubseteq B");
+        assert_eq!(parse("x ↦ 2*x").to_latex(), "x \\mapsto 2 \\cdot x");
+    }
+
+    #[test]
+    fn ex3_relation_semantics() {
+        assert_eq!(simp("pi ≈ 3.14159"), Ex::from(true));
+        assert_eq!(simp("pi ≈ 3.14"), Ex::from(false));
+        assert_eq!(simp("2^(1/2) ≉ 1.41421356"), Ex::from(false));
+        assert_eq!(simp("x ≈ 1").to_text(), "x ≈ 1");
+
+        assert_eq!(simp("true => false"), Ex::from(false));
+        assert_eq!(simp("false => x"), Ex::from(true));
+        assert_eq!(simp("true => x"), parse("x"));
+        assert_eq!(simp("x <== true"), parse("x"));
+        assert_eq!(simp("false <=> false"), Ex::from(true));
+        assert_eq!(simp("true <=> x"), parse("x"));
+        assert_eq!(simp("x => y").to_text(), "x => y");
+        assert_eq!(simp("1<2 => 2<1"), Ex::from(false));
+        assert_eq!(simp("1=1 <=> 2!=2"), Ex::from(false));
+        assert_eq!(simp("x <== 1/3<=1/2"), parse("x"));
+        assert_eq!(simp("pi>3 => x"), parse("x"));
+        assert_eq!(simp("x<1 => 2>=3").to_text(), "x >= 1");
+        assert_eq!(simp("x => 2>=3").to_text(), "x => false");
+
+        assert_eq!(simp("2 <= 3"), Ex::from(true));
+        assert_eq!(simp("3 != 3"), Ex::from(false));
+        assert_eq!(simp("1/3 > 1/2"), Ex::from(false));
+        assert_eq!(simp("pi = 3"), Ex::from(false));
+        assert_eq!(simp("x = 3").to_text(), "x = 3");
+        assert_eq!(simp("x < 1 <=> 2 < 1").to_text(), "x >= 1");
+        assert_eq!(simp("false <=> x = y").to_text(), "x != y");
+        assert_eq!(simp("p <=> false").to_text(), "p => false");
+
+        let k = || Ex::from(super::K);
+        assert_eq!(
+            simp("solve(y ∝ x^2, y)"),
+            Ex::from("y").c(k() * simp("x^2"), Rl::Eqq.into())
+        );
+        assert_eq!(
+            parse("6 ∝ 3").solve(super::K, Domain::Real),
+            Some(k().c(Ex::from(2), Rl::Eqq.into()))
+        );
+        assert!(simp("deSolve(y' ∝ y, x, y)").has(super::K));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,897 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, symbols};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Plain text in the input syntax, bracketed by precedence
+    ///
+    /// - Parses back to the same tree, up to brackets around associative
+    ///   operators
+    pub fn to_text(&self) -> String {
+        match self {
+            Ex::Val(v) => v.to_text(),
+            Ex::Infix(a, j, b) => {
+                let (a, b) = operands(self, a, b);
+                match j {
+                    Bin::Op(Op::Mul | Op::Div | Op::Pow | Op::Mod) => {
+                        format!(
+                            "{}{}{}",
+                            a.0.to_text_in(a.1),
+                            j.to_text(),
+                            b.0.to_text_in(b.1)
+                        )
+                    }
+                    _ => format!(
+                        "{} {} {}",
+                        a.0.to_text_in(a.1),
+                        j.to_text(),
+                        b.0.to_text_in(b.1)
+                    ),
+                }
+            }
+            // the parser reads `-x^2` as `(-x)^2`
+            Ex::Neg(a) => match prec(a) < ATOM {
+                true => format!("-({})", a.to_text()),
+                false => format!("-{}", a.to_text()),
+            },
+            Ex::Fn(f) => {
+                let args: Vec<String> = f.args().iter().map(|a| a.to_text()).collect();
+                format!("{}({})", f.name(), args.join(", "))
+            }
+            Ex::Mat(xs) => {
+                let xs: Vec<String> = xs.iter().map(Ex::to_text).collect();
+                format!("[{}]", xs.join(", "))
+            }
+            Ex::Invalid => "undef".to_owned(),
+        }
+    }
+
+    /// LaTeX math, bracketed by precedence
+    pub fn to_latex(&self) -> String {
+        match self {
+            Ex::Val(v) => v.to_latex(),
+            Ex::Infix(a, Bin::Op(Op::Div), b) => {
+                format!("\\frac{{{}}}{{{}}}", a.to_latex(), b.to_latex())
+            }
+            Ex::Infix(a, Bin::Op(Op::Pow), b) => {
+                let base = match prec(a) < ATOM {
+                    true => format!("\\left({}\\right)", a.to_latex()),
+                    false => a.to_latex(),
+                };
+                match root(b) {
+                    Some(n) if n == Ex::from(2) => format!("\\sqrt{{{}}}", a.to_latex()),
+                    Some(n) => format!("\\sqrt[{}]{{{}}}", n.to_latex(), a.to_latex()),
+                    None => format!("{}^{{{}}}", base, b.to_latex()),
+                }
+            }
+            Ex::Infix(a, j, b) => {
+                let (a, b) = operands(self, a, b);
+                format!(
+                    "{} {} {}",
+                    a.0.to_latex_in(a.1),
+                    j.to_latex(),
+                    b.0.to_latex_in(b.1)
+                )
+            }
+            Ex::Neg(a) => match prec(a) < NEG {
+                true => format!("-\\left({}\\right)", a.to_latex()),
+                false => format!("-{}", a.to_latex()),
+            },
+            Ex::Fn(f) => f.to_latex(),
+            Ex::Mat(xs) => {
+                let row = |xs: Vec<&Ex>| -> String {
+                    let xs: Vec<String> = xs.iter().map(|x| x.to_latex()).collect();
+                    xs.join(" & ")
+                };
+                let rows: Vec<String> = match xs.iter().all(|x| matches!(x, Ex::Mat(_))) {
+                    true => xs.iter().map(|r| row(r.children())).collect(),
+                    false => vec![row(xs.iter().collect())],
+                };
+                format!(
+                    "\\begin{{bmatrix}} {} \\end{{bmatrix}}",
+                    rows.join(" \\\\ ")
+                )
+            }
+            Ex::Invalid => "\\text{undefined}".to_owned(),
+        }
+    }
+
+    /// Typst math, bracketed by precedence
+    pub fn to_typst(&self) -> String {
+        match self {
+            Ex::Val(v) => v.to_typst(),
+            Ex::Infix(a, Bin::Op(Op::Div), b) => {
+                format!("frac({}, {})", a.to_typst(), b.to_typst())
+            }
+            Ex::Infix(a, Bin::Op(Op::Pow), b) => match root(b) {
+                Some(n) if n == Ex::from(2) => format!("sqrt({})", a.to_typst()),
+                Some(n) => format!("root({}, {})", n.to_typst(), a.to_typst()),
+                None => format!("{}^({})", a.to_typst_in(prec(a) < ATOM), b.to_typst()),
+            },
+            Ex::Infix(a, j, b) => {
+                let (a, b) = operands(self, a, b);
+                format!(
+                    "{} {} {}",
+                    a.0.to_typst_in(a.1),
+                    j.to_typst(),
+                    b.0.to_typst_in(b.1)
+                )
+            }
+            Ex::Neg(a) => format!("-{}", a.to_typst_in(prec(a) < NEG)),
+            Ex::Fn(f) => f.to_typst(),
+            Ex::Mat(xs) => {
+                let row = |xs: Vec<&Ex>| -> String {
+                    let xs: Vec<String> = xs.iter().map(|x| x.to_typst()).collect();
+                    xs.join(", ")
+                };
+                let rows: Vec<String> = match xs.iter().all(|x| matches!(x, Ex::Mat(_))) {
+                    true => xs.iter().map(|r| row(r.children())).collect(),
+                    false => vec![row(xs.iter().collect())],
+                };
+                format!("mat({})", rows.join("; "))
+            }
+            Ex::Invalid => "\"undefined\"".to_owned(),
+        }
+    }
+
+    /// Presentation MathML in a `<math>` element, bracketed by precedence
+    pub fn to_mathml(&self) -> String {
+        format!(
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+            self.mathml()
+        )
+    }
+
+    /// MathML of a single element, without the `<math>` around it
+    fn mathml(&self) -> String {
+        match self {
+            Ex::Val(v) => v.to_mathml(),
+            Ex::Infix(a, Bin::Op(Op::Div), b) => {
+                format!("<mfrac>{}{}</mfrac>", a.mathml(), b.mathml())
+            }
+            Ex::Infix(a, Bin::Op(Op::Pow), b) => match root(b) {
+                Some(n) if n == Ex::from(2) => format!("<msqrt>{}</msqrt>", a.mathml()),
+                Some(n) => format!("<mroot>{}{}</mroot>", a.mathml(), n.mathml()),
+                None => format!(
+                    "<msup>{}{}</msup>",
+                    a.to_mathml_in(prec(a) < ATOM),
+                    b.mathml()
+                ),
+            },
+            Ex::Infix(a, j, b) => {
+                let (a, b) = operands(self, a, b);
+                format!(
+                    "<mrow>{}{}{}</mrow>",
+                    a.0.to_mathml_in(a.1),
+                    mo(j.symbol()),
+                    b.0.to_mathml_in(b.1)
+                )
+            }
+            Ex::Neg(a) => format!("<mrow>{}{}</mrow>", mo("−"), a.to_mathml_in(prec(a) < NEG)),
+            Ex::Fn(f) => f.to_mathml(),
+            Ex::Mat(xs) => {
+                let row = |xs: Vec<&Ex>| -> String {
+                    let xs: Vec<String> = xs
+                        .iter()
+                        .map(|x| format!("<mtd>{}</mtd>", x.mathml()))
+                        .collect();
+                    format!("<mtr>{}</mtr>", xs.concat())
+                };
+                let rows: Vec<String> = match xs.iter().all(|x| matches!(x, Ex::Mat(_))) {
+                    true => xs.iter().map(|r| row(r.children())).collect(),
+                    false => vec![row(xs.iter().collect())],
+                };
+                fence("[", &format!("<mtable>{}</mtable>", rows.concat()), "]")
+            }
+            Ex::Invalid => "<mtext>undefined</mtext>".to_owned(),
+        }
+    }
+
+    fn to_text_in(&self, brackets: bool) -> String {
+        match brackets {
+            true => format!("({})", self.to_text()),
+            false => self.to_text(),
+        }
+    }
+
+    fn to_latex_in(&self, brackets: bool) -> String {
+        match brackets {
+            true => format!("\\left({}\\right)", self.to_latex()),
+            false => self.to_latex(),
+        }
+    }
+
+    fn to_typst_in(&self, brackets: bool) -> String {
+        match brackets {
+            true => format!("({})", self.to_typst()),
+            false => self.to_typst(),
+        }
+    }
+
+    fn to_mathml_in(&self, brackets: bool) -> String {
+        match brackets {
+            true => fence("(", &self.mathml(), ")"),
+            false => self.mathml(),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Val {
+    fn to_text(&self) -> String {
+        match self {
+            Val::Num(Num::Int(n)) => n.to_string(),
+            Val::Num(Num::Flt(v)) => v.to_string(),
+            Val::Num(Num::Big(v)) => v.to_string(),
+            Val::Var(x) => x.clone(),
+            Val::Bool(b) => b.to_string(),
+            Val::Tok(t) => format!("{:?}", t),
+        }
+    }
+
+    fn to_latex(&self) -> String {
+        match self {
+            Val::Num(_) => self.to_text(),
+            Val::Var(x) => match x.split_once('_') {
+                Some((a, b)) => format!("{}_{{{}}}", name(a), name(b)),
+                None => name(x),
+            },
+            Val::Bool(b) => format!("\\text{{{}}}", b),
+            Val::Tok(Tok::E) => "e".to_owned(),
+            Val::Tok(Tok::Pi) => "\\pi".to_owned(),
+            Val::Tok(Tok::I) => "i".to_owned(),
+            Val::Tok(Tok::Inf) => "\\infty".to_owned(),
+        }
+    }
+
+    fn to_typst(&self) -> String {
+        match self {
+            Val::Num(_) => self.to_text(),
+            Val::Var(x) => match x.split_once('_') {
+                Some((a, b)) => format!("{}_{}", typst_name(a), typst_name(b)),
+                None => typst_name(x),
+            },
+            Val::Bool(b) => format!("\"{}\"", b),
+            Val::Tok(Tok::E) => "e".to_owned(),
+            Val::Tok(Tok::Pi) => "pi".to_owned(),
+            Val::Tok(Tok::I) => "i".to_owned(),
+            Val::Tok(Tok::Inf) => "infinity".to_owned(),
+        }
+    }
+
+    fn to_mathml(&self) -> String {
+        match self {
+            Val::Num(_) => {
+                let n = self.to_text();
+                match n.strip_prefix('-') {
+                    Some(n) => format!("<mrow>{}<mn>{}</mn></mrow>", mo("−"), n),
+                    None => format!("<mn>{}</mn>", n),
+                }
+            }
+            Val::Var(x) => match x.split_once('_') {
+                Some((a, b)) => format!("<msub>{}{}</msub>", mathml_name(a), mathml_name(b)),
+                None => mathml_name(x),
+            },
+            Val::Bool(b) => format!("<mtext>{}</mtext>", b),
+            Val::Tok(Tok::E) => "<mi>e</mi>".to_owned(),
+            Val::Tok(Tok::Pi) => "<mi>π</mi>".to_owned(),
+            Val::Tok(Tok::I) => "<mi>i</mi>".to_owned(),
+            Val::Tok(Tok::Inf) => "<mi>∞</mi>".to_owned(),
+        }
+    }
+}
+
+/// Single letters in italics, longer names upright
+fn name(x: &str) -> String {
+    let base = x.trim_end_matches('\'');
+    let primes = &x[base.len()..];
+    if let Some(l) = symbols::latex(base) {
+        return format!("{}{}", l, primes);
+    }
+    match base.chars().count() {
+        1 => x.to_owned(),
+        _ => format!("\\mathrm{{{}}}{}", base, primes),
+    }
+}
+
+/// Like [`name`], longer names quoted as upright text
+fn typst_name(x: &str) -> String {
+    let base = x.trim_end_matches('\'');
+    let primes = &x[base.len()..];
+    if let Some(u) = symbols::unicode(base) {
+        return format!("{}{}", u, primes);
+    }
+    match base.chars().count() {
+        1 => x.to_owned(),
+        _ => format!("\"{}\"{}", base, primes),
+    }
+}
+
+/// Like [`name`], digits as a number and primes as `′`
+fn mathml_name(x: &str) -> String {
+    let base = x.trim_end_matches('\'');
+    let primes = "′".repeat(x.len() - base.len());
+    if !base.is_empty() && base.bytes().all(|b| b.is_ascii_digit()) {
+        return format!("<mn>{}</mn>", base);
+    }
+    match symbols::unicode(base) {
+        Some(u) => format!("<mi>{}{}</mi>", u, primes),
+        None => format!("<mi>{}{}</mi>", base, primes),
+    }
+}
+
+fn mo(op: &str) -> String {
+    let op = op
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!("<mo>{}</mo>", op)
+}
+
+/// `x` between stretchy delimiters
+fn fence(open: &str, x: &str, close: &str) -> String {
+    format!("<mrow>{}{}{}</mrow>", mo(open), x, mo(close))
+}
+
+/// `f(args)` with an upright function name
+fn apply(f: &str, args: &[&Ex]) -> String {
+    let args: Vec<String> = args.iter().map(|a| a.mathml()).collect();
+    format!(
+        "<mrow><mi>{}</mi><mo>&#x2061;</mo>{}</mrow>",
+        f,
+        fence("(", &args.join(&mo(",")), ")")
+    )
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Bin {
+    fn to_text(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn to_latex(&self) -> &'static str {
+        match self {
+            Bin::Op(Op::Add) => "+",
+            Bin::Op(Op::Sub) => "-",
+            Bin::Op(Op::Mul) => "\\cdot",
+            Bin::Op(Op::Div) => "/",
+            Bin::Op(Op::Pow) => "^",
+            Bin::Op(Op::Mod) => "\\bmod",
+            Bin::Rl(Rl::Eqq) => "=",
+            Bin::Rl(Rl::Ltt) => "<",
+            Bin::Rl(Rl::Leq) => "\\leq",
+            Bin::Rl(Rl::Gtt) => ">",
+            Bin::Rl(Rl::Geq) => "\\geq",
+            Bin::Rl(Rl::Neq) => "\\neq",
+            Bin::Rl(Rl::Equiv) => "\\equiv",
+            Bin::Rl(Rl::Cong) => "\\cong",
+            Bin::Rl(Rl::Sim) => "\\sim",
+            Bin::Rl(Rl::SimEq) => "\\simeq",
+            Bin::Rl(Rl::Approx) => "\\approx",
+            Bin::Rl(Rl::Asymp) => "\\asymp",
+            Bin::Rl(Rl::Prop) => "\\propto",
+            Bin::Rl(Rl::NEquiv) => "\\not\\equiv",
+            Bin::Rl(Rl::NCong) => "\\ncong",
+            Bin::Rl(Rl::NSim) => "\\nsim",
+            Bin::Rl(Rl::NApprox) => "\\not\\approx",
+            Bin::Rl(Rl::NLtt) => "\\nless",
+            Bin::Rl(Rl::NLeq) => "\\nleq",
+            Bin::Rl(Rl::NGtt) => "\\ngtr",
+            Bin::Rl(Rl::NGeq) => "\\ngeq",
+            Bin::Rl(Rl::In) => "\\in",
+            Bin::Rl(Rl::NotIn) => "\\notin",
+            Bin::Rl(Rl::Sub) => "\\subset",
+            Bin::Rl(Rl::SubEq) => "\\subseteq",
+            Bin::Rl(Rl::Sup) => "\\supset",
+            Bin::Rl(Rl::SupEq) => "\\supseteq",
+            Bin::Rl(Rl::NSub) => "\\not\\subset",
+            Bin::Rl(Rl::NSubEq) => "\\nsubseteq",
+            Bin::Rl(Rl::If) => "\\Leftarrow",
+            Bin::Rl(Rl::To) => "\\to",
+            Bin::Rl(Rl::Implies) => "\\Rightarrow",
+            Bin::Rl(Rl::MapsTo) => "\\mapsto",
+            Bin::Rl(Rl::Iff) => "\\Leftrightarrow",
+            Bin::Rl(Rl::Or) => "\\lor",
+            Bin::Rl(Rl::Where) => "\\mid",
+        }
+    }
+
+    fn to_typst(&self) -> &'static str {
+        match self {
+            Bin::Op(Op::Sub) => "-",
+            Bin::Op(Op::Mul) => "dot",
+            Bin::Op(Op::Mod) => "\"mod\"",
+            Bin::Rl(Rl::Where) => "bar.v",
+            _ => self.symbol(),
+        }
+    }
+
+    /// Unicode symbol, shared by the MathML and Typst output
+    fn symbol(&self) -> &'static str {
+        match self {
+            Bin::Op(Op::Add) => "+",
+            Bin::Op(Op::Sub) => "−",
+            Bin::Op(Op::Mul) => "⋅",
+            Bin::Op(Op::Div) => "/",
+            Bin::Op(Op::Pow) => "^",
+            Bin::Op(Op::Mod) => "mod",
+            Bin::Rl(Rl::Eqq) => "=",
+            Bin::Rl(Rl::Ltt) => "<",
+            Bin::Rl(Rl::Leq) => "≤",
+            Bin::Rl(Rl::Gtt) => ">",
+            Bin::Rl(Rl::Geq) => "≥",
+            Bin::Rl(Rl::Neq) => "≠",
+            Bin::Rl(Rl::Equiv) => "≡",
+            Bin::Rl(Rl::Cong) => "≅",
+            Bin::Rl(Rl::Sim) => "∼",
+            Bin::Rl(Rl::SimEq) => "≃",
+            Bin::Rl(Rl::Approx) => "≈",
+            Bin::Rl(Rl::Asymp) => "≍",
+            Bin::Rl(Rl::Prop) => "∝",
+            Bin::Rl(Rl::NEquiv) => "≢",
+            Bin::Rl(Rl::NCong) => "≇",
+            Bin::Rl(Rl::NSim) => "≁",
+            Bin::Rl(Rl::NApprox) => "≉",
+            Bin::Rl(Rl::NLtt) => "≮",
+            Bin::Rl(Rl::NLeq) => "≰",
+            Bin::Rl(Rl::NGtt) => "≯",
+            Bin::Rl(Rl::NGeq) => "≱",
+            Bin::Rl(Rl::In) => "∈",
+            Bin::Rl(Rl::NotIn) => "∉",
+            Bin::Rl(Rl::Sub) => "⊂",
+            Bin::Rl(Rl::SubEq) => "⊆",
+            Bin::Rl(Rl::Sup) => "⊃",
+            Bin::Rl(Rl::SupEq) => "⊇",
+            Bin::Rl(Rl::NSub) => "⊄",
+            Bin::Rl(Rl::NSubEq) => "⊈",
+            Bin::Rl(Rl::If) => "⇐",
+            Bin::Rl(Rl::To) => "→",
+            Bin::Rl(Rl::Implies) => "⇒",
+            Bin::Rl(Rl::MapsTo) => "↦",
+            Bin::Rl(Rl::Iff) => "⇔",
+            Bin::Rl(Rl::Or) => "∨",
+            Bin::Rl(Rl::Where) => "|",
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Fnc {
+    fn to_latex(&self) -> String {
+        let paren = |a: &Ex| format!("\\left({}\\right)", a.to_latex());
+        // a sum in front of `dx` would read as the sum of two integrals
+        let integrand = |a: &Ex| a.to_latex_in(prec(a) < MUL);
+        match self {
+            Fnc::Ln(a) | Fnc::Sin(a) | Fnc::Cos(a) | Fnc::Tan(a) => {
+                format!("\\{}{}", self.name(), paren(a))
+            }
+            Fnc::Atan(a) => format!("\\arctan{}", paren(a)),
+            Fnc::Abs(a) => format!("\\left|{}\\right|", a.to_latex()),
+            Fnc::Floor(a) => format!("\\left\\lfloor {} \\right\\rfloor", a.to_latex()),
+            Fnc::Ceil(a) => format!("\\left\\lceil {} \\right\\rceil", a.to_latex()),
+            Fnc::Norm(a) => format!("\\left\\| {} \\right\\|", a.to_latex()),
+            Fnc::D(a, x) => format!("\\frac{{d}}{{d{}}}{}", x.to_latex(), paren(a)),
+            Fnc::Int(a, x) => format!("\\int {} \\, d{}", integrand(a), x.to_latex()),
+            Fnc::DefInt(a, x, lo, hi) => format!(
+                "\\int_{{{}}}^{{{}}} {} \\, d{}",
+                lo.to_latex(),
+                hi.to_latex(),
+                integrand(a),
+                x.to_latex()
+            ),
+            Fnc::Lim(a, x, p) => format!(
+                "\\lim_{{{} \\to {}}} {}",
+                x.to_latex(),
+                p.to_latex(),
+                integrand(a)
+            ),
+            Fnc::LimSide(a, x, p, s) => {
+                let side = match s.flt(&[]) {
+                    Some(v) if v < 0.0 => "^{-}",
+                    Some(v) if v > 0.0 => "^{+}",
+                    _ => "",
+                };
+                format!(
+                    "\\lim_{{{} \\to {}{}}} {}",
+                    x.to_latex(),
+                    p.to_latex_in(prec(p) < ATOM && !side.is_empty()),
+                    side,
+                    integrand(a)
+                )
+            }
+            Fnc::Sum(a, k, lo, hi) | Fnc::Prod(a, k, lo, hi) => format!(
+                "\\{}_{{{}={}}}^{{{}}} {}",
+                self.name(),
+                k.to_latex(),
+                lo.to_latex(),
+                hi.to_latex(),
+                integrand(a)
+            ),
+            _ => {
+                let args: Vec<String> = self.args().iter().map(|a| a.to_latex()).collect();
+                format!(
+                    "\\operatorname{{{}}}\\left({}\\right)",
+                    self.name(),
+                    args.join(", ")
+                )
+            }
+        }
+    }
+
+    fn to_typst(&self) -> String {
+        let paren = |a: &Ex| format!("({})", a.to_typst());
+        let integrand = |a: &Ex| a.to_typst_in(prec(a) < MUL);
+        match self {
+            Fnc::Ln(a)
+            | Fnc::Sin(a)
+            | Fnc::Cos(a)
+            | Fnc::Tan(a)
+            | Fnc::Abs(a)
+            | Fnc::Floor(a)
+            | Fnc::Ceil(a)
+            | Fnc::Round(a)
+            | Fnc::Norm(a) => format!("{}{}", self.name(), paren(a)),
+            Fnc::Atan(a) => format!("arctan{}", paren(a)),
+            Fnc::D(a, x) => format!("frac(dif, dif {}) {}", x.to_typst(), paren(a)),
+            Fnc::Int(a, x) => format!("integral {} dif {}", integrand(a), x.to_typst()),
+            Fnc::DefInt(a, x, lo, hi) => format!(
+                "integral_({})^({}) {} dif {}",
+                lo.to_typst(),
+                hi.to_typst(),
+                integrand(a),
+                x.to_typst()
+            ),
+            Fnc::Lim(a, x, p) => format!(
+                "lim_({} -> {}) {}",
+                x.to_typst(),
+                p.to_typst(),
+                integrand(a)
+            ),
+            Fnc::LimSide(a, x, p, s) => {
+                let side = match s.flt(&[]) {
+                    Some(v) if v < 0.0 => "^-",
+                    Some(v) if v > 0.0 => "^+",
+                    _ => "",
+                };
+                format!(
+                    "lim_({} -> {}{}) {}",
+                    x.to_typst(),
+                    p.to_typst_in(prec(p) < ATOM && !side.is_empty()),
+                    side,
+                    integrand(a)
+                )
+            }
+            Fnc::Sum(a, k, lo, hi) | Fnc::Prod(a, k, lo, hi) => format!(
+                "{}_({} = {})^({}) {}",
+                match self {
+                    Fnc::Sum(..) => "sum",
+                    _ => "product",
+                },
+                k.to_typst(),
+                lo.to_typst(),
+                hi.to_typst(),
+                integrand(a)
+            ),
+            _ => {
+                let args: Vec<String> = self.args().iter().map(|a| a.to_typst()).collect();
+                format!("op(\"{}\")({})", self.name(), args.join(", "))
+            }
+        }
+    }
+
+    fn to_mathml(&self) -> String {
+        let paren = |a: &Ex| fence("(", &a.mathml(), ")");
+        let integrand = |a: &Ex| a.to_mathml_in(prec(a) < MUL);
+        let d = |x: &Ex| {
+            format!(
+                "<mrow><mi mathvariant=\"normal\">d</mi>{}</mrow>",
+                x.mathml()
+            )
+        };
+        match self {
+            Fnc::Atan(a) => apply("arctan", &[a]),
+            Fnc::Abs(a) => fence("|", &a.mathml(), "|"),
+            Fnc::Floor(a) => fence("⌊", &a.mathml(), "⌋"),
+            Fnc::Ceil(a) => fence("⌈", &a.mathml(), "⌉"),
+            Fnc::Norm(a) => fence("‖", &a.mathml(), "‖"),
+            Fnc::D(a, x) => format!(
+                "<mrow><mfrac><mi mathvariant=\"normal\">d</mi>{}</mfrac>{}</mrow>",
+                d(x),
+                paren(a)
+            ),
+            Fnc::Int(a, x) => format!("<mrow>{}{}{}</mrow>", mo("∫"), integrand(a), d(x)),
+            Fnc::DefInt(a, x, lo, hi) => format!(
+                "<mrow><msubsup>{}{}{}</msubsup>{}{}</mrow>",
+                mo("∫"),
+                lo.mathml(),
+                hi.mathml(),
+                integrand(a),
+                d(x)
+            ),
+            Fnc::Lim(a, x, p) => format!(
+                "<mrow><munder><mo>lim</mo><mrow>{}{}{}</mrow></munder>{}</mrow>",
+                x.mathml(),
+                mo("→"),
+                p.mathml(),
+                integrand(a)
+            ),
+            Fnc::LimSide(a, x, p, s) => {
+                let side = match s.flt(&[]) {
+                    Some(v) if v < 0.0 => "−",
+                    Some(v) if v > 0.0 => "+",
+                    _ => "",
+                };
+                let p = match side {
+                    "" => p.mathml(),
+                    _ => format!(
+                        "<msup>{}{}</msup>",
+                        p.to_mathml_in(prec(p) < ATOM),
+                        mo(side)
+                    ),
+                };
+                format!(
+                    "<mrow><munder><mo>lim</mo><mrow>{}{}{}</mrow></munder>{}</mrow>",
+                    x.mathml(),
+                    mo("→"),
+                    p,
+                    integrand(a)
+                )
+            }
+            Fnc::Sum(a, k, lo, hi) | Fnc::Prod(a, k, lo, hi) => format!(
+                "<mrow><munderover>{}<mrow>{}{}{}</mrow>{}</munderover>{}</mrow>",
+                mo(match self {
+                    Fnc::Sum(..) => "∑",
+                    _ => "∏",
+                }),
+                k.mathml(),
+                mo("="),
+                lo.mathml(),
+                hi.mathml(),
+                integrand(a)
+            ),
+            _ => apply(self.name(), &self.args()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+const MUL: u8 = 7;
+const NEG: u8 = 8;
+const ATOM: u8 = 10;
+
+/// Binding strength, higher binds tighter
+fn prec(e: &Ex) -> u8 {
+    match e {
+        Ex::Infix(_, Bin::Rl(Rl::Where), _) => 1,
+        Ex::Infix(_, Bin::Rl(Rl::Iff), _) => 2,
+        Ex::Infix(_, Bin::Rl(Rl::If | Rl::Implies | Rl::To | Rl::MapsTo), _) => 3,
+        Ex::Infix(_, Bin::Rl(Rl::Or), _) => 4,
+        Ex::Infix(_, Bin::Rl(_), _) => 5,
+        Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _) => 6,
+        Ex::Infix(_, Bin::Op(Op::Mul | Op::Div | Op::Mod), _) => MUL,
+        Ex::Neg(_) => NEG,
+        Ex::Val(Val::Num(Num::Int(n))) if *n < 0 => NEG,
+        Ex::Val(Val::Num(Num::Flt(v))) if *v < 0.0 => NEG,
+        Ex::Val(Val::Num(Num::Big(v))) if v.is_neg() => NEG,
+        Ex::Infix(_, Bin::Op(Op::Pow), _) => 9,
+        _ => ATOM,
+    }
+}
+
+/// Operands of an infix node, each with whether it needs brackets
+///
+/// - `^` and the arrows group to the right, everything else to the left
+/// - A negative right operand of an operator is always bracketed
+fn operands<'a>(e: &Ex, a: &'a Ex, b: &'a Ex) -> ((&'a Ex, bool), (&'a Ex, bool)) {
+    let p = prec(e);
+    let (pa, pb) = (prec(a), prec(b));
+    let rtl = matches!(
+        e,
+        Ex::Infix(
+            _,
+            Bin::Op(Op::Pow) | Bin::Rl(Rl::If | Rl::Implies | Rl::To | Rl::MapsTo),
+            _
+        )
+    );
+    let op = matches!(e, Ex::Infix(_, Bin::Op(_), _));
+    let left = pa < p || (rtl && pa == p);
+    let right = pb < p || (!rtl && pb == p && !associative(e)) || (op && pb == NEG);
+    ((a, left), (b, right))
+}
+
+fn associative(e: &Ex) -> bool {
+    matches!(e, Ex::Infix(_, Bin::Op(Op::Add | Op::Mul), _))
+}
+
+/// Degree `n` when `k` is `1/n`
+fn root(k: &Ex) -> Option<Ex> {
+    match k {
+        Ex::Infix(one, Bin::Op(Op::Div), n) if one.is_one() => Some(*n.clone()),
+        _ => None,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::parse;
+
+    #[test]
+    fn ex3_render_text() {
+        for s in [
+            "x^2 + 2*x + 1",
+            "a - (b + c)",
+            "(a + b)*c",
+            "a/(b*c)",
+            "(a^b)^c",
+            "a^b^c",
+            "-(x^2)",
+            "x*(-y)",
+            "sin(x + 1)",
+            "d(x^2, x)",
+            "x = 1 or x = -1",
+            "[1, 2.5]",
+        ] {
+            assert_eq!(parse(s).to_text(), s);
+            assert_eq!(parse(&parse(s).to_text()), parse(s));
+        }
+        assert_eq!(parse("((a+b)+c)").to_text(), "a + b + c");
+    }
+
+    #[test]
+    fn ex3_render_latex() {
+        assert_eq!(parse("(x+1)/2").to_latex(), "\\frac{x + 1}{2}");
+        assert_eq!(parse("(x+1)^2").to_latex(), "\\left(x + 1\\right)^{2}");
+        assert_eq!(parse("e^(x*y)").to_latex(), "e^{x \\cdot y}");
+        assert_eq!(parse("x^(1/2)").to_latex(), "\\sqrt{x}");
+        assert_eq!(parse("x^(1/3)").to_latex(), "\\sqrt[3]{x}");
+        assert_eq!(
+            parse("-(a-b)*pi").to_latex(),
+            "-\\left(a - b\\right) \\cdot \\pi"
+        );
+        assert_eq!(parse("sin(x) > 1").to_latex(), "\\sin\\left(x\\right) > 1");
+        assert_eq!(
+            parse("d(x^2, x)").to_latex(),
+            "\\frac{d}{dx}\\left(x^{2}\\right)"
+        );
+        assert_eq!(
+            parse("int(t, t, 0, inf)").to_latex(),
+            "\\int_{0}^{\\infty} t \\, dt"
+        );
+        assert_eq!(
+            parse("lim(sin(x)/x, x, 0, -1)").to_latex(),
+            "\\lim_{x \\to 0^{-}} \\frac{\\sin\\left(x\\right)}{x}"
+        );
+        assert_eq!(
+            parse("int(x+1, x)").to_latex(),
+            "\\int \\left(x + 1\\right) \\, dx"
+        );
+        assert_eq!(
+            parse("sum(k^2, k, 1, n)").to_latex(),
+            "\\sum_{k=1}^{n} k^{2}"
+        );
+        assert_eq!(
+            parse("factor(x_ab)").to_latex(),
+            "\\operatorname{factor}\\left(x_{\\mathrm{ab}}\\right)"
+        );
+        assert_eq!(
+            parse("[[1, 2], [3, 4]]").to_latex(),
+            "\\begin{bmatrix} 1 & 2 \\\\ 3 & 4 \\end{bmatrix}"
+        );
+    }
+
+    #[test]
+    fn ex3_render_typst() {
+        assert_eq!(parse("(x+1)/2").to_typst(), "frac(x + 1, 2)");
+        assert_eq!(parse("(x+1)^2").to_typst(), "(x + 1)^(2)");
+        assert_eq!(
+            parse("x^(1/2) + x^(1/3)").to_typst(),
+            "sqrt(x) + root(3, x)"
+        );
+        assert_eq!(parse("-(a-b)*pi").to_typst(), "-(a - b) dot pi");
+        assert_eq!(parse("a - (b + c)").to_typst(), "a - (b + c)");
+        assert_eq!(parse("x ≤ -1 or θ' ≠ 2.5").to_typst(), "x ≤ -1 ∨ θ' ≠ 2.5");
+        assert_eq!(parse("d(x^2, x)").to_typst(), "frac(dif, dif x) (x^(2))");
+        assert_eq!(
+            parse("int(t, t, 0, inf)").to_typst(),
+            "integral_(0)^(infinity) t dif t"
+        );
+        assert_eq!(
+            parse("lim(sin(x)/x, x, 0, -1)").to_typst(),
+            "lim_(x -> 0^-) frac(sin(x), x)"
+        );
+        assert_eq!(
+            parse("sum(k^2, k, 1, n)").to_typst(),
+            "sum_(k = 1)^(n) k^(2)"
+        );
+        assert_eq!(parse("abs(x) + ⌊y⌋").to_typst(), "abs(x) + floor(y)");
+        assert_eq!(parse("factor(x_ab)").to_typst(), "op(\"factor\")(x_\"ab\")");
+        assert_eq!(parse("[[1, 2], [3, 4]]").to_typst(), "mat(1, 2; 3, 4)");
+    }
+
+    #[test]
+    fn ex3_render_mathml() {
+        let m = |s: &str| {
+            let m = parse(s).to_mathml();
+            let inner = m
+                .strip_prefix("<math xmlns=\"http://www.w3.org/1998/Math/MathML\">")
+                .and_then(|m| m.strip_suffix("</math>"));
+            inner.unwrap().to_owned()
+        };
+        assert_eq!(
+            m("(x+1)/2"),
+            "<mfrac><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mn>2</mn></mfrac>"
+        );
+        assert_eq!(
+            m("(x+1)^2"),
+            concat!(
+                "<msup><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow>",
+                "<mo>)</mo></mrow><mn>2</mn></msup>"
+            )
+        );
+        assert_eq!(m("x^(1/3)"), "<mroot><mi>x</mi><mn>3</mn></mroot>");
+        assert_eq!(
+            m("a^b^c"),
+            "<msup><mi>a</mi><msup><mi>b</mi><mi>c</mi></msup></msup>"
+        );
+        assert_eq!(
+            m("x*(-y)"),
+            concat!(
+                "<mrow><mi>x</mi><mo>⋅</mo><mrow><mo>(</mo><mrow><mo>−</mo>",
+                "<mi>y</mi></mrow><mo>)</mo></mrow></mrow>"
+            )
+        );
+        assert_eq!(
+            m("sin(x) > -1"),
+            concat!(
+                "<mrow><mrow><mi>sin</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi>",
+                "<mo>)</mo></mrow></mrow><mo>&gt;</mo><mrow><mo>−</mo><mn>1</mn></mrow></mrow>"
+            )
+        );
+        assert_eq!(
+            m("f_1 ∈ θ'"),
+            "<mrow><msub><mi>f</mi><mn>1</mn></msub><mo>∈</mo><mi>θ′</mi></mrow>"
+        );
+        assert_eq!(
+            m("sum(k, k, 1, n)"),
+            concat!(
+                "<mrow><munderover><mo>∑</mo><mrow><mi>k</mi><mo>=</mo><mn>1</mn></mrow>",
+                "<mi>n</mi></munderover><mi>k</mi></mrow>"
+            )
+        );
+        assert_eq!(
+            m("lim(1/x, x, 0, 1)"),
+            concat!(
+                "<mrow><munder><mo>lim</mo><mrow><mi>x</mi><mo>→</mo><msup><mn>0</mn>",
+                "<mo>+</mo></msup></mrow></munder><mfrac><mn>1</mn><mi>x</mi></mfrac></mrow>"
+            )
+        );
+        assert_eq!(
+            m("[[1, 2], [3, 4]]"),
+            concat!(
+                "<mrow><mo>[</mo><mtable><mtr><mtd><mn>1</mn></mtd><mtd><mn>2</mn></mtd></mtr>",
+                "<mtr><mtd><mn>3</mn></mtd><mtd><mn>4</mn></mtd></mtr></mtable><mo>]</mo></mrow>"
+            )
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
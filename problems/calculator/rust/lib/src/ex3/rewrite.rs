@@ -0,0 +1,395 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::mem::discriminant;
+
+use super::ast::*;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Most rule applications in one normalization
+const MAX_STEPS: usize = 1_000;
+
+/// Largest expression a normalization may build
+const MAX_SIZE: usize = 10_000;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Subexpressions bound to the wildcards of a pattern
+#[derive(Clone, Debug, Default)]
+pub struct Bindings(Vec<(String, Ex)>);
+
+impl Bindings {
+    pub fn get(&self, name: &str) -> &Ex {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, e)| e)
+            .unwrap_or(&Ex::Invalid)
+    }
+}
+
+/// Right hand side of a rule
+#[derive(Clone)]
+pub enum Rhs {
+    /// Pattern with the bound wildcards substituted in
+    Pattern(Ex),
+    /// Computed from the bindings, the rule does not apply on `None`
+    Eval(fn(&Bindings) -> Option<Ex>),
+}
+
+/// Rewrite rule `lhs -> rhs if cond`
+///
+/// - Every variable in a pattern is a wildcard, a repeated wildcard only
+///   matches equal subexpressions
+/// - Numbers and constants only match themselves
+#[derive(Clone)]
+pub struct RwRule {
+    pub name: &'static str,
+    pub lhs: Ex,
+    pub rhs: Rhs,
+    pub cond: fn(&Bindings) -> bool,
+}
+
+impl RwRule {
+    pub fn new(name: &'static str, lhs: &str, rhs: &str) -> Self {
+        RwRule {
+            name,
+            lhs: lhs.parse().unwrap(),
+            rhs: Rhs::Pattern(rhs.parse().unwrap()),
+            cond: |_| true,
+        }
+    }
+
+    pub fn eval(name: &'static str, lhs: &str, f: fn(&Bindings) -> Option<Ex>) -> Self {
+        RwRule {
+            name,
+            lhs: lhs.parse().unwrap(),
+            rhs: Rhs::Eval(f),
+            cond: |_| true,
+        }
+    }
+
+    /// Only apply when `cond` holds for the bindings
+    pub fn when(self, cond: fn(&Bindings) -> bool) -> Self {
+        RwRule { cond, ..self }
+    }
+
+    /// Rewrite `e` at the root
+    pub fn apply(&self, e: &Ex) -> Option<Ex> {
+        let mut b = Bindings::default();
+        if !matches(&self.lhs, e, &mut b) || !(self.cond)(&b) {
+            return None;
+        }
+        match &self.rhs {
+            Rhs::Pattern(p) => Some(substitute(p, &b)),
+            Rhs::Eval(f) => f(&b),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+fn matches(p: &Ex, e: &Ex, b: &mut Bindings) -> bool {
+    match (p, e) {
+        (Ex::Val(Val::Var(v)), _) => match b.0.iter().find(|(n, _)| n == v) {
+            Some((_, x)) => x == e,
+            None => {
+                b.0.push((v.clone(), e.clone()));
+                true
+            }
+        },
+        (Ex::Infix(pa, pj, pb), Ex::Infix(a, j, c)) => {
+            pj == j && matches(pa, a, b) && matches(pb, c, b)
+        }
+        (Ex::Neg(pa), Ex::Neg(a)) => matches(pa, a, b),
+        (Ex::Fn(pf), Ex::Fn(f)) => {
+            discriminant(pf) == discriminant(f)
+                && pf
+                    .args()
+                    .iter()
+                    .zip(f.args())
+                    .all(|(p, a)| matches(p, a, b))
+        }
+        (Ex::Mat(ps), Ex::Mat(xs)) => {
+            ps.len() == xs.len() && ps.iter().zip(xs).all(|(p, x)| matches(p, x, b))
+        }
+        _ => p == e,
+    }
+}
+
+fn substitute(p: &Ex, b: &Bindings) -> Ex {
+    match p {
+        Ex::Val(Val::Var(v)) => b.get(v).clone(),
+        _ => p.map(|c| substitute(c, b)),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Term rewriting system, applied bottom-up until nothing changes
+#[derive(Clone)]
+pub struct Rewrite {
+    rules: Vec<RwRule>,
+}
+
+impl Rewrite {
+    pub fn new(rules: Vec<RwRule>) -> Self {
+        Rewrite { rules }
+    }
+
+    /// Identities of `0`, `1`, `e` and `ln`, plus exact constant folding
+    ///
+    /// - Gives unique normal forms: every critical pair joins, so the
+    ///   result does not depend on the order in which rules fire
+    /// - Neither commutative nor associative, `x+1+2` is left alone
+    /// - Rules with an exact result skip operands holding a float, which
+    ///   could fold to a float instead
+    pub fn standard() -> Self {
+        Rewrite::new(vec![
+            RwRule::new("add_zero", "a+0", "a"),
+            RwRule::new("zero_add", "0+a", "a"),
+            RwRule::new("sub_zero", "a-0", "a"),
+            RwRule::new("sub_self", "a-a", "0").when(exact),
+            RwRule::new("mul_one", "a*1", "a"),
+            RwRule::new("one_mul", "1*a", "a"),
+            RwRule::new("mul_zero", "a*0", "0").when(exact),
+            RwRule::new("zero_mul", "0*a", "0").when(exact),
+            RwRule::new("div_one", "a/1", "a"),
+            RwRule::new("pow_one", "a^1", "a"),
+            RwRule::new("pow_zero", "a^0", "1").when(exact),
+            RwRule::new("one_pow", "1^a", "1").when(exact),
+            RwRule::new("neg_neg", "-(-a)", "a"),
+            RwRule::new("ln_one", "ln(1)", "0"),
+            RwRule::new("ln_e", "ln(e)", "1"),
+            RwRule::new("ln_exp", "ln(e^a)", "a"),
+            RwRule::new("exp_ln", "e^ln(a)", "a"),
+            RwRule::new("sin_zero", "sin(0)", "0"),
+            RwRule::new("cos_zero", "cos(0)", "1"),
+            RwRule::new("abs_neg", "abs(-a)", "abs(a)"),
+            RwRule::new("abs_abs", "abs(abs(a))", "abs(a)"),
+            RwRule::eval("fold_add", "a+b", |b| fold(Op::Add, b.get("a"), b.get("b"))),
+            RwRule::eval("fold_sub", "a-b", |b| fold(Op::Sub, b.get("a"), b.get("b"))),
+            RwRule::eval("fold_mul", "a*b", |b| fold(Op::Mul, b.get("a"), b.get("b"))),
+            RwRule::eval("fold_div", "a/b", |b| fold(Op::Div, b.get("a"), b.get("b"))),
+            RwRule::eval("fold_pow", "a^b", |b| fold(Op::Pow, b.get("a"), b.get("b"))),
+            RwRule::eval("fold_neg", "-a", |b| {
+                fold(Op::Sub, &Ex::from(0), b.get("a"))
+            }),
+            RwRule::eval("fold_abs", "abs(a)", |b| match b.get("a") {
+                Ex::Val(Val::Num(Num::Int(n))) => n.checked_abs().map(Ex::from),
+                Ex::Val(Val::Num(Num::Flt(v))) => Some(Ex::from(v.abs())),
+                _ => None,
+            }),
+        ])
+    }
+
+    pub fn rules(&self) -> &[RwRule] {
+        &self.rules
+    }
+
+    /// Rewrite to normal form
+    ///
+    /// - `None` if the step or size guard trips first, which can only
+    ///   happen for a rule set that does not terminate
+    pub fn normalize(&self, e: &Ex) -> Option<Ex> {
+        let mut steps = 0;
+        let mut e = e.clone();
+        loop {
+            let next = self.pass(&e, &mut steps)?;
+            if next == e {
+                return Some(e);
+            }
+            e = next;
+        }
+    }
+
+    /// Every expression one rule application away, with the rule name
+    pub fn successors(&self, e: &Ex) -> Vec<(&'static str, Ex)> {
+        let mut out: Vec<(&'static str, Ex)> = self
+            .rules
+            .iter()
+            .filter_map(|r| r.apply(e).map(|x| (r.name, x)))
+            .collect();
+        let children = e.children();
+        for (i, c) in children.iter().enumerate() {
+            for (name, x) in self.successors(c) {
+                let mut k = 0;
+                let rebuilt = e.map(|d| {
+                    k += 1;
+                    if k - 1 == i {
+                        x.clone()
+                    } else {
+                        d.clone()
+                    }
+                });
+                out.push((name, rebuilt));
+            }
+        }
+        out
+    }
+
+    /// Normalize the children, then rewrite at the root until no rule applies
+    fn pass(&self, e: &Ex, steps: &mut usize) -> Option<Ex> {
+        let mut failed = false;
+        let mut e = e.map(|c| match self.pass(c, steps) {
+            Some(c) => c,
+            None => {
+                failed = true;
+                c.clone()
+            }
+        });
+        if failed {
+            return None;
+        }
+        while let Some(next) = self.rules.iter().find_map(|r| r.apply(&e)) {
+            *steps += 1;
+            if *steps > MAX_STEPS || next.size() > MAX_SIZE {
+                return None;
+            }
+            e = next;
+        }
+        Some(e)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Wildcard `a` is bound to an expression without floats
+fn exact(b: &Bindings) -> bool {
+    fn go(e: &Ex) -> bool {
+        !matches!(e, Ex::Val(Val::Num(Num::Flt(_)))) && e.children().into_iter().all(go)
+    }
+    go(b.get("a"))
+}
+
+/// Exact arithmetic on two numbers, floats only if a float is involved
+fn fold(op: Op, a: &Ex, b: &Ex) -> Option<Ex> {
+    let (Ex::Val(Val::Num(x)), Ex::Val(Val::Num(y))) = (a, b) else {
+        return None;
+    };
+    let v = match (x.clone(), y.clone()) {
+        (Num::Big(_), _) | (_, Num::Big(_)) => return None,
+        (Num::Int(x), Num::Int(y)) => match op {
+            Op::Add => x.checked_add(y),
+            Op::Sub => x.checked_sub(y),
+            Op::Mul => x.checked_mul(y),
+            Op::Div if x.checked_rem(y) == Some(0) => x.checked_div(y),
+            Op::Pow => x.checked_pow(u32::try_from(y).ok()?),
+            _ => None,
+        }
+        .map(Ex::from)?,
+        (x, y) => {
+            let (x, y) = (x.flt(), y.flt());
+            let v = match op {
+                Op::Add => x + y,
+                Op::Sub => x - y,
+                Op::Mul => x * y,
+                Op::Div => x / y,
+                Op::Pow => x.powf(y),
+                Op::Mod => x.rem_euclid(y),
+            };
+            v.is_finite().then(|| Ex::from(v))?
+        }
+    };
+    Some(v)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::parse;
+
+    fn norm(s: &str) -> Ex {
+        Rewrite::standard().normalize(&parse(s)).unwrap()
+    }
+
+    /// Every normal form reachable from `e` by any order of rewrites
+    fn normal_forms(rw: &Rewrite, e: &Ex, out: &mut Vec<Ex>) {
+        let next = rw.successors(e);
+        if next.is_empty() && !out.contains(e) {
+            out.push(e.clone());
+        }
+        for (_, x) in next {
+            normal_forms(rw, &x, out);
+        }
+    }
+
+    #[test]
+    fn ex3_rewrite_normalize() {
+        assert_eq!(norm("x*1+0"), parse("x"));
+        assert_eq!(norm("(2+3)*x^(4-3)"), parse("5") * parse("x"));
+        assert_eq!(norm("ln(e^(y*0))+sin(0)"), parse("0"));
+        assert_eq!(norm("e^ln(x-x+1)"), parse("1"));
+        assert_eq!(norm("-(-(x))/1"), parse("x"));
+        assert_eq!(norm("7/2"), parse("7/2"));
+        assert_eq!(norm("x+1+2"), parse("x+1+2"));
+        assert_eq!(norm("abs(-(abs(-x)))"), parse("abs(x)"));
+        assert_eq!(norm("abs(2-5)"), parse("3"));
+    }
+
+    #[test]
+    fn ex3_rewrite_unique() {
+        let rw = Rewrite::standard();
+        for s in [
+            "0*0",
+            "1^0",
+            "0-0",
+            "(0+x)*1",
+            "e^ln(e)",
+            "ln(e^1)",
+            "ln(e^0)",
+            "e^ln(1)",
+            "-(-0)",
+            "-(-(3))",
+            "(x-x)^(1*0)",
+            "(2*0)+(y^1-y)",
+            "ln(e^(x*1))^0",
+            "2.5*0",
+            "(1.5-1.5)^0",
+            "abs(-(3))",
+            "abs(-(-x))",
+            "abs(abs(-(2.5)))",
+        ] {
+            let mut forms = vec![];
+            normal_forms(&rw, &parse(s), &mut forms);
+            assert_eq!(forms, vec![norm(s)], "{}", s);
+        }
+
+        // the result does not depend on rule order
+        let mut rules = rw.rules().to_vec();
+        rules.reverse();
+        let reversed = Rewrite::new(rules);
+        for s in ["(0+x)*1", "e^ln(1)", "(x-x)^(1*0)", "2^3*x-0"] {
+            assert_eq!(reversed.normalize(&parse(s)), rw.normalize(&parse(s)));
+        }
+    }
+
+    #[test]
+    fn ex3_rewrite_guard() {
+        let loops = Rewrite::new(vec![
+            RwRule::new("swap", "a+b", "b+a"),
+            RwRule::new("grow", "ln(a)", "ln(a+0)"),
+        ]);
+        assert_eq!(loops.normalize(&parse("x+y")), None);
+        assert_eq!(loops.normalize(&parse("ln(x)")), None);
+
+        let positive = Rewrite::new(vec![RwRule::new("abs", "-a", "a")
+            .when(|b| matches!(b.get("a"), Ex::Val(Val::Num(Num::Int(n))) if *n > 0))]);
+        assert_eq!(positive.normalize(&parse("-(3)")), Some(parse("3")));
+        assert_eq!(positive.normalize(&parse("-x")), Some(parse("-x")));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
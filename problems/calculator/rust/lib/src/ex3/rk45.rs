@@ -0,0 +1,181 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Dormand–Prince nodes
+const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+
+/// Dormand–Prince stage coefficients, row `i` builds stage `i`
+const A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+        0.0,
+        0.0,
+    ],
+    [
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+        0.0,
+    ],
+    [
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+
+/// Fifth order weights, the step that is kept
+const B5: [f64; 7] = [
+    35.0 / 384.0,
+    0.0,
+    500.0 / 1113.0,
+    125.0 / 192.0,
+    -2187.0 / 6784.0,
+    11.0 / 84.0,
+    0.0,
+];
+
+/// Embedded fourth order weights, for the error estimate
+const B4: [f64; 7] = [
+    5179.0 / 57600.0,
+    0.0,
+    7571.0 / 16695.0,
+    393.0 / 640.0,
+    -92097.0 / 339200.0,
+    187.0 / 2100.0,
+    1.0 / 40.0,
+];
+
+/// Most steps, accepted or rejected, before giving up
+const MAX_STEPS: usize = 100_000;
+
+/// Steps the interval is split into for the first attempt
+const INITIAL_STEPS: f64 = 100.0;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Result of a numeric integration of an initial value problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory {
+    /// Accepted points `(x, y)`, starting with the initial value
+    pub points: Vec<(f64, Vec<f64>)>,
+    /// Whether the end of the interval was reached
+    pub converged: bool,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Integrate the system `y' = f(x, y)` from `(x0, y0)` to `x1`
+///
+/// - Adaptive Dormand–Prince 5(4) steps, with the error of each component
+///   kept below `tol` relative to its size
+/// - `x1` may lie on either side of `x0`
+pub fn integrate<F: Fn(f64, &[f64]) -> Vec<f64>>(
+    f: F,
+    x0: f64,
+    y0: &[f64],
+    x1: f64,
+    tol: f64,
+) -> Trajectory {
+    let (mut x, mut y) = (x0, y0.to_vec());
+    let mut points = vec![(x, y.clone())];
+    let dir = (x1 - x0).signum();
+    let mut h = (x1 - x0).abs() / INITIAL_STEPS;
+
+    for _ in 0..MAX_STEPS {
+        if (x1 - x) * dir <= 0.0 {
+            return Trajectory {
+                points,
+                converged: true,
+            };
+        }
+        h = h.min((x1 - x).abs());
+        if h <= 1e-14 * (1.0 + x.abs()) {
+            break;
+        }
+
+        let mut k: Vec<Vec<f64>> = Vec::with_capacity(7);
+        for (c, a) in C.iter().zip(A.iter()) {
+            let yi: Vec<f64> = (0..y.len())
+                .map(|j| y[j] + dir * h * k.iter().zip(a).map(|(k, a)| a * k[j]).sum::<f64>())
+                .collect();
+            k.push(f(x + dir * h * c, &yi));
+        }
+        let step = |b: &[f64; 7]| -> Vec<f64> {
+            (0..y.len())
+                .map(|j| y[j] + dir * h * k.iter().zip(b).map(|(k, b)| b * k[j]).sum::<f64>())
+                .collect()
+        };
+        let (y5, y4) = (step(&B5), step(&B4));
+
+        let err = y5
+            .iter()
+            .zip(&y4)
+            .map(|(a, b)| (a - b).abs() / (tol * (1.0 + a.abs())))
+            .fold(0.0, f64::max);
+        if err.is_nan() || y5.iter().any(|v| !v.is_finite()) {
+            break;
+        }
+        if err <= 1.0 {
+            x += dir * h;
+            y = y5;
+            points.push((x, y.clone()));
+        }
+        h *= (0.9 * err.powf(-0.2)).clamp(0.2, 5.0);
+    }
+    Trajectory {
+        points,
+        converged: false,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ex3_rk45_integrate() {
+        // y' = y, y(0) = 1
+        let t = integrate(|_, y| vec![y[0]], 0.0, &[1.0], 1.0, 1e-10);
+        assert!(t.converged);
+        let (x, y) = t.points.last().unwrap();
+        assert_eq!(*x, 1.0);
+        assert!((y[0] - std::f64::consts::E).abs() < 1e-8);
+
+        // y'' = -y backwards from y(0) = 0, y'(0) = 1
+        let t = integrate(|_, y| vec![y[1], -y[0]], 0.0, &[0.0, 1.0], -2.0, 1e-10);
+        let (_, y) = t.points.last().unwrap();
+        assert!((y[0] - (-2.0f64).sin()).abs() < 1e-8);
+
+        // blows up at x = 1
+        let t = integrate(|_, y| vec![y[0] * y[0]], 0.0, &[1.0], 2.0, 1e-8);
+        assert!(!t.converged);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,408 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Most iterations of either method before giving up
+const MAX_ITER: usize = 200;
+
+/// Relative step size at which an iteration counts as converged
+const TOL: f64 = 1e-13;
+
+/// Most doublings of the search interval when looking for a sign change
+const MAX_EXPAND: usize = 64;
+
+/// Subintervals scanned by [`zeros`]
+const SAMPLES: usize = 400;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Method that produced a [`Root`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Newton,
+    Brent,
+}
+
+/// Result of a numeric root search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Root {
+    /// Approximate root
+    pub value: f64,
+    /// `|f(value)|`
+    pub residual: f64,
+    /// Iterations spent, across both methods
+    pub iterations: usize,
+    /// Whether the iteration settled within tolerance
+    pub converged: bool,
+    /// Method that produced `value`
+    pub method: Method,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Numeric root of the equation `self` in `x`, starting from `guess`
+    ///
+    /// - Anything that is not an equation is solved as `self = 0`
+    /// - Newton's method with the symbolic derivative, or a central
+    ///   difference where there is none
+    /// - Falls back to Brent's method on a bracket grown around `guess` when
+    ///   Newton diverges
+    pub fn nsolve(&self, x: &str, guess: f64) -> Root {
+        let f = residual(self);
+        let df = f.derivative(x);
+        let eval = |v: f64| f.flt(&[(x, v)]).unwrap_or(f64::NAN);
+        let slope = |v: f64| match df.flt(&[(x, v)]) {
+            Some(d) => d,
+            None => central(&eval, v),
+        };
+        nsolve(eval, slope, guess)
+    }
+
+    /// Every numeric root of `self` in `x` between `a` and `b`
+    ///
+    /// - Sign changes are refined with Brent's method, poles are discarded
+    /// - Roots that only touch zero are found with Newton's method from local
+    ///   minima of `|f|`
+    pub fn zeros(&self, x: &str, a: f64, b: f64) -> Vec<Root> {
+        let f = residual(self);
+        let df = f.derivative(x);
+        let eval = |v: f64| f.flt(&[(x, v)]).unwrap_or(f64::NAN);
+        let slope = |v: f64| match df.flt(&[(x, v)]) {
+            Some(d) => d,
+            None => central(&eval, v),
+        };
+        zeros(eval, slope, a, b)
+    }
+}
+
+/// `lhs - rhs` of an equation, or the expression itself
+fn residual(e: &Ex) -> Ex {
+    match e {
+        Ex::Infix(a, Bin::Rl(Rl::Eqq), b) => {
+            sum(&[a.simplified(), product(&[Ex::from(-1), b.simplified()])])
+        }
+        _ => e.simplified(),
+    }
+}
+
+/// Central difference, for when there is no symbolic derivative
+fn central<F: Fn(f64) -> f64>(f: &F, x: f64) -> f64 {
+    let h = 1e-6 * (1.0 + x.abs());
+    (f(x + h) - f(x - h)) / (2.0 * h)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Newton's method from `guess`, then Brent's method if that diverges
+pub fn nsolve<F: Fn(f64) -> f64, D: Fn(f64) -> f64>(f: F, df: D, guess: f64) -> Root {
+    let root = newton(&f, &df, guess);
+    if root.converged {
+        return root;
+    }
+    match bracket(&f, guess) {
+        Some((a, b)) => {
+            let r = brent(&f, a, b);
+            Root {
+                iterations: r.iterations + root.iterations,
+                ..r
+            }
+        }
+        None => root,
+    }
+}
+
+/// Every root of `f` between `a` and `b`, in increasing order
+pub fn zeros<F: Fn(f64) -> f64, D: Fn(f64) -> f64>(f: F, df: D, a: f64, b: f64) -> Vec<Root> {
+    let (a, b) = (a.min(b), a.max(b));
+    if !a.is_finite() || !b.is_finite() {
+        return vec![];
+    }
+    let xs: Vec<f64> = (0..=SAMPLES)
+        .map(|i| a + (b - a) * i as f64 / SAMPLES as f64)
+        .collect();
+    let ys: Vec<f64> = xs.iter().map(|&x| f(x)).collect();
+    let scale = ys
+        .iter()
+        .filter(|y| y.is_finite())
+        .fold(0.0_f64, |m, y| m.max(y.abs()));
+    let small = |r: &Root| r.converged && r.residual <= 1e-9 * (1.0 + scale);
+
+    let mut out: Vec<Root> = vec![];
+    let mut push = |r: Root| {
+        if !out
+            .iter()
+            .any(|o| (o.value - r.value).abs() <= 1e-9 * (1.0 + r.value.abs()))
+        {
+            out.push(r);
+        }
+    };
+    for i in 0..=SAMPLES {
+        let (x, y) = (xs[i], ys[i]);
+        if y == 0.0 {
+            push(Root {
+                value: x,
+                residual: 0.0,
+                iterations: 0,
+                converged: true,
+                method: Method::Brent,
+            });
+            continue;
+        }
+        if i < SAMPLES && y * ys[i + 1] < 0.0 {
+            let r = brent(&f, x, xs[i + 1]);
+            // a sign change across a pole converges too, but not to a root
+            if small(&r) {
+                push(r);
+            }
+            continue;
+        }
+        let dip = i > 0
+            && i < SAMPLES
+            && y.abs() <= ys[i - 1].abs()
+            && y.abs() <= ys[i + 1].abs()
+            && y * ys[i - 1] > 0.0
+            && y * ys[i + 1] > 0.0;
+        if dip {
+            let r = newton(&f, &df, x);
+            if small(&r) && xs[i - 1] <= r.value && r.value <= xs[i + 1] {
+                push(r);
+            }
+        }
+    }
+    out.sort_by(|p, q| p.value.total_cmp(&q.value));
+    out
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Newton's method, failing as soon as the iteration leaves the finite
+/// numbers or runs out of steps
+fn newton<F: Fn(f64) -> f64, D: Fn(f64) -> f64>(f: &F, df: &D, guess: f64) -> Root {
+    let done = |x: f64, iterations: usize, converged: bool| Root {
+        value: x,
+        residual: f(x).abs(),
+        iterations,
+        converged,
+        method: Method::Newton,
+    };
+    let mut x = guess;
+    for i in 1..=MAX_ITER {
+        let (y, dy) = (f(x), df(x));
+        if y == 0.0 {
+            return done(x, i, true);
+        }
+        if !y.is_finite() || !dy.is_finite() || dy == 0.0 {
+            return done(x, i, false);
+        }
+        let step = y / dy;
+        x -= step;
+        if !x.is_finite() {
+            return done(x, i, false);
+        }
+        if step.abs() <= TOL * (1.0 + x.abs()) {
+            return done(x, i, settled(f, x));
+        }
+    }
+    done(x, MAX_ITER, false)
+}
+
+/// Whether an iteration that stopped moving at `x` found a root, rather than
+/// stalling on a decaying tail, an underflow or a pole
+///
+/// - `|f(x)|` must be small against `|f|` a short step either side
+fn settled<F: Fn(f64) -> f64>(f: &F, x: f64) -> bool {
+    let h = 1e-6 * (1.0 + x.abs());
+    let y = f(x).abs();
+    y.is_finite() && (y == 0.0 || y <= 1e-3 * f(x - h).abs().max(f(x + h).abs()))
+}
+
+/// Grow an interval around `x` until `f` changes sign across one of its ends
+fn bracket<F: Fn(f64) -> f64>(f: &F, x: f64) -> Option<(f64, f64)> {
+    let mut h = 0.01 * (1.0 + x.abs());
+    let y = f(x);
+    let (mut lo, mut hi) = ((x, y), (x, y));
+    for _ in 0..MAX_EXPAND {
+        for (side, end) in [(-1.0, &mut lo), (1.0, &mut hi)] {
+            let v = x + side * h;
+            let w = f(v);
+            if !w.is_finite() {
+                continue;
+            }
+            if end.1.is_finite() && end.1 * w <= 0.0 {
+                return Some((end.0.min(v), end.0.max(v)));
+            }
+            *end = (v, w);
+        }
+        h *= 2.0;
+    }
+    None
+}
+
+/// Brent's method on a bracket `[a, b]` where `f` changes sign
+fn brent<F: Fn(f64) -> f64>(f: &F, a: f64, b: f64) -> Root {
+    let (mut a, mut b) = (a, b);
+    let (mut fa, mut fb) = (f(a), f(b));
+    let done = |x: f64, iterations: usize, converged: bool| Root {
+        value: x,
+        residual: f(x).abs(),
+        iterations,
+        converged,
+        method: Method::Brent,
+    };
+    if fa * fb > 0.0 || !fa.is_finite() || !fb.is_finite() {
+        return done(b, 0, false);
+    }
+
+    let (mut c, mut fc) = (b, fb);
+    let (mut d, mut e) = (b - a, b - a);
+    for i in 1..=MAX_ITER {
+        if fb * fc > 0.0 {
+            (c, fc) = (a, fa);
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            (a, fa) = (b, fb);
+            (b, fb) = (c, fc);
+            (c, fc) = (a, fa);
+        }
+        let tol = 2.0 * f64::EPSILON * b.abs() + 0.5 * TOL;
+        let m = 0.5 * (c - b);
+        if m.abs() <= tol || fb == 0.0 {
+            return done(b, i, fb == 0.0 || settled(f, b));
+        }
+
+        if e.abs() >= tol && fa.abs() > fb.abs() {
+            // inverse quadratic interpolation, or secant when a == c
+            let s = fb / fa;
+            let (mut p, mut q) = match a == c {
+                true => (2.0 * m * s, 1.0 - s),
+                false => {
+                    let (q, r) = (fa / fc, fb / fc);
+                    (
+                        s * (2.0 * m * q * (q - r) - (b - a) * (r - 1.0)),
+                        (q - 1.0) * (r - 1.0) * (s - 1.0),
+                    )
+                }
+            };
+            if p > 0.0 {
+                q = -q;
+            } else {
+                p = -p;
+            }
+            if 2.0 * p < (3.0 * m * q - (tol * q).abs()).min((e * q).abs()) {
+                e = d;
+                d = p / q;
+            } else {
+                d = m;
+                e = m;
+            }
+        } else {
+            d = m;
+            e = m;
+        }
+
+        (a, fa) = (b, fb);
+        b += if d.abs() > tol { d } else { tol.copysign(m) };
+        fb = f(b);
+    }
+    done(b, MAX_ITER, false)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn nsolve(s: &str, guess: f64) -> Root {
+        s.parse::<Ex>().unwrap().nsolve("x", guess)
+    }
+
+    fn zeros(s: &str, a: f64, b: f64) -> Vec<f64> {
+        let rs = s.parse::<Ex>().unwrap().zeros("x", a, b);
+        assert!(rs.iter().all(|r| r.converged), "{:?}", rs);
+        rs.iter().map(|r| r.value).collect()
+    }
+
+    fn close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn ex3_rootfind_newton() {
+        let r = nsolve("x^2 = 2", 1.0);
+        assert!(r.converged);
+        assert_eq!(r.method, Method::Newton);
+        close(r.value, 2f64.sqrt());
+
+        let r = nsolve("cos(x) = x", 0.0);
+        close(r.value, 0.739_085_133_215_160_6);
+
+        // the same input always takes the same path
+        assert_eq!(nsolve("e^x = 3*x", 0.0), nsolve("e^x = 3*x", 0.0));
+
+        let mut e: Ex = "cos(x) = x".parse().unwrap();
+        e.gradient_descent();
+        assert_eq!(
+            e,
+            "x = 0.7390851332151607".parse::<Ex>().unwrap().simplified()
+        );
+        let mut e: Ex = "e^x = 0".parse().unwrap();
+        e.gradient_descent();
+        assert_eq!(e, "e^x = 0".parse().unwrap());
+    }
+
+    #[test]
+    fn ex3_rootfind_fallback() {
+        // Newton overshoots further every step on atan
+        let r = nsolve("atan(x)", 2.0);
+        assert!(r.converged, "{:?}", r);
+        assert_eq!(r.method, Method::Brent);
+        close(r.value, 0.0);
+
+        // no real root anywhere
+        let r = nsolve("x^2+1", 3.0);
+        assert!(!r.converged, "{:?}", r);
+
+        // tiny far out, or changing sign across a pole, but never zero
+        let r = nsolve("e^x = 0", 0.0);
+        assert!(!r.converged, "{:?}", r);
+        let r = nsolve("1/x", 1.0);
+        assert!(!r.converged, "{:?}", r);
+    }
+
+    #[test]
+    fn ex3_rootfind_zeros() {
+        let rs = zeros("sin(x)", -1.0, 10.0);
+        assert_eq!(rs.len(), 4, "{:?}", rs);
+        for (r, k) in rs.iter().zip(0..) {
+            close(*r, k as f64 * PI);
+        }
+
+        let rs = zeros("(x-1)^2", -2.0, 2.0);
+        assert_eq!(rs.len(), 1, "{:?}", rs);
+        assert!((rs[0] - 1.0).abs() < 1e-6);
+
+        assert!(zeros("1/x", -1.0, 1.0).is_empty());
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
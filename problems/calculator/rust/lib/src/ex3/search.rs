@@ -0,0 +1,287 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+    time::{Duration, Instant},
+};
+
+use super::{
+    ast::*,
+    rewrite::{Rewrite, RwRule},
+};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Weighted score of an expression, lower is simpler
+#[derive(Clone, Copy, Debug)]
+pub struct Cost {
+    /// Per node of the tree
+    pub nodes: f64,
+    /// Per level of nesting
+    pub depth: f64,
+    /// Per division or negative power
+    pub fractions: f64,
+}
+
+impl Default for Cost {
+    fn default() -> Self {
+        Cost {
+            nodes: 1.0,
+            depth: 0.0,
+            fractions: 0.0,
+        }
+    }
+}
+
+impl Cost {
+    pub fn of(&self, e: &Ex) -> f64 {
+        self.nodes * e.size() as f64
+            + self.depth * depth(e) as f64
+            + self.fractions * fractions(e) as f64
+    }
+}
+
+fn depth(e: &Ex) -> usize {
+    1 + e.children().into_iter().map(depth).max().unwrap_or(0)
+}
+
+fn fractions(e: &Ex) -> usize {
+    let own = match e {
+        Ex::Infix(_, Bin::Op(Op::Div), _) => 1,
+        Ex::Infix(_, Bin::Op(Op::Pow), k) if matches!(k.as_ref(), Ex::Neg(_)) => 1,
+        _ => 0,
+    };
+    own + e.children().into_iter().map(fractions).sum::<usize>()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Limits on a search, whichever runs out first
+#[derive(Clone, Copy, Debug)]
+pub struct Budget {
+    /// Most expressions expanded
+    pub nodes: usize,
+    /// Longest time spent
+    pub time: Duration,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget {
+            nodes: 500,
+            time: Duration::from_millis(200),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// One move of a search path
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub rule: &'static str,
+    pub ex: Ex,
+}
+
+/// Outcome of a search
+#[derive(Clone, Debug)]
+pub struct Search {
+    /// Cheapest form found
+    pub best: Ex,
+    pub cost: f64,
+    /// Moves from the input to `best`
+    pub path: Vec<Step>,
+    /// Expressions expanded
+    pub explored: usize,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Best-first search for the cheapest equivalent form
+    ///
+    /// - Neighbours are single local rewrites anywhere in the tree, plus
+    ///   `simplify`, `factor` and `expand` of the whole expression
+    /// - The cheapest unexpanded form is expanded next, each form once
+    pub fn search(&self, cost: &Cost, budget: &Budget) -> Search {
+        let start = Instant::now();
+        let rewrite = moves();
+        let key = |e: &Ex| format!("{:?}", e);
+
+        let mut seen = HashSet::from([key(self)]);
+        let mut front = BinaryHeap::from([Node {
+            cost: cost.of(self),
+            ex: self.clone(),
+            path: vec![],
+        }]);
+        let mut best = front.peek().unwrap().clone();
+        let mut explored = 0;
+
+        while let Some(node) = front.pop() {
+            if explored >= budget.nodes || start.elapsed() > budget.time {
+                break;
+            }
+            explored += 1;
+            if node.cost < best.cost {
+                best = node.clone();
+            }
+
+            let mut next = rewrite.successors(&node.ex);
+            next.extend([
+                ("simplify", node.ex.simplified()),
+                ("factor", node.ex.factor()),
+                ("expand", node.ex.normal()),
+            ]);
+            for (rule, ex) in next {
+                if ex == Ex::Invalid || !seen.insert(key(&ex)) {
+                    continue;
+                }
+                let mut path = node.path.clone();
+                path.push(Step {
+                    rule,
+                    ex: ex.clone(),
+                });
+                front.push(Node {
+                    cost: cost.of(&ex),
+                    ex,
+                    path,
+                });
+            }
+        }
+
+        Search {
+            best: best.ex,
+            cost: best.cost,
+            path: best.path,
+            explored,
+        }
+    }
+}
+
+/// Local rewrites in both directions, on top of the standard identities
+fn moves() -> Rewrite {
+    let mut rules = vec![
+        RwRule::new("add_comm", "a+b", "b+a"),
+        RwRule::new("mul_comm", "a*b", "b*a"),
+        RwRule::new("add_assoc", "(a+b)+c", "a+(b+c)"),
+        RwRule::new("add_assoc", "a+(b+c)", "(a+b)+c"),
+        RwRule::new("mul_assoc", "(a*b)*c", "a*(b*c)"),
+        RwRule::new("mul_assoc", "a*(b*c)", "(a*b)*c"),
+        RwRule::new("distribute", "a*(b+c)", "a*b+a*c"),
+        RwRule::new("distribute", "a*(b-c)", "a*b-a*c"),
+        RwRule::new("factor_out", "a*b+a*c", "a*(b+c)"),
+        RwRule::new("factor_out", "a*b-a*c", "a*(b-c)"),
+        RwRule::new("common_den", "a/c+b/c", "(a+b)/c"),
+        RwRule::new("common_den", "a/c-b/c", "(a-b)/c"),
+        RwRule::new("split_frac", "(a+b)/c", "a/c+b/c"),
+        RwRule::new("square", "a*a", "a^2"),
+        RwRule::new("add_exp", "a^b*a^c", "a^(b+c)"),
+        RwRule::new("ln_product", "ln(a)+ln(b)", "ln(a*b)"),
+        RwRule::new("ln_product", "ln(a*b)", "ln(a)+ln(b)"),
+        RwRule::new("ln_power", "ln(a^b)", "b*ln(a)"),
+        RwRule::new("pythagoras", "sin(a)^2+cos(a)^2", "1"),
+        RwRule::new("pythagoras", "cos(a)^2+sin(a)^2", "1"),
+        RwRule::new("tan", "sin(a)/cos(a)", "tan(a)"),
+    ];
+    rules.extend(Rewrite::standard().rules().iter().cloned());
+    Rewrite::new(rules)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+struct Node {
+    cost: f64,
+    ex: Ex,
+    path: Vec<Step>,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    /// Reversed so the heap pops the cheapest, then the shortest path
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then(other.path.len().cmp(&self.path.len()))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::parse;
+
+    fn search(s: &str) -> Search {
+        parse(s).search(&Cost::default(), &Budget::default())
+    }
+
+    #[test]
+    fn ex3_search_best() {
+        assert_eq!(search("sin(x)^2+cos(x)^2").best, parse("1"));
+        assert_eq!(search("x*y+x*z").best, parse("x*(y+z)"));
+        assert_eq!(search("(x^2-1)/(x-1)").best, parse("x+1"));
+        assert_eq!(search("ln(a)+ln(b)").best, parse("ln(a*b)"));
+
+        let s = search("x");
+        assert_eq!((s.best, s.path.len()), (parse("x"), 0));
+    }
+
+    #[test]
+    fn ex3_search_path() {
+        let s = search("(ln(a)+ln(b))*1");
+        assert_eq!(s.best, parse("ln(a*b)"));
+        assert_eq!(s.path.last().map(|p| &p.ex), Some(&s.best));
+        assert_eq!(s.path.len(), 2);
+        assert_eq!(s.path[1].rule, "ln_product");
+        assert_eq!(s.cost, Cost::default().of(&s.best));
+    }
+
+    #[test]
+    fn ex3_search_cost_and_budget() {
+        let cost = Cost {
+            nodes: 0.0,
+            depth: 1.0,
+            fractions: 10.0,
+        };
+        assert_eq!(cost.of(&parse("a/c+b/c")), 23.0);
+        assert_eq!(cost.of(&parse("x^(-1)")), 13.0);
+        let s = parse("a/c+b/c").search(&cost, &Budget::default());
+        assert_eq!((s.best, s.cost), (parse("(a+b)/c"), 13.0));
+
+        let tight = Budget {
+            nodes: 1,
+            ..Budget::default()
+        };
+        let s = parse("x*y+x*z").search(&Cost::default(), &tight);
+        assert_eq!((s.best, s.explored), (parse("x*y+x*z"), 1));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,498 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, helper::*, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Most extra terms computed to make up for precision lost in quotients
+const MAX_GUARD: i32 = 8;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Truncated power series `Σ c_k (x-a)^(val+k) + O((x-a)^order)`
+///
+/// - Negative exponents are allowed, so quotients like `sin(x)/x^2` work
+/// - Coefficients are simplified, exact whenever the input was
+#[derive(Clone, Debug, PartialEq)]
+pub struct Series {
+    pub x: String,
+    pub a: Ex,
+    /// Exponent of the first coefficient
+    pub val: i32,
+    pub coeffs: Vec<Ex>,
+    /// Exponent of the order term, every coefficient below it is known
+    pub order: i32,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Power series in `x` around `a`, up to but excluding `(x-a)^order`
+    ///
+    /// - Built from the series of the parts, so removable singularities such
+    ///   as `sin(x)/x` at `0` expand fine
+    /// - Returns `None` for anything that has no such series
+    pub fn series(&self, x: &str, a: &Ex, order: i32) -> Option<Series> {
+        let a = a.simplified();
+        (0..=MAX_GUARD)
+            .filter_map(|g| expand(self, x, &a, order + g))
+            .find(|s| s.order >= order)
+            .map(|s| s.truncate(order).normalized())
+    }
+
+    /// Taylor polynomial of degree `n` in `x` around `a`
+    pub fn taylor(&self, x: &str, n: u32, a: &Ex) -> Option<Ex> {
+        let s = self.series(x, a, n as i32 + 1)?;
+        (s.val >= 0).then(|| s.to_ex())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+fn expand(e: &Ex, x: &str, a: &Ex, order: i32) -> Option<Series> {
+    let go = |e: &Ex| expand(e, x, a, order);
+    if !e.has(x) {
+        return Some(Series::constant(e.simplified(), x, a, order));
+    }
+    match e {
+        Ex::Val(_) => Some(Series::var(x, a, order)),
+        Ex::Neg(b) => Some(go(b)?.neg()),
+        Ex::Infix(l, Bin::Op(op), r) => match op {
+            Op::Add => Some(go(l)?.add(&go(r)?)),
+            Op::Sub => Some(go(l)?.sub(&go(r)?)),
+            Op::Mul => Some(go(l)?.mul(&go(r)?)),
+            Op::Div => go(l)?.div(&go(r)?),
+            Op::Pow if !r.has(x) => go(l)?.pow(&r.simplified()),
+            Op::Pow if **l == Ex::from(Tok::E) => go(r)?.exp(),
+            Op::Pow => go(r)?.mul(&go(l)?.ln()?).exp(),
+            Op::Mod => None,
+        },
+        Ex::Fn(f) => match f {
+            Fnc::Ln(b) => go(b)?.ln(),
+            Fnc::Sin(b) => go(b)?.sin(),
+            Fnc::Cos(b) => go(b)?.cos(),
+            Fnc::Tan(b) => go(b)?.tan(),
+            Fnc::Atan(b) => go(b)?.atan(),
+            _ => match e.simplified() {
+                s if s == *e => None,
+                s => go(&s),
+            },
+        },
+        _ => None,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Series {
+    /// `c + O((x-a)^order)`
+    pub fn constant(c: Ex, x: &str, a: &Ex, order: i32) -> Self {
+        Series {
+            x: x.to_owned(),
+            a: a.clone(),
+            val: 0,
+            coeffs: vec![c],
+            order,
+        }
+        .truncate(order)
+    }
+
+    /// `x`, which is `a + (x-a)`
+    pub fn var(x: &str, a: &Ex, order: i32) -> Self {
+        Series {
+            coeffs: vec![a.clone(), Ex::from(1)],
+            ..Series::constant(Ex::from(0), x, a, order)
+        }
+        .truncate(order)
+    }
+
+    /// Same variable and point with new terms
+    fn with(&self, val: i32, coeffs: Vec<Ex>, order: i32) -> Self {
+        Series {
+            x: self.x.clone(),
+            a: self.a.clone(),
+            val,
+            coeffs,
+            order,
+        }
+        .truncate(order)
+    }
+
+    /// Coefficient of `(x-a)^k`
+    pub fn coeff(&self, k: i32) -> Ex {
+        match k - self.val {
+            i if i >= 0 && (i as usize) < self.coeffs.len() => self.coeffs[i as usize].clone(),
+            _ => Ex::from(0),
+        }
+    }
+
+    /// Drop the terms from `(x-a)^order` on, padding missing ones with zeros
+    pub fn truncate(mut self, order: i32) -> Self {
+        self.order = self.order.min(order);
+        let len = (self.order - self.val).max(0) as usize;
+        self.coeffs.resize(len, Ex::from(0));
+        self
+    }
+
+    /// Multiply by `(x-a)^k`
+    fn shift(&self, k: i32) -> Self {
+        self.with(self.val + k, self.coeffs.clone(), self.order + k)
+    }
+
+    /// Move leading zero coefficients into the exponent
+    fn normalized(&self) -> Self {
+        let zeros = self.coeffs.iter().take_while(|c| c.is_zero()).count();
+        self.with(
+            self.val + zeros as i32,
+            self.coeffs[zeros..].to_vec(),
+            self.order,
+        )
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    pub fn add(&self, rhs: &Series) -> Series {
+        let (val, order) = (self.val.min(rhs.val), self.order.min(rhs.order));
+        let coeffs = (val..order)
+            .map(|k| sum(&[self.coeff(k), rhs.coeff(k)]))
+            .collect();
+        self.with(val, coeffs, order)
+    }
+
+    pub fn neg(&self) -> Series {
+        self.scale(&Ex::from(-1))
+    }
+
+    pub fn sub(&self, rhs: &Series) -> Series {
+        self.add(&rhs.neg())
+    }
+
+    pub fn scale(&self, c: &Ex) -> Series {
+        let coeffs = self.coeffs.iter().map(|a| product(&[c.clone(), a.clone()]));
+        self.with(self.val, coeffs.collect(), self.order)
+    }
+
+    pub fn mul(&self, rhs: &Series) -> Series {
+        let val = self.val + rhs.val;
+        let order = (self.order + rhs.val).min(rhs.order + self.val);
+        let coeffs = (val..order)
+            .map(|k| {
+                let terms: Vec<Ex> = (self.val..=k - rhs.val)
+                    .map(|i| product(&[self.coeff(i), rhs.coeff(k - i)]))
+                    .collect();
+                sum(&terms)
+            })
+            .collect();
+        self.with(val, coeffs, order)
+    }
+
+    /// `1/self`, if the series is not zero to the known precision
+    pub fn recip(&self) -> Option<Series> {
+        let s = self.normalized();
+        let c = s.coeffs.first()?.clone();
+        let u = s.shift(-s.val).scale(&power(c.clone(), Ex::from(-1)));
+        let u = u.sub(&Series::constant(Ex::from(1), &s.x, &s.a, u.order));
+        let geometric: Vec<Ex> = (0..u.order)
+            .map(|k| Ex::from((-1i32).pow(k as u32 % 2)))
+            .collect();
+        Some(
+            u.compose(&geometric)
+                .scale(&power(c, Ex::from(-1)))
+                .shift(-s.val),
+        )
+    }
+
+    pub fn div(&self, rhs: &Series) -> Option<Series> {
+        Some(self.mul(&rhs.recip()?))
+    }
+
+    pub fn powi(&self, n: i32) -> Option<Series> {
+        if n < 0 {
+            return self.recip()?.powi(-n);
+        }
+        let one = Series::constant(Ex::from(1), &self.x, &self.a, self.order - self.val);
+        Some((0..n).fold(one, |acc, _| acc.mul(self)))
+    }
+
+    /// `self^q` for an exponent free of `x`, by the binomial series
+    pub fn pow(&self, q: &Ex) -> Option<Series> {
+        if let Some(n) = q.as_rat().filter(|q| q.is_int()) {
+            return self.powi(i32::try_from(n.numer()).ok()?);
+        }
+        let s = self.normalized();
+        let c = s.coeffs.first()?.clone();
+        let shift = match s.val {
+            0 => 0,
+            v => {
+                let k = Rat::int(v as i128).checked_mul(q.as_rat()?)?;
+                i32::try_from(Some(k).filter(|k| k.is_int())?.numer()).ok()?
+            }
+        };
+        let u = s.shift(-s.val).scale(&power(c.clone(), Ex::from(-1)));
+        let u = u.sub(&Series::constant(Ex::from(1), &s.x, &s.a, u.order));
+        let mut binomial = vec![Ex::from(1)];
+        for k in 1..u.order.max(1) {
+            let prev = binomial[k as usize - 1].clone();
+            let next = sum(&[q.clone(), Ex::from(1 - k)]);
+            binomial.push(product(&[prev, next, Rat::new(1, k as i128).to_ex()]));
+        }
+        Some(
+            u.compose(&binomial)
+                .scale(&power(c, q.clone()))
+                .shift(shift),
+        )
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    /// Split into the constant term and the rest, for a series without
+    /// negative exponents
+    fn split(&self) -> Option<(Ex, Series)> {
+        if self.normalized().val < 0 {
+            return None;
+        }
+        let c = self.coeff(0);
+        let u = self.sub(&Series::constant(c.clone(), &self.x, &self.a, self.order));
+        Some((c, u))
+    }
+
+    pub fn exp(&self) -> Option<Series> {
+        let (c, u) = self.split()?;
+        let mut f = vec![Ex::from(1)];
+        for k in 1..u.order.max(1) {
+            f.push(product(&[
+                f[k as usize - 1].clone(),
+                Rat::new(1, k as i128).to_ex(),
+            ]));
+        }
+        Some(u.compose(&f).scale(&power(Tok::E.into(), c)))
+    }
+
+    pub fn ln(&self) -> Option<Series> {
+        let (c, _) = self.split()?;
+        if c.is_zero() {
+            return None;
+        }
+        let u = self.scale(&power(c.clone(), Ex::from(-1)));
+        let u = u.sub(&Series::constant(Ex::from(1), &self.x, &self.a, u.order));
+        let f: Vec<Ex> = (0..u.order.max(1))
+            .map(|k| match k {
+                0 => Ex::from(0),
+                k => Rat::new(if k % 2 == 1 { 1 } else { -1 }, k as i128).to_ex(),
+            })
+            .collect();
+        let log = Series::constant(ln(c).simplified(), &self.x, &self.a, u.order);
+        Some(u.compose(&f).add(&log))
+    }
+
+    pub fn sin(&self) -> Option<Series> {
+        let (c, u) = self.split()?;
+        let (s, co) = (sin(c.clone()).simplified(), cos(c).simplified());
+        Some(
+            u.compose(&trig(u.order, 1)?)
+                .scale(&co)
+                .add(&u.compose(&trig(u.order, 0)?).scale(&s)),
+        )
+    }
+
+    pub fn cos(&self) -> Option<Series> {
+        let (c, u) = self.split()?;
+        let (s, co) = (sin(c.clone()).simplified(), cos(c).simplified());
+        Some(
+            u.compose(&trig(u.order, 0)?)
+                .scale(&co)
+                .sub(&u.compose(&trig(u.order, 1)?).scale(&s)),
+        )
+    }
+
+    pub fn tan(&self) -> Option<Series> {
+        self.sin()?.div(&self.cos()?)
+    }
+
+    /// By integrating `s'/(1+s^2)`
+    pub fn atan(&self) -> Option<Series> {
+        let (c, _) = self.split()?;
+        let one = Series::constant(Ex::from(1), &self.x, &self.a, self.order);
+        let d = self.derivative().div(&one.add(&self.mul(self)))?;
+        d.integral(atan(c).simplified())
+    }
+
+    pub fn derivative(&self) -> Series {
+        let coeffs = (self.val..self.order)
+            .map(|k| product(&[Ex::from(k), self.coeff(k)]))
+            .collect();
+        self.with(self.val, coeffs, self.order)
+            .shift(-1)
+            .normalized()
+    }
+
+    /// Antiderivative with constant term `c`, if there is no `(x-a)^-1` term
+    fn integral(&self, c: Ex) -> Option<Series> {
+        if !self.coeff(-1).is_zero() {
+            return None;
+        }
+        let coeffs = (self.val..self.order)
+            .map(|k| match k {
+                -1 => Ex::from(0),
+                k => product(&[self.coeff(k), Rat::new(1, k as i128 + 1).to_ex()]),
+            })
+            .collect();
+        let s = self.with(self.val, coeffs, self.order).shift(1);
+        Some(s.add(&Series::constant(c, &self.x, &self.a, s.order)))
+    }
+
+    /// `Σ f_k self^k`, for a series with only positive exponents
+    pub fn compose(&self, f: &[Ex]) -> Series {
+        let mut out = Series::constant(Ex::from(0), &self.x, &self.a, self.order);
+        let mut pow = Series::constant(Ex::from(1), &self.x, &self.a, self.order);
+        for c in f {
+            out = out.add(&pow.scale(c));
+            pow = pow.mul(self);
+        }
+        out
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    /// The known terms as an expression in `x`
+    pub fn to_ex(&self) -> Ex {
+        let terms: Vec<Ex> = (self.val..self.order)
+            .map(|k| product(&[self.coeff(k), power(self.base(), Ex::from(k))]))
+            .collect();
+        sum(&terms)
+    }
+
+    /// Known terms in ascending order followed by the order term
+    pub fn to_text(&self) -> String {
+        let mut out: Option<Ex> = None;
+        for k in self.val..self.order {
+            let t = product(&[self.coeff(k), power(self.base(), Ex::from(k))]);
+            out = match out {
+                _ if t.is_zero() => out,
+                None => Some(t),
+                Some(acc) if Product::of(&t).coef.is_neg() => {
+                    Some(acc - product(&[Ex::from(-1), t]))
+                }
+                Some(acc) => Some(acc + t),
+            };
+        }
+        let o = power(self.base(), Ex::from(self.order)).to_text();
+        match out {
+            Some(e) => format!("{} + O({})", e.to_text(), o),
+            None => format!("O({})", o),
+        }
+    }
+
+    fn base(&self) -> Ex {
+        sum(&[
+            Ex::from(self.x.as_str()),
+            product(&[Ex::from(-1), self.a.clone()]),
+        ])
+    }
+}
+
+/// Maclaurin coefficients of `sin` if `odd`, `cos` otherwise, below `order`
+///
+/// - `None` once the factorial overflows
+fn trig(order: i32, odd: i32) -> Option<Vec<Ex>> {
+    let mut f = vec![];
+    let mut fact = Rat::ONE;
+    for k in 0..order.max(1) {
+        if k > 0 {
+            fact = fact.checked_mul(Rat::int(k as i128))?;
+        }
+        f.push(match k % 2 == odd {
+            true => match (k / 2) % 2 {
+                0 => fact.recip().to_ex(),
+                _ => (-fact.recip()).to_ex(),
+            },
+            false => Ex::from(0),
+        });
+    }
+    Some(f)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex3::testing::{parse, simp};
+
+    fn taylor(s: &str) -> Ex {
+        simp(s)
+    }
+
+    #[test]
+    fn ex3_series_taylor() {
+        assert_eq!(
+            taylor("taylor(sin(x), x, 5)"),
+            taylor("x - x^3/6 + x^5/120")
+        );
+        assert_eq!(taylor("taylor(e^x, x, 3)"), taylor("1 + x + x^2/2 + x^3/6"));
+        assert_eq!(taylor("taylor(1/(1-x), x, 3)"), taylor("1 + x + x^2 + x^3"));
+        assert_eq!(
+            taylor("taylor(tan(x), x, 5)"),
+            taylor("x + x^3/3 + 2*x^5/15")
+        );
+        assert_eq!(taylor("taylor(atan(x), x, 5)"), taylor("x - x^3/3 + x^5/5"));
+        assert_eq!(
+            taylor("taylor((1+x)^(1/2), x, 2)"),
+            taylor("1 + x/2 - x^2/8")
+        );
+        assert_eq!(
+            taylor("taylor(sin(x)/x, x, 4)"),
+            taylor("1 - x^2/6 + x^4/120")
+        );
+        assert_eq!(
+            taylor("taylor(ln(x), x, 3, 1)"),
+            taylor("(x-1) - (x-1)^2/2 + (x-1)^3/3")
+        );
+        assert!(matches!(taylor("taylor(ln(x), x, 3)"), Ex::Fn(_)));
+        // the factorials overflow, so the call stays as written
+        assert!(matches!(taylor("taylor(sin(x), x, 100)"), Ex::Fn(_)));
+        assert!(matches!(taylor("taylor(cos(x), x, 100)"), Ex::Fn(_)));
+    }
+
+    #[test]
+    fn ex3_series_arithmetic() {
+        let zero = Ex::from(0);
+        let s = parse("sin(x)").series("x", &zero, 6).unwrap();
+        let e = parse("e^x").series("x", &zero, 6).unwrap();
+        assert_eq!(
+            s.mul(&e),
+            parse("sin(x)*e^x").series("x", &zero, 6).unwrap()
+        );
+        assert_eq!(s.mul(&e).order, 6);
+
+        let c = parse("x^2").series("x", &zero, 6).unwrap();
+        let r = parse("cos(x)").series("x", &zero, 6).unwrap();
+        // x^2 is only known up to x^6, so its reciprocal only up to x^2
+        assert_eq!(c.recip().unwrap().mul(&r).val, -2);
+        assert_eq!(c.recip().unwrap().mul(&r).order, 2);
+        let q = parse("cos(x)/x^2").series("x", &zero, 4).unwrap();
+        assert_eq!((q.val, q.order), (-2, 4));
+        assert_eq!(q.coeff(2), Rat::new(1, 24).to_ex());
+
+        let u = parse("sin(x)").series("x", &zero, 4).unwrap();
+        assert_eq!(
+            u.compose(&trig(4, 0).unwrap()),
+            parse("cos(sin(x))").series("x", &zero, 4).unwrap()
+        );
+        assert_eq!(u.to_text(), "x - x^3/6 + O(x^4)");
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,848 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::cmp::Ordering;
+
+use super::{
+    ast::*, expand::Expand, limit::Side, mode, mpoly::MPoly, piecewise, polydiv::cancel,
+    rational::*, relation, solve::Domain, special, trace, trig,
+};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Symbolic reduction step
+    ///
+    /// - Fold constants exactly, floats only if a float was in the input
+    /// - Flatten products, merging equal bases by adding exponents
+    /// - Evaluate calculus operators where possible
+    pub fn simplify(&mut self) {
+        *self = self.simplified();
+    }
+
+    /// Simplified copy, see [`Ex::simplify`]
+    pub fn simplified(&self) -> Ex {
+        trace::node(|| {
+            let mut i = 0;
+            let mut child = |a: &Ex| {
+                i += 1;
+                trace::child(i - 1, || a.simplified())
+            };
+            let (rule, before, after) = match self {
+                Ex::Val(Val::Num(Num::Int(n))) if *n < 0 && *n > i128::MIN => return -Ex::from(-n),
+                Ex::Val(Val::Num(Num::Flt(v))) if *v < 0.0 => return -Ex::from(-v),
+                Ex::Val(Val::Num(Num::Big(v))) if v.is_neg() => return -Ex::from(v.neg()),
+                Ex::Val(_) | Ex::Invalid => return self.clone(),
+                Ex::Neg(a) => {
+                    let a = child(a);
+                    ("negate", -a.clone(), product(&[Ex::from(-1), a]))
+                }
+                Ex::Infix(a, Bin::Rl(Rl::Where), b) => match b.as_ref() {
+                    Ex::Infix(x, Bin::Rl(Rl::Eqq), v) if x.var().is_some() => (
+                        "substitute",
+                        self.clone(),
+                        a.subs(x.var().unwrap(), &v.simplified()).simplified(),
+                    ),
+                    _ => return child(a).c(child(b), Rl::Where.into()),
+                },
+                Ex::Infix(a, j, b) => {
+                    let (a, b) = (child(a), child(b));
+                    let before = a.clone().c(b.clone(), j.clone());
+                    let (rule, after) = match j {
+                        Bin::Op(Op::Add) => ("add", expand_sum(sum(&[a, b]))),
+                        Bin::Op(Op::Sub) => (
+                            "subtract",
+                            expand_sum(sum(&[a, product(&[Ex::from(-1), b])])),
+                        ),
+                        Bin::Op(Op::Mul) => ("multiply", product(&[a, b])),
+                        Bin::Op(Op::Div) => {
+                            ("divide", cancel(product(&[a, power(b, Ex::from(-1))])))
+                        }
+                        Bin::Op(Op::Pow) => ("power", power(a, b)),
+                        Bin::Op(Op::Mod) => ("modulo", modulo(a, b)),
+                        Bin::Rl(r) => match relation::evaluate(&a, r, &b) {
+                            Some(v) => ("compare", v),
+                            None => return before,
+                        },
+                    };
+                    (rule, before, after)
+                }
+                Ex::Fn(f) => {
+                    let f = f.map(&mut child);
+                    let before = f.clone().into();
+                    // only calculus and solving show their inner steps
+                    let after = match f {
+                        Fnc::D(..) | Fnc::Int(..) | Fnc::DefInt(..) => function(f.clone()),
+                        Fnc::Solve(..) | Fnc::CSolve(..) => function(f.clone()),
+                        _ => trace::quiet(|| function(f.clone())),
+                    };
+                    (f.name(), before, after)
+                }
+                Ex::Mat(xs) => return Ex::Mat(xs.iter().map(child).collect()),
+            };
+            trace::record(rule, &before, &after);
+            after
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Numeric coefficient, exact unless a float was involved
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Coef {
+    Q(Rat),
+    F(f64),
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Coef {
+    pub fn of(e: &Ex) -> Option<Coef> {
+        match e {
+            Ex::Val(Val::Num(Num::Flt(v))) => Some(Coef::F(*v)),
+            Ex::Neg(a) => Coef::of(a).map(|a| a.mul(Coef::Q(-Rat::ONE))),
+            _ => e.as_rat().map(Coef::Q),
+        }
+    }
+
+    pub fn flt(self) -> f64 {
+        match self {
+            Coef::Q(q) => q.flt(),
+            Coef::F(v) => v,
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.flt() == 0.0
+    }
+
+    pub fn is_one(self) -> bool {
+        self == Coef::Q(Rat::ONE)
+    }
+
+    pub fn is_neg(self) -> bool {
+        self.flt() < 0.0
+    }
+
+    pub fn abs(self) -> Coef {
+        match self {
+            Coef::Q(q) => Coef::Q(q.abs()),
+            Coef::F(v) => Coef::F(v.abs()),
+        }
+    }
+
+    pub fn add(self, rhs: Coef) -> Coef {
+        match (self, rhs) {
+            (Coef::Q(a), Coef::Q(b)) => Coef::Q(a + b),
+            (a, b) => Coef::F(a.flt() + b.flt()),
+        }
+    }
+
+    /// Sum, or `None` if an exact sum overflows
+    pub fn checked_add(self, rhs: Coef) -> Option<Coef> {
+        match (self, rhs) {
+            (Coef::Q(a), Coef::Q(b)) => a.checked_add(b).map(Coef::Q),
+            (a, b) => Some(a.add(b)),
+        }
+    }
+
+    pub fn mul(self, rhs: Coef) -> Coef {
+        match (self, rhs) {
+            (Coef::Q(a), Coef::Q(b)) => Coef::Q(a * b),
+            (a, b) => Coef::F(a.flt() * b.flt()),
+        }
+    }
+
+    /// Product, or `None` if an exact product overflows
+    pub fn checked_mul(self, rhs: Coef) -> Option<Coef> {
+        match (self, rhs) {
+            (Coef::Q(a), Coef::Q(b)) => a.checked_mul(b).map(Coef::Q),
+            (a, b) => Some(a.mul(b)),
+        }
+    }
+
+    /// Reciprocal, for a non-zero coefficient
+    pub fn recip(self) -> Coef {
+        match self {
+            Coef::Q(q) => Coef::Q(q.recip()),
+            Coef::F(v) => Coef::F(1.0 / v),
+        }
+    }
+
+    pub fn to_ex(self) -> Ex {
+        match self {
+            Coef::Q(q) => q.to_ex(),
+            Coef::F(v) if v < 0.0 => -Ex::from(-v),
+            Coef::F(v) => Ex::from(v),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Flattened product `coef * Π base^exp`
+#[derive(Clone, Debug)]
+pub struct Product {
+    pub coef: Coef,
+    pub factors: Vec<(Ex, Ex)>,
+    pub undefined: bool,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Product {
+    fn one() -> Self {
+        Product {
+            coef: Coef::Q(Rat::ONE),
+            factors: vec![],
+            undefined: false,
+        }
+    }
+
+    /// Split a simplified expression into its coefficient and factors
+    pub fn of(e: &Ex) -> Self {
+        let mut p = Product::one();
+        p.push(e, &Ex::from(1));
+        p
+    }
+
+    /// Multiply in `e^exp`, where both sides are already simplified
+    fn push(&mut self, e: &Ex, exp: &Ex) {
+        match e {
+            Ex::Infix(a, Bin::Op(Op::Mul), b) => {
+                self.push(a, exp);
+                self.push(b, exp);
+            }
+            Ex::Infix(a, Bin::Op(Op::Div), b) => {
+                self.push(a, exp);
+                self.push(b, &mul_exp(exp, &Ex::from(-1)));
+            }
+            Ex::Neg(a) => {
+                self.push(&Ex::from(-1), exp);
+                // a symbolic power of one would not fold away
+                if !a.is_one() {
+                    self.push(a, exp);
+                }
+            }
+            Ex::Infix(a, Bin::Op(Op::Pow), b) if exp.as_rat().is_some_and(|k| k.is_int()) => {
+                self.push(a, &mul_exp(b, exp));
+            }
+            _ => match (Coef::of(e), exp.as_rat()) {
+                (Some(Coef::Q(q)), Some(k)) if k.is_int() => {
+                    if q.is_zero() && k.is_neg() {
+                        self.undefined = true;
+                    } else {
+                        // too large to hold exactly, so the power is kept
+                        match q
+                            .powi(k.numer())
+                            .and_then(|v| self.coef.checked_mul(Coef::Q(v)))
+                        {
+                            Some(c) => self.coef = c,
+                            None => self.factors.push((e.clone(), exp.clone())),
+                        }
+                    }
+                }
+                (Some(c), _) if Coef::of(exp).is_some() && !matches!(c, Coef::Q(_)) => {
+                    let k = Coef::of(exp).unwrap().flt();
+                    self.coef = self.coef.mul(Coef::F(c.flt().powf(k)));
+                }
+                (Some(Coef::Q(q)), None) if matches!(Coef::of(exp), Some(Coef::F(_))) => {
+                    let k = Coef::of(exp).unwrap().flt();
+                    self.coef = self.coef.mul(Coef::F(q.flt().powf(k)));
+                }
+                _ => self.factors.push((e.clone(), exp.clone())),
+            },
+        }
+    }
+
+    /// Merge equal bases and fold numeric powers
+    fn collect(mut self) -> Self {
+        let mut merged: Vec<(Ex, Ex)> = vec![];
+        for (base, exp) in self.factors.drain(..) {
+            match merged.iter_mut().find(|(b, _)| *b == base) {
+                Some((_, e)) => *e = sum(&[e.clone(), exp]),
+                None => merged.push((base, exp)),
+            }
+        }
+        trig::quotients(&mut merged);
+
+        let mut out = Product {
+            factors: vec![],
+            ..self
+        };
+        for (base, exp) in merged {
+            if exp.is_zero() {
+                continue;
+            }
+            match (base.as_rat(), exp.as_rat()) {
+                (Some(q), Some(k)) if !k.is_int() && !q.is_neg() => {
+                    let (outside, inside) = rational_root(q, k);
+                    let Some(c) = out.coef.checked_mul(Coef::Q(outside)) else {
+                        out.factors.push((base, exp));
+                        continue;
+                    };
+                    out.coef = c;
+                    if let Some((inside, k)) = inside {
+                        out.factors.push((inside.to_ex(), k.to_ex()));
+                    }
+                }
+                (Some(_), Some(k)) if k.is_int() => out.push(&base, &exp),
+                _ => out.factors.push((base, exp)),
+            }
+        }
+        out.factors.sort_by(|a, b| cmp_ex(&a.0, &b.0));
+        out
+    }
+
+    /// Rebuild the canonical expression `coef * num / den`
+    pub fn to_ex(&self) -> Ex {
+        if self.undefined {
+            return Ex::Invalid;
+        }
+        if self.coef.is_zero() {
+            return Ex::from(0);
+        }
+
+        let mut num = vec![];
+        let mut den = vec![];
+        for (base, exp) in &self.factors {
+            match exp.as_rat() {
+                Some(k) if k.is_neg() => den.push(raise(base, (-k).to_ex())),
+                _ => num.push(raise(base, exp.clone())),
+            }
+        }
+
+        match self.coef.abs() {
+            Coef::Q(q) => {
+                if q.numer() != 1 || num.is_empty() {
+                    num.insert(0, Rat::int(q.numer()).to_ex());
+                }
+                if q.denom() != 1 {
+                    den.insert(0, Rat::int(q.denom()).to_ex());
+                }
+            }
+            Coef::F(v) => {
+                if v != 1.0 || num.is_empty() {
+                    num.insert(0, Ex::from(v));
+                }
+            }
+        }
+
+        let num = chain(num);
+        let ex = match den.is_empty() {
+            true => num,
+            false => num / chain(den),
+        };
+        if self.coef.is_neg() {
+            -ex
+        } else {
+            ex
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Flattened sum `constant + Σ coef * term`, with equal terms merged
+#[derive(Clone, Debug)]
+pub struct Sum {
+    pub constant: Coef,
+    pub terms: Vec<(Coef, Ex)>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Sum {
+    /// Split a simplified expression into its constant and terms
+    pub fn of(e: &Ex) -> Self {
+        let mut s = Sum {
+            constant: Coef::Q(Rat::ZERO),
+            terms: vec![],
+        };
+        s.push(e, Coef::Q(Rat::ONE));
+        s
+    }
+
+    fn push(&mut self, e: &Ex, sign: Coef) {
+        match e {
+            Ex::Infix(a, Bin::Op(Op::Add), b) => {
+                self.push(a, sign);
+                self.push(b, sign);
+            }
+            Ex::Infix(a, Bin::Op(Op::Sub), b) => {
+                self.push(a, sign);
+                self.push(b, sign.mul(Coef::Q(-Rat::ONE)));
+            }
+            _ => match Coef::of(e) {
+                // too large to hold exactly, so kept as a separate term
+                Some(c) => match self.constant.checked_add(c.mul(sign)) {
+                    Some(k) => self.constant = k,
+                    None => self.terms.push((c.mul(sign), Ex::from(1))),
+                },
+                None => {
+                    let p = Product::of(e);
+                    let rest = Product {
+                        coef: Coef::Q(Rat::ONE),
+                        ..p.clone()
+                    };
+                    let (c, t) = (p.coef.mul(sign), rest.to_ex());
+                    match self.terms.iter_mut().find(|(_, u)| *u == t) {
+                        Some((k, _)) if k.checked_add(c).is_some() => *k = k.add(c),
+                        _ => self.terms.push((c, t)),
+                    }
+                }
+            },
+        }
+    }
+
+    /// Rebuild the canonical expression, highest degree terms first
+    pub fn to_ex(&self) -> Ex {
+        let mut terms: Vec<(Coef, Ex)> = self
+            .terms
+            .iter()
+            .filter(|(c, _)| !c.is_zero())
+            .cloned()
+            .collect();
+        terms.sort_by(|a, b| cmp_term(&a.1, &b.1));
+        if !self.constant.is_zero() || terms.is_empty() {
+            terms.push((self.constant, Ex::from(1)));
+        }
+
+        let mut out: Option<Ex> = None;
+        for (c, t) in terms {
+            let mag = Product::of(&t);
+            let mag = match mag.coef.checked_mul(c.abs()) {
+                Some(coef) => Product { coef, ..mag }.to_ex(),
+                // too large to hold exactly, so the coefficient stays apart
+                None => c.abs().to_ex() * mag.to_ex(),
+            };
+            out = Some(match (out, c.is_neg()) {
+                (None, false) => mag,
+                (None, true) => -mag,
+                (Some(acc), false) => acc + mag,
+                (Some(acc), true) => acc - mag,
+            });
+        }
+        out.unwrap()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Simplified sum of already simplified terms
+pub fn sum(xs: &[Ex]) -> Ex {
+    if xs.contains(&Ex::Invalid) {
+        return Ex::Invalid;
+    }
+    let mut s = Sum::of(&Ex::from(0));
+    for x in xs {
+        s.push(x, Coef::Q(Rat::ONE));
+    }
+    trig::pythagoras(&mut s.constant, &mut s.terms);
+    s.to_ex()
+}
+
+/// Simplified product of already simplified factors
+pub fn product(xs: &[Ex]) -> Ex {
+    if xs.contains(&Ex::Invalid) {
+        return Ex::Invalid;
+    }
+    let mut p = Product::one();
+    for x in xs {
+        p.push(x, &Ex::from(1));
+    }
+    p.collect().to_ex()
+}
+
+/// Simplified power of an already simplified base and exponent
+pub fn power(base: Ex, exp: Ex) -> Ex {
+    if base == Ex::Invalid || exp == Ex::Invalid {
+        return Ex::Invalid;
+    }
+    if exp.is_zero() {
+        return Ex::from(1);
+    }
+    if exp.is_one() {
+        return base;
+    }
+    if base.is_one() {
+        return Ex::from(1);
+    }
+    if base.is_zero() && exp.as_rat().is_some_and(|k| !k.is_neg()) {
+        return Ex::from(0);
+    }
+    if base == Ex::from(Tok::E) {
+        // e^(k ln(a)) = a^k
+        let p = Product::of(&exp);
+        if let (Coef::Q(k), [(Ex::Fn(Fnc::Ln(a)), one)]) = (p.coef, p.factors.as_slice()) {
+            if one.is_one() {
+                return power((**a).clone(), k.to_ex());
+            }
+        }
+        if let Some(v) = special::exp(&exp) {
+            return v;
+        }
+    }
+    let mut p = Product::one();
+    match exp.as_rat() {
+        Some(k) if k.is_int() => p.push(&base, &exp),
+        _ => p.factors.push((base, exp)),
+    }
+    p.collect().to_ex()
+}
+
+/// Multiply out a sum when that does not make it larger, so that like terms
+/// hidden inside products and powers of sums are collected too
+fn expand_sum(s: Ex) -> Ex {
+    let nested = Sum::of(&s).terms.iter().any(|(_, t)| {
+        Product::of(t)
+            .factors
+            .iter()
+            .any(|(b, _)| matches!(b, Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _)))
+    });
+    if !nested {
+        return s;
+    }
+    match MPoly::from_ex(&s) {
+        Some(p) => {
+            let e = sum(&p.term_exs());
+            if e.size() <= s.size() {
+                e
+            } else {
+                s
+            }
+        }
+        None => s,
+    }
+}
+
+fn modulo(a: Ex, b: Ex) -> Ex {
+    match (Coef::of(&a), Coef::of(&b)) {
+        (Some(Coef::Q(x)), Some(Coef::Q(y))) if x.is_int() && y.is_int() && !y.is_zero() => {
+            Rat::int(x.numer().rem_euclid(y.numer())).to_ex()
+        }
+        (Some(x), Some(y)) if !y.is_zero() => Ex::from(x.flt().rem_euclid(y.flt())),
+        _ => a % b,
+    }
+}
+
+fn function(f: Fnc) -> Ex {
+    let float = |e: &Ex, g: fn(f64) -> f64| match Coef::of(e) {
+        Some(Coef::F(v)) => Some(Coef::F(g(v)).to_ex()),
+        _ => None,
+    };
+    if let Some(v) = special::value(&f) {
+        return v;
+    }
+    let fallback = Ex::Fn(f.clone());
+    match &f {
+        // odd and even
+        Fnc::Sin(a) | Fnc::Tan(a) | Fnc::Atan(a) if trig::negated(a) => {
+            let g = f.map(|a| product(&[Ex::from(-1), a.clone()]));
+            product(&[Ex::from(-1), Ex::Fn(g).simplified()])
+        }
+        Fnc::Cos(a) if trig::negated(a) => {
+            Ex::Fn(f.map(|a| product(&[Ex::from(-1), a.clone()]))).simplified()
+        }
+        Fnc::Ln(a) => float(a, f64::ln).unwrap_or(fallback),
+        Fnc::Sin(a) => float(a, f64::sin).unwrap_or(fallback),
+        Fnc::Cos(a) => float(a, f64::cos).unwrap_or(fallback),
+        Fnc::Tan(a) => float(a, f64::tan).unwrap_or(fallback),
+        Fnc::Atan(a) => float(a, f64::atan).unwrap_or(fallback),
+        Fnc::Abs(_)
+        | Fnc::Floor(_)
+        | Fnc::Ceil(_)
+        | Fnc::Round(_)
+        | Fnc::Frac(_)
+        | Fnc::Sign(_)
+        | Fnc::Norm(_) => piecewise::simplify(&f).unwrap_or(fallback),
+        Fnc::D(a, x) => match x.var() {
+            Some(x) => a.derivative(x),
+            None => fallback,
+        },
+        Fnc::Int(a, x) => match x.var().and_then(|x| a.integrate(x)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::DefInt(a, x, lo, hi) => match x.var().and_then(|x| a.definite(x, lo, hi)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::NInt(a, x, lo, hi) => match x.var().and_then(|x| a.definite_numeric(x, lo, hi)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::Solve(a, x) => match x.var().and_then(|x| a.solve(x, Domain::Real)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::CSolve(a, x) => match x.var().and_then(|x| a.solve(x, Domain::Complex)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::NSolve(a, x, g) => match (x.var(), g.flt(&[])) {
+            (Some(x), Some(g)) => match a.nsolve(x, g) {
+                r if r.converged => Ex::from(x).c(Coef::F(r.value).to_ex(), Rl::Eqq.into()),
+                _ => fallback,
+            },
+            _ => fallback,
+        },
+        Fnc::Zeros(a, x, lo, hi) => match (x.var(), lo.flt(&[]), hi.flt(&[])) {
+            (Some(x), Some(lo), Some(hi)) => Ex::Mat(
+                a.zeros(x, lo, hi)
+                    .into_iter()
+                    .map(|r| Coef::F(r.value).to_ex())
+                    .collect(),
+            ),
+            _ => fallback,
+        },
+        Fnc::Factor(a) => a.factor(),
+        Fnc::Expand(a) => a.expand(Expand::default()),
+        Fnc::ExpandWith(a, o) => match Expand::of(o) {
+            Some(opts) => a.expand(opts),
+            None => fallback,
+        },
+        Fnc::TExpand(a) => a.texpand(),
+        Fnc::TCollect(a) => a.tcollect(),
+        Fnc::Approx(a) => a.approx(mode::DIGITS),
+        Fnc::ApproxTo(a, d) => match d.as_rat() {
+            Some(d) if d.is_int() && (1..=mode::MAX_PRECISION as i128).contains(&d.numer()) => {
+                a.approx(d.numer() as u32)
+            }
+            _ => fallback,
+        },
+        Fnc::Quo(a, b, x) => match x.var().and_then(|x| a.quotient(b, x)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::Rem(a, b, x) => match x.var().and_then(|x| a.remainder(b, x)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::PropFrac(a, x) => match x.var().and_then(|x| a.prop_frac(x)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::Gcd(a, b) => a.gcd(b).unwrap_or(fallback),
+        Fnc::Lcm(a, b) => a.lcm(b).unwrap_or(fallback),
+        Fnc::Lim(a, x, p) => match x.var().and_then(|x| a.limit(x, p, Side::Both)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::LimSide(a, x, p, s) => {
+            let side = match s.flt(&[]) {
+                Some(v) if v < 0.0 => Some(Side::Left),
+                Some(v) if v > 0.0 => Some(Side::Right),
+                Some(_) => Some(Side::Both),
+                None => None,
+            };
+            match (x.var(), side) {
+                (Some(x), Some(side)) => a.limit(x, p, side).unwrap_or(fallback),
+                _ => fallback,
+            }
+        }
+        Fnc::Taylor(a, x, n, p) => {
+            let n = n.as_rat().filter(|n| n.is_int() && !n.is_neg());
+            match (x.var(), n.and_then(|n| u32::try_from(n.numer()).ok())) {
+                (Some(x), Some(n)) => a.taylor(x, n, p).unwrap_or(fallback),
+                _ => fallback,
+            }
+        }
+        Fnc::Sum(a, k, lo, hi) => match k.var().and_then(|k| a.sum_over(k, lo, hi)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::Prod(a, k, lo, hi) => match k.var().and_then(|k| a.prod_over(k, lo, hi)) {
+            Some(r) => r,
+            None => fallback,
+        },
+        Fnc::DeSolve(a, x, y) => match (x.var(), y.var()) {
+            (Some(x), Some(y)) => a.de_solve(x, y, &[]).unwrap_or(fallback),
+            _ => fallback,
+        },
+        Fnc::DeSolveIvp(a, x, y, init) => match (x.var(), y.var(), &**init) {
+            (Some(x), Some(y), Ex::Mat(init)) => a.de_solve(x, y, init).unwrap_or(fallback),
+            _ => fallback,
+        },
+        Fnc::NDeSolve(a, x, y, init, end) => {
+            let init: Option<Vec<f64>> = match &**init {
+                Ex::Mat(xs) => xs.iter().map(|v| v.flt(&[])).collect(),
+                _ => None,
+            };
+            match (x.var(), y.var(), init, end.flt(&[])) {
+                (Some(x), Some(y), Some(init), Some(end)) => match a.nde_solve(x, y, &init, end) {
+                    Some(t) if t.converged => Ex::Mat(
+                        t.points
+                            .into_iter()
+                            .map(|(x, y)| Ex::Mat(vec![Coef::F(x).to_ex(), Coef::F(y[0]).to_ex()]))
+                            .collect(),
+                    ),
+                    _ => fallback,
+                },
+                _ => fallback,
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+fn mul_exp(a: &Ex, b: &Ex) -> Ex {
+    product(&[a.clone(), b.clone()])
+}
+
+fn raise(base: &Ex, exp: Ex) -> Ex {
+    if exp.is_one() {
+        base.clone()
+    } else {
+        base.clone().pow(exp)
+    }
+}
+
+fn chain(xs: Vec<Ex>) -> Ex {
+    xs.into_iter().reduce(|acc, x| acc * x).unwrap()
+}
+
+/// Split `q^k` into an exact rational part and a remaining radical
+fn rational_root(q: Rat, k: Rat) -> (Rat, Option<(Rat, Rat)>) {
+    let whole = k.floor();
+    let frac = k - Rat::int(whole);
+    let Some(outer) = q.powi(whole) else {
+        return (Rat::ONE, Some((q, k)));
+    };
+    let (p, r) = (frac.numer(), frac.denom());
+    let Some(inner) = q.powi(p) else {
+        return (outer, Some((q, frac)));
+    };
+    let (n_out, n_in) = extract_power(inner.numer(), r);
+    let (d_out, d_in) = extract_power(inner.denom(), r);
+    let rest = Rat::new(n_in, d_in);
+    let Some(outside) = outer.checked_mul(Rat::new(n_out, d_out)) else {
+        return (Rat::ONE, Some((q, k)));
+    };
+    if rest.is_one() {
+        (outside, None)
+    } else {
+        (outside, Some((rest, Rat::new(1, r))))
+    }
+}
+
+/// Write `n = out^k * in` with `in` free of `k`th powers (small factors only)
+fn extract_power(n: i128, k: i128) -> (i128, i128) {
+    let k = k as u32;
+    let (mut out, mut rest) = (1, n);
+    let mut f: i128 = 2;
+    while f < 10_000 && f.checked_pow(k).is_some_and(|fk| fk <= rest) {
+        let fk = f.pow(k);
+        while rest % fk == 0 {
+            rest /= fk;
+            out *= f;
+        }
+        f += 1;
+    }
+    (out, rest)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Total order used to sort factors into canonical form
+pub fn cmp_ex(a: &Ex, b: &Ex) -> Ordering {
+    fn rank(e: &Ex) -> u8 {
+        match e {
+            Ex::Val(Val::Num(_)) => 0,
+            Ex::Val(Val::Tok(_)) => 1,
+            Ex::Val(Val::Var(_)) => 2,
+            Ex::Val(Val::Bool(_)) => 3,
+            Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _) => 4,
+            Ex::Infix(_, _, _) | Ex::Neg(_) => 5,
+            Ex::Fn(_) => 6,
+            Ex::Mat(_) => 7,
+            Ex::Invalid => 8,
+        }
+    }
+    match (a, b) {
+        (Ex::Val(Val::Var(x)), Ex::Val(Val::Var(y))) => x.cmp(y),
+        _ => rank(a)
+            .cmp(&rank(b))
+            .then_with(|| format!("{:?}", a).cmp(&format!("{:?}", b))),
+    }
+}
+
+/// Order of terms in a sum: descending degree, then by [`cmp_ex`]
+pub fn cmp_term(a: &Ex, b: &Ex) -> Ordering {
+    degree(b).cmp(&degree(a)).then_with(|| cmp_ex(a, b))
+}
+
+/// Total degree of a monomial-like term in all of its variables
+pub fn degree(e: &Ex) -> Rat {
+    Product::of(e)
+        .factors
+        .iter()
+        .filter(|(b, _)| b.var().is_some())
+        .filter_map(|(_, e)| e.as_rat())
+        .fold(Rat::ZERO, |a, b| a + b)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::Ex;
+
+    fn simp(s: &str) -> String {
+        format!("{:?}", s.parse::<Ex>().unwrap().simplified())
+    }
+
+    #[test]
+    fn ex3_simplify_constants() {
+        assert_eq!(simp("1+2*3"), "7");
+        assert_eq!(simp("1/2+1/3"), "5/6");
+        assert_eq!(simp("6/14"), "3/7");
+        assert_eq!(simp("2^-2"), "1/4");
+        assert_eq!(simp("8^(1/3)"), "2");
+        assert_eq!(simp("12^(1/2)"), simp("2*3^(1/2)"));
+        assert_eq!(simp("1.5+1"), "2.5");
+        assert_eq!(simp("x*((6/14)^2) | x = 5 + 7"), "108/49");
+    }
+
+    #[test]
+    fn ex3_simplify_products() {
+        assert_eq!(simp("x*x"), simp("x^2"));
+        assert_eq!(simp("x/x"), "1");
+        assert_eq!(simp("x/(x^2)"), simp("1/x"));
+        assert_eq!(simp("x*(((x^2)/x)^1)"), simp("x^2"));
+        assert_eq!(simp("(2*x)^2"), simp("4*x^2"));
+        assert_eq!(simp("-(-x)"), "\"x\"");
+        assert_eq!(simp("0*x+1*y"), "\"y\"");
+    }
+
+    #[test]
+    fn ex3_simplify_large_ints() {
+        assert_eq!(simp("2^31"), "2147483648");
+        assert_eq!(simp("3000000000*2"), "6000000000");
+        assert!(!simp("2^127").contains('.'));
+        assert!(!simp("expand((x+1000)^20)").contains('.'));
+        assert!(!simp("taylor(e^x, x, 40)").contains('.'));
+        assert!(!simp("sum(1000^k, k, 1, 20)").contains('.'));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,774 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, helper::*, poly::*, rational::*, simplify::*, trace};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Integer parameter of periodic solutions, never produced by the parser
+const N: &str = "@n";
+
+/// Highest power expanded when reading symbolic polynomial coefficients
+const MAX_POWER: i128 = 8;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Which numbers count as solutions
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Domain {
+    Real,
+    Complex,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Solve the equation `self` for the variable `x`
+    ///
+    /// - Anything that is not a relation is solved as `self = 0`, and
+    ///   `a ∝ b` as `a = @k*b`
+    /// - Polynomials with rational coefficients are solved exactly up to
+    ///   degree four where practical, numerically otherwise
+    /// - Other equations are solved by undoing invertible functions
+    /// - Returns `x = a or x = b ..`, `false` if there is no solution and
+    ///   `true` if every value is one
+    /// - Returns `None` if the equation could not be solved
+    pub fn solve(&self, x: &str, domain: Domain) -> Option<Ex> {
+        let (lhs, rhs) = match self {
+            Ex::Infix(a, Bin::Rl(Rl::Eqq), b) => (a.simplified(), b.simplified()),
+            Ex::Infix(_, Bin::Rl(Rl::Prop), _) => return self.as_equation().solve(x, domain),
+            Ex::Infix(_, Bin::Rl(_), _) => return None,
+            _ => (self.simplified(), Ex::from(0)),
+        };
+        let f = sum(&[lhs.clone(), product(&[Ex::from(-1), rhs.clone()])]);
+        trace::record(
+            "subtract right side",
+            &lhs.clone().c(rhs.clone(), Rl::Eqq.into()),
+            &f.clone().c(Ex::from(0), Rl::Eqq.into()),
+        );
+        if !f.has(x) {
+            return Coef::of(&f).map(|c| Ex::from(c.is_zero()));
+        }
+        if UPoly::ratfn_from_ex(&f, x).is_some_and(|(n, _)| n.is_zero()) {
+            return Some(Ex::from(true));
+        }
+
+        let (method, roots) = trace::quiet(|| {
+            rational(&f, x, domain)
+                .map(|r| ("polynomial roots", r))
+                .or_else(|| isolate(&lhs, &rhs, x, domain).map(|r| ("isolate", r)))
+                .or_else(|| symbolic(&f, x, domain).map(|r| ("formula", r)))
+        })?;
+
+        let eq = |r: Ex| Ex::from(x).c(r, Rl::Eqq.into());
+        let or = |xs: Vec<Ex>| {
+            xs.into_iter()
+                .map(eq)
+                .reduce(|acc, e| acc.c(e, Rl::Or.into()))
+                .unwrap_or(Ex::from(false))
+        };
+        let candidates = or(roots.clone());
+        trace::record(
+            method,
+            &f.clone().c(Ex::from(0), Rl::Eqq.into()),
+            &candidates,
+        );
+
+        let mut poles = vec![];
+        denominators(self, x, &mut poles);
+        let mut out: Vec<Ex> = vec![];
+        for r in roots.iter().map(Ex::simplified) {
+            if !out.contains(&r)
+                && satisfies(&lhs, &rhs, x, &r, domain)
+                && !poles.iter().any(|d| d.subs(x, &r).simplified().is_zero())
+            {
+                out.push(r);
+            }
+        }
+        out.sort_by(|a, b| match (a.flt(&[]), b.flt(&[])) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+
+        let out = or(out);
+        trace::record("check solutions", &candidates, &out);
+        Some(out)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Reject roots outside the domain and extraneous roots introduced by
+/// clearing denominators or raising both sides to a power
+fn satisfies(lhs: &Ex, rhs: &Ex, x: &str, r: &Ex, domain: Domain) -> bool {
+    if r == &Ex::Invalid {
+        return false;
+    }
+    if domain == Domain::Real && r.contains(&Ex::from(Tok::I)) {
+        return false;
+    }
+    let Some(v) = r.flt(&[]) else {
+        // a periodic family must be real at its base, unless that is symbolic
+        let base = r.subs(N, &Ex::from(0));
+        return domain == Domain::Complex
+            || !r.has(N)
+            || base.flt(&[]).is_some()
+            || symbolic_in(&base);
+    };
+    if !v.is_finite() {
+        return false;
+    }
+    match (lhs.flt(&[(x, v)]), rhs.flt(&[(x, v)])) {
+        (Some(a), Some(b)) => {
+            a.is_finite() && b.is_finite() && (a - b).abs() <= 1e-6 * (1.0 + a.abs() + b.abs())
+        }
+        _ => true,
+    }
+}
+
+/// Denominators in `x` of the equation as written, which simplifying may
+/// cancel, as in `(x^2-1)/(x-1)`
+fn denominators(e: &Ex, x: &str, out: &mut Vec<Ex>) {
+    match e {
+        Ex::Infix(_, Bin::Op(Op::Div), d) if d.has(x) => out.push((**d).clone()),
+        Ex::Infix(b, Bin::Op(Op::Pow), k) if b.has(x) && k.flt(&[]).is_some_and(|k| k < 0.0) => {
+            out.push((**b).clone())
+        }
+        _ => {}
+    }
+    e.children()
+        .into_iter()
+        .for_each(|a| denominators(a, x, out));
+}
+
+/// Whether `e` has any variable
+fn symbolic_in(e: &Ex) -> bool {
+    e.var().is_some() || e.children().into_iter().any(symbolic_in)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Roots of the numerator of a rational function with rational coefficients
+fn rational(f: &Ex, x: &str, domain: Domain) -> Option<Vec<Ex>> {
+    let (num, _) = UPoly::ratfn_from_ex(f, x)?;
+    upoly_roots(&num, domain)
+}
+
+/// Distinct roots of a polynomial with rational coefficients
+///
+/// - Rational roots are divided out first
+/// - What is left is solved by formula, by substituting `u = x^k`, by
+///   Cardano's method or numerically
+/// - `None` if the coefficients overflow along the way
+fn upoly_roots(p: &UPoly, domain: Domain) -> Option<Vec<Ex>> {
+    if p.deg() == 0 {
+        return Some(vec![]);
+    }
+    // square-free part, so every root is simple
+    let mut p = p.divrem(&p.gcd(&p.derivative()?)?)?.0;
+    let mut out = vec![];
+    for r in p.rational_roots() {
+        p = p.divrem(&UPoly::linear(r))?.0;
+        out.push(r.to_ex());
+    }
+
+    let c: Vec<Ex> = p.coeffs().iter().map(|a| a.to_ex().simplified()).collect();
+    match p.deg() {
+        0 => {}
+        1 | 2 => out.extend(formula(&c, domain)),
+        n => match substitution(&p, domain) {
+            Some(rs) => out.extend(rs),
+            None if n == 3 => {
+                out.extend(cardano(&p, domain).unwrap_or_else(|| numeric(&p, domain)))
+            }
+            None => out.extend(numeric(&p, domain)),
+        },
+    }
+    Some(out)
+}
+
+/// Solve `q(x^k) = 0` as `q(u) = 0` followed by `x^k = u`
+fn substitution(p: &UPoly, domain: Domain) -> Option<Vec<Ex>> {
+    let k = (1..=p.deg())
+        .filter(|&i| !p.coeff(i).is_zero())
+        .fold(0, |acc, i| gcd(acc, i as i128));
+    if k < 2 {
+        return None;
+    }
+    let q = UPoly::new(p.coeffs().iter().step_by(k as usize).copied().collect());
+    let us = upoly_roots(&q, Domain::Real)?;
+    if domain == Domain::Complex && us.len() < q.deg() {
+        return None;
+    }
+    Some(
+        us.iter()
+            .flat_map(|u| roots_of(u, k as u32, domain))
+            .collect(),
+    )
+}
+
+/// Roots of an irreducible cubic
+///
+/// - With one real and two complex roots by Cardano's formula
+/// - With three real roots in trigonometric form, see [`trigonometric`]
+fn cardano(p: &UPoly, domain: Domain) -> Option<Vec<Ex>> {
+    let p = p.monic()?;
+    let (b, c, d) = (p.coeff(2), p.coeff(1), p.coeff(0));
+    let three = Rat::int(3);
+    // x = t - b/3 turns the cubic into t^3 + s*t + q
+    let b2 = b.checked_mul(b)?;
+    let s = c.checked_sub(b2.checked_div(three)?)?;
+    let q = Rat::new(2, 27).checked_mul(b2.checked_mul(b)?)?;
+    let q = q
+        .checked_sub(b.checked_mul(c)?.checked_div(three)?)?
+        .checked_add(d)?;
+    let disc = q.checked_div(Rat::int(2))?.powi(2)?;
+    let disc = disc.checked_add(s.checked_div(three)?.powi(3)?)?;
+    let shift = b.checked_div(-three)?.to_ex().simplified();
+    if disc.is_zero() {
+        return None;
+    }
+    if disc.is_neg() {
+        return trigonometric(s, q, shift);
+    }
+
+    let half = q.checked_div(Rat::int(-2))?.to_ex();
+    let sqrt = power(disc.to_ex().simplified(), Rat::new(1, 2).to_ex());
+    let u = cbrt(sum(&[half.simplified(), sqrt.clone()]));
+    let v = cbrt(sum(&[half.simplified(), product(&[Ex::from(-1), sqrt])]));
+
+    let mut out = vec![sum(&[u.clone(), v.clone(), shift.clone()])];
+    if domain == Domain::Complex {
+        let re = sum(&[
+            product(&[Rat::new(-1, 2).to_ex(), sum(&[u.clone(), v.clone()])]),
+            shift,
+        ]);
+        let im = product(&[
+            power(three.to_ex(), Rat::new(1, 2).to_ex()),
+            Rat::new(1, 2).to_ex(),
+            sum(&[u, product(&[Ex::from(-1), v])]),
+            Ex::from(Tok::I),
+        ]);
+        out.push(sum(&[re.clone(), im.clone()]));
+        out.push(sum(&[re, product(&[Ex::from(-1), im])]));
+    }
+    Some(out)
+}
+
+/// The three real roots of `t^3 + s*t + q` with a negative discriminant,
+/// plus `shift`
+///
+/// - `t = 2*(-s/3)^(1/2) * cos(acos(z)/3 - 2*pi*k/3)` for `k = 0, 1, 2`,
+///   with `z = 3*q/(2*s) * (-3/s)^(1/2)`
+fn trigonometric(s: Rat, q: Rat, shift: Ex) -> Option<Vec<Ex>> {
+    let three = Rat::int(3);
+    let half = Rat::new(1, 2).to_ex();
+    let m = power((-s).checked_div(three)?.to_ex(), half.clone());
+    let z = product(&[
+        q.checked_mul(three)?
+            .checked_div(s.checked_mul(Rat::int(2))?)?
+            .to_ex(),
+        power(Rat::int(-3).checked_div(s)?.to_ex(), half),
+    ])
+    .simplified();
+    let angle = product(&[Rat::new(1, 3).to_ex(), arccos(&z)]);
+    Some(
+        (0..3)
+            .map(|k| {
+                let turn = product(&[Rat::new(-2 * k, 3).to_ex(), Ex::from(Tok::Pi)]);
+                let t = product(&[Ex::from(2), m.clone(), cos(sum(&[angle.clone(), turn]))]);
+                sum(&[t, shift.clone()])
+            })
+            .collect(),
+    )
+}
+
+/// `asin(r)`, written with `atan` as there is no `asin`
+fn arcsin(r: &Ex) -> Ex {
+    let half_pi = product(&[Rat::new(1, 2).to_ex(), Ex::from(Tok::Pi)]);
+    match r.flt(&[]) {
+        Some(1.0) => half_pi,
+        Some(-1.0) => product(&[Ex::from(-1), half_pi]),
+        _ => {
+            let square = power(r.clone(), Ex::from(2));
+            let root = power(
+                sum(&[Ex::from(1), product(&[Ex::from(-1), square])]),
+                Rat::new(-1, 2).to_ex(),
+            );
+            atan(product(&[r.clone(), root]))
+        }
+    }
+}
+
+/// `acos(r) = pi/2 - asin(r)`
+fn arccos(r: &Ex) -> Ex {
+    let half_pi = product(&[Rat::new(1, 2).to_ex(), Ex::from(Tok::Pi)]);
+    sum(&[half_pi, product(&[Ex::from(-1), arcsin(r)])])
+}
+
+/// Real cube root, taking the sign outside so the radicand is positive
+fn cbrt(e: Ex) -> Ex {
+    let third = Rat::new(1, 3).to_ex();
+    match e.flt(&[]) {
+        Some(v) if v < 0.0 => product(&[Ex::from(-1), power(negate(&e), third)]),
+        _ => power(e, third),
+    }
+}
+
+/// Negate a simplified sum term by term, rather than wrapping it
+fn negate(e: &Ex) -> Ex {
+    let s = Sum::of(e);
+    let minus = Coef::Q(-Rat::ONE);
+    Sum {
+        constant: s.constant.mul(minus),
+        terms: s
+            .terms
+            .into_iter()
+            .map(|(c, t)| (c.mul(minus), t))
+            .collect(),
+    }
+    .to_ex()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Roots by formula of `c[0] + c[1]*x` or `c[0] + c[1]*x + c[2]*x^2`, with
+/// simplified coefficients
+fn formula(c: &[Ex], domain: Domain) -> Vec<Ex> {
+    let neg = |e: Ex| product(&[Ex::from(-1), e]);
+    let div = |a: Ex, b: Ex| product(&[a, power(b, Ex::from(-1))]);
+    match c {
+        [c0, c1] => vec![div(neg(c0.clone()), c1.clone())],
+        [c0, c1, c2] if c0.is_zero() => vec![Ex::from(0), div(neg(c1.clone()), c2.clone())],
+        [c0, c1, c2] => {
+            let disc = sum(&[
+                power(c1.clone(), Ex::from(2)),
+                product(&[Ex::from(-4), c2.clone(), c0.clone()]),
+            ]);
+            let two_a = product(&[Ex::from(2), c2.clone()]);
+            let mid = div(neg(c1.clone()), two_a.clone());
+            if disc.is_zero() {
+                return vec![mid];
+            }
+            let offs: Vec<Ex> = roots_of(&disc, 2, domain)
+                .into_iter()
+                .map(|r| div(r, two_a.clone()))
+                .collect();
+            offs.into_iter().map(|r| sum(&[mid.clone(), r])).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Every `k`th root of `u`
+///
+/// - In the real domain, negative `u` only has a root for odd `k`
+/// - In the complex domain, the roots of numeric `u` are spread evenly
+///   around the circle
+fn roots_of(u: &Ex, k: u32, domain: Domain) -> Vec<Ex> {
+    let neg = |e: Ex| product(&[Ex::from(-1), e]);
+    let root = |e: Ex| power(e, Rat::new(1, k as i128).to_ex());
+    let even = k.is_multiple_of(2);
+    let negative = u.flt(&[]).is_some_and(|v| v < 0.0);
+    if u.is_zero() {
+        return vec![Ex::from(0)];
+    }
+    if domain == Domain::Complex && u.flt(&[]).is_some() && (k > 2 || negative) {
+        // |u|^(1/k) * e^(i*pi*(2j + s)/k), with s = 1 for negative u
+        let r = root(if negative { neg(u.clone()) } else { u.clone() });
+        let s = if negative { 1 } else { 0 };
+        return (0..k as i128)
+            .rev()
+            .map(|j| {
+                let t = Rat::new(2 * j + s, k as i128);
+                match (t.numer(), t.denom()) {
+                    (0, _) => r.clone(),
+                    (1, 1) => neg(r.clone()),
+                    (1, 2) => product(&[r.clone(), Ex::from(Tok::I)]),
+                    (3, 2) => neg(product(&[r.clone(), Ex::from(Tok::I)])),
+                    _ => {
+                        let angle = product(&[t.to_ex().simplified(), Ex::from(Tok::Pi)]);
+                        let unit =
+                            sum(&[cos(angle.clone()), product(&[sin(angle), Ex::from(Tok::I)])]);
+                        product(&[r.clone(), unit])
+                    }
+                }
+            })
+            .collect();
+    }
+    match (even, negative) {
+        (true, true) => vec![],
+        (true, false) => vec![neg(root(u.clone())), root(u.clone())],
+        (false, true) => vec![neg(root(neg(u.clone())))],
+        (false, false) => vec![root(u.clone())],
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Numeric roots with the Durand–Kerner iteration
+fn numeric(p: &UPoly, domain: Domain) -> Vec<Ex> {
+    let c: Vec<f64> = p.coeffs().iter().map(|a| a.flt()).collect();
+    durand_kerner(&c)
+        .into_iter()
+        .filter_map(|(re, im)| {
+            if im.abs() <= 1e-9 * (1.0 + re.abs()) {
+                Some(Coef::F(polish(&c, re)).to_ex())
+            } else if domain == Domain::Complex {
+                Some(sum(&[
+                    Coef::F(re).to_ex(),
+                    product(&[Coef::F(im).to_ex(), Ex::from(Tok::I)]),
+                ]))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// All complex roots of the polynomial with coefficients `c`, lowest first
+fn durand_kerner(c: &[f64]) -> Vec<(f64, f64)> {
+    fn mul(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+        (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+    }
+    fn div(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+        let d = b.0 * b.0 + b.1 * b.1;
+        ((a.0 * b.0 + a.1 * b.1) / d, (a.1 * b.0 - a.0 * b.1) / d)
+    }
+
+    let n = c.len() - 1;
+    let lead = c[n];
+    let eval = |z: (f64, f64)| {
+        c.iter().rev().fold((0.0, 0.0), |acc, a| {
+            let m = mul(acc, z);
+            (m.0 + a / lead, m.1)
+        })
+    };
+
+    let mut z: Vec<(f64, f64)> = vec![(1.0, 0.0)];
+    for _ in 1..n {
+        let last = *z.last().unwrap();
+        z.push(mul(last, (0.4, 0.9)));
+    }
+    for _ in 0..1000 {
+        let mut step = 0.0_f64;
+        for i in 0..n {
+            let den = (0..n).filter(|&j| j != i).fold((1.0, 0.0), |acc, j| {
+                mul(acc, (z[i].0 - z[j].0, z[i].1 - z[j].1))
+            });
+            let d = div(eval(z[i]), den);
+            z[i] = (z[i].0 - d.0, z[i].1 - d.1);
+            step = step.max(d.0.hypot(d.1));
+        }
+        if step < 1e-15 {
+            break;
+        }
+    }
+    z
+}
+
+/// A few Newton steps on a real root
+fn polish(c: &[f64], mut x: f64) -> f64 {
+    for _ in 0..8 {
+        let (f, df) = c
+            .iter()
+            .rev()
+            .fold((0.0, 0.0), |(f, df), a| (f * x + a, df * x + f));
+        if df == 0.0 {
+            break;
+        }
+        x -= f / df;
+    }
+    x
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Undo the outermost operation on the side containing `x` until `x` is
+/// alone
+fn isolate(lhs: &Ex, rhs: &Ex, x: &str, domain: Domain) -> Option<Vec<Ex>> {
+    if !lhs.has(x) {
+        return match rhs.has(x) {
+            true => isolate(rhs, lhs, x, domain),
+            false => None,
+        };
+    }
+    if rhs.has(x) {
+        return None;
+    }
+    let r = rhs.clone();
+    let neg = |e: &Ex| product(&[Ex::from(-1), e.clone()]);
+    let inv = |e: &Ex| power(e.clone(), Ex::from(-1));
+    let go = |a: &Ex, r: Ex| isolate(a, &r, x, domain);
+
+    match lhs {
+        Ex::Val(Val::Var(y)) if y == x => Some(vec![r]),
+        Ex::Neg(a) => go(a, neg(&r)),
+        Ex::Infix(a, Bin::Op(op), b) => match (op, a.has(x), b.has(x)) {
+            (Op::Add, true, false) => go(a, sum(&[r, neg(b)])),
+            (Op::Add, false, true) => go(b, sum(&[r, neg(a)])),
+            (Op::Sub, true, false) => go(a, sum(&[r, *b.clone()])),
+            (Op::Sub, false, true) => go(b, sum(&[*a.clone(), neg(&r)])),
+            (Op::Mul, true, false) => go(a, product(&[r, inv(b)])),
+            (Op::Mul, false, true) => go(b, product(&[r, inv(a)])),
+            (Op::Div, true, false) => go(a, product(&[r, *b.clone()])),
+            (Op::Div, false, true) if !r.is_zero() => go(b, product(&[*a.clone(), inv(&r)])),
+            (Op::Pow, true, false) => match b.as_rat() {
+                Some(k) if k.is_int() && !k.is_zero() && k.numer().abs() <= 64 => {
+                    let u = if k.is_neg() { inv(&r) } else { r };
+                    let n = k.numer().unsigned_abs() as u32;
+                    collect(roots_of(&u, n, domain).into_iter().map(|u| go(a, u)))
+                }
+                _ => go(a, power(r, inv(b))),
+            },
+            (Op::Pow, false, true) => {
+                let exp = match **a == Ex::from(Tok::E) {
+                    true => ln(r),
+                    false => product(&[ln(r), inv(&ln(*a.clone()))]),
+                };
+                match domain {
+                    Domain::Real => go(b, exp.simplified()),
+                    Domain::Complex => {
+                        let period = product(&[
+                            Ex::from(2),
+                            Ex::from(Tok::Pi),
+                            Ex::from(Tok::I),
+                            Ex::from(N),
+                        ]);
+                        let period = match **a == Ex::from(Tok::E) {
+                            true => period,
+                            false => product(&[period, inv(&ln(*a.clone()))]),
+                        };
+                        go(b, sum(&[exp.simplified(), period]))
+                    }
+                }
+            }
+            _ => None,
+        },
+        Ex::Fn(f) => {
+            let pi_n = product(&[Ex::from(Tok::Pi), Ex::from(N)]);
+            match f {
+                Fnc::Ln(a) => go(a, power(Ex::from(Tok::E), r)),
+                Fnc::Sin(a) if r.is_zero() => go(a, pi_n),
+                Fnc::Cos(a) if r.is_zero() => {
+                    let half = product(&[Rat::new(1, 2).to_ex(), Ex::from(Tok::Pi)]);
+                    go(a, sum(&[half, pi_n]))
+                }
+                Fnc::Sin(a) | Fnc::Cos(a) => {
+                    // no real angle has a sine or cosine beyond one
+                    if r.flt(&[]).is_some_and(|v| v.abs() > 1.0) {
+                        return (domain == Domain::Real).then(Vec::new);
+                    }
+                    let two_pi_n = product(&[Ex::from(2), Ex::from(Tok::Pi), Ex::from(N)]);
+                    let pi = Ex::from(Tok::Pi);
+                    let neg = |e: Ex| product(&[Ex::from(-1), e]);
+                    // at one and minus one both branches are the same family
+                    let edge = r.flt(&[]).is_some_and(|v| v.abs() == 1.0);
+                    let mut branches = match f {
+                        Fnc::Sin(_) => vec![arcsin(&r), sum(&[pi, neg(arcsin(&r))])],
+                        _ => vec![arccos(&r), neg(arccos(&r))],
+                    };
+                    if edge {
+                        branches.truncate(1);
+                    }
+                    collect(
+                        branches
+                            .into_iter()
+                            .map(|u| go(a, sum(&[u.simplified(), two_pi_n.clone()]))),
+                    )
+                }
+                Fnc::Tan(a) => go(a, sum(&[atan(r).simplified(), pi_n])),
+                Fnc::Atan(a) => go(a, tan(r).simplified()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Concatenate the solutions of every branch, failing if any branch fails
+fn collect<I: Iterator<Item = Option<Vec<Ex>>>>(branches: I) -> Option<Vec<Ex>> {
+    let mut out = vec![];
+    for b in branches {
+        out.extend(b?);
+    }
+    Some(out)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Linear and quadratic equations with coefficients free of `x`
+fn symbolic(f: &Ex, x: &str, domain: Domain) -> Option<Vec<Ex>> {
+    let c = coeffs(f, x)?;
+    (c.len() <= 3).then(|| formula(&c, domain))
+}
+
+/// Coefficients of `f` as a polynomial in `x`, lowest degree first
+pub fn coeffs(f: &Ex, x: &str) -> Option<Vec<Ex>> {
+    fn add(a: &[Ex], b: &[Ex]) -> Vec<Ex> {
+        let n = a.len().max(b.len());
+        let zero = Ex::from(0);
+        (0..n)
+            .map(|i| {
+                sum(&[
+                    a.get(i).unwrap_or(&zero).clone(),
+                    b.get(i).unwrap_or(&zero).clone(),
+                ])
+            })
+            .collect()
+    }
+    fn mul(a: &[Ex], b: &[Ex]) -> Vec<Ex> {
+        let mut c = vec![Ex::from(0); a.len() + b.len() - 1];
+        for (i, p) in a.iter().enumerate() {
+            for (j, q) in b.iter().enumerate() {
+                c[i + j] = sum(&[c[i + j].clone(), product(&[p.clone(), q.clone()])]);
+            }
+        }
+        c
+    }
+    fn scale(a: &[Ex], k: &Ex) -> Vec<Ex> {
+        a.iter().map(|p| product(&[p.clone(), k.clone()])).collect()
+    }
+
+    let mut c = match f {
+        _ if !f.has(x) => vec![f.clone()],
+        Ex::Val(Val::Var(_)) => vec![Ex::from(0), Ex::from(1)],
+        Ex::Neg(a) => scale(&coeffs(a, x)?, &Ex::from(-1)),
+        Ex::Infix(a, Bin::Op(op), b) => match op {
+            Op::Add => add(&coeffs(a, x)?, &coeffs(b, x)?),
+            Op::Sub => add(&coeffs(a, x)?, &scale(&coeffs(b, x)?, &Ex::from(-1))),
+            Op::Mul => mul(&coeffs(a, x)?, &coeffs(b, x)?),
+            Op::Div if !b.has(x) => scale(&coeffs(a, x)?, &power(*b.clone(), Ex::from(-1))),
+            Op::Pow => {
+                let k = b.as_rat().filter(|k| k.is_int() && !k.is_neg())?;
+                if k.numer() > MAX_POWER {
+                    return None;
+                }
+                let a = coeffs(a, x)?;
+                (0..k.numer()).fold(vec![Ex::from(1)], |acc, _| mul(&acc, &a))
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    while c.len() > 1 && c.last().is_some_and(Ex::is_zero) {
+        c.pop();
+    }
+    Some(c)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::Domain;
+    use crate::ex3::ast::Ex;
+    use crate::ex3::testing::simp;
+
+    fn solve(s: &str) -> Ex {
+        s.parse::<Ex>().unwrap().solve("x", Domain::Real).unwrap()
+    }
+
+    fn csolve(s: &str) -> Ex {
+        s.parse::<Ex>()
+            .unwrap()
+            .solve("x", Domain::Complex)
+            .unwrap()
+    }
+
+    #[test]
+    fn ex3_solve_polynomial() {
+        assert_eq!(solve("2*x+3 = 7"), simp("x = 2"));
+        assert_eq!(solve("x^2 = 4"), simp("x = -2 or x = 2"));
+        assert_eq!(solve("x^2-2*x-3"), simp("x = -1 or x = 3"));
+        assert_eq!(solve("x^2 = 2"), simp("x = -(2^(1/2)) or x = 2^(1/2)"));
+        assert_eq!(solve("(x-1)^2 = 0"), simp("x = 1"));
+        assert_eq!(
+            solve("x^3-6*x^2+11*x-6 = 0"),
+            simp("x = 1 or x = 2 or x = 3")
+        );
+        assert_eq!(
+            solve("x^4-5*x^2+6 = 0"),
+            simp("x = -(3^(1/2)) or x = -(2^(1/2)) or x = 2^(1/2) or x = 3^(1/2)")
+        );
+        assert_eq!(solve("x^3 = 2"), simp("x = 2^(1/3)"));
+        assert_eq!(solve("1/x = 2"), simp("x = 1/2"));
+        // the cancelled factor still excludes its root
+        assert_eq!(solve("(x^2-1)/(x-1) = 2"), Ex::from(false));
+        assert_eq!(solve("x/x^2 = 1"), simp("x = 1"));
+        assert_eq!(solve("x*(x-1)^(-1) = 1/(x-1)"), Ex::from(false));
+    }
+
+    #[test]
+    fn ex3_solve_domain() {
+        assert_eq!(solve("x^2+1 = 0"), Ex::from(false));
+        assert_eq!(csolve("x^2+1 = 0"), simp("x = -i or x = i"));
+        assert_eq!(csolve("x^2+2*x+5 = 0"), simp("x = -1-2*i or x = -1+2*i"));
+        assert_eq!(solve("x = x"), Ex::from(true));
+        assert_eq!(solve("x+1 = x"), Ex::from(false));
+    }
+
+    #[test]
+    fn ex3_solve_numeric() {
+        // three real irrational roots, exact in trigonometric form
+        assert_eq!(
+            solve("x^3-3*x+1 = 0"),
+            simp("x = 2*cos(10*pi/9) or x = 2*cos(4*pi/9) or x = 2*cos(2*pi/9)")
+        );
+        let roots = format!("{:?}", solve("x^3-7*x+7 = 0"));
+        assert_eq!(roots.matches("or").count(), 2, "{}", roots);
+        assert!(!roots.contains('.'), "{}", roots);
+
+        // one real root by Cardano, x^3+x+1
+        let r = solve("x^3+x+1 = 0");
+        match r {
+            Ex::Infix(_, _, r) => {
+                let v = r.flt(&[]);
+                assert!(
+                    (v.unwrap() + 0.682_327_803_828_019_3).abs() < 1e-12,
+                    "{:?} {:?}",
+                    r,
+                    v
+                )
+            }
+            _ => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn ex3_solve_invertible() {
+        assert_eq!(solve("e^x = 5"), simp("x = ln(5)"));
+        assert_eq!(solve("ln(x) = 2"), simp("x = e^2"));
+        assert_eq!(solve("x^(1/2) = 3"), simp("x = 9"));
+        assert_eq!(solve("x^(1/2) = -3"), Ex::from(false));
+        assert_eq!(solve("2^x = 8"), simp("x = ln(8)/ln(2)"));
+        let base = |s: &str| solve(s).subs("@n", &Ex::from(0)).simplified();
+        assert_eq!(base("sin(x) = 1/2"), simp("x = pi/6 or x = 5*pi/6"));
+        assert_eq!(base("cos(x) = 1/2"), simp("x = pi/3 or x = -pi/3"));
+        assert_eq!(base("sin(x) = -1"), simp("x = -pi/2"));
+        assert_eq!(base("tan(x) = a"), simp("x = atan(a)"));
+        assert_eq!(solve("sin(x) = 2"), Ex::from(false));
+        assert_eq!(solve("a*x+b = c"), simp("x = (c-b)/a"));
+        assert_eq!(solve("x^2 = a"), simp("x = -(a^(1/2)) or x = a^(1/2)"));
+        assert_eq!(solve("x^2+b*x = 0"), simp("x = 0 or x = -b"));
+        assert_eq!(
+            solve("x^2+b*x+c = 0"),
+            simp("x = -b/2 - (b^2-4*c)^(1/2)/2 or x = -b/2 + (b^2-4*c)^(1/2)/2")
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
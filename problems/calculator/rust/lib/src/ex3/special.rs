@@ -0,0 +1,195 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Exact value of an elementary function at a special argument
+///
+/// - `sin`, `cos` and `tan` of multiples of `pi/12`, and `atan` of the
+///   values `tan` takes there
+/// - `ln(1) = 0` and `ln(e^k) = k` for real `k`
+/// - `None` for anything else, including `tan(pi/2)`
+pub fn value(f: &Fnc) -> Option<Ex> {
+    match f {
+        Fnc::Sin(a) => Some(sin(twelfths(a)?)),
+        Fnc::Cos(a) => Some(sin(twelfths(a)? + 6)),
+        Fnc::Tan(a) => tan(twelfths(a)?),
+        Fnc::Atan(a) => (-5..=5)
+            .find(|&k| tan(k).as_ref() == Some(a))
+            .map(|k| product(&[Rat::new(k, 12).to_ex(), Ex::from(Tok::Pi)])),
+        Fnc::Ln(a) if a.is_one() && !matches!(**a, Ex::Val(Val::Num(Num::Flt(_)))) => {
+            Some(Ex::from(0))
+        }
+        Fnc::Ln(a) if **a == Ex::from(Tok::E) => Some(Ex::from(1)),
+        Fnc::Ln(a) => match a.as_ref() {
+            Ex::Infix(b, Bin::Op(Op::Pow), k)
+                if **b == Ex::from(Tok::E) && !k.contains(&Ex::from(Tok::I)) =>
+            {
+                Some((**k).clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `f` is at a pole for an exact argument, `tan` at an odd
+/// multiple of `pi/2`
+///
+/// - Floating point evaluation there gives a huge but finite value instead
+pub fn pole(f: &Fnc) -> bool {
+    match f {
+        Fnc::Tan(a) => twelfths(a).is_some_and(|k| k % 12 == 6),
+        _ => false,
+    }
+}
+
+/// `e^(q*i*pi) = cos(q*pi) + i*sin(q*pi)` for `q` a multiple of `1/12`
+pub fn exp(e: &Ex) -> Option<Ex> {
+    let p = Product::of(e);
+    let i = Ex::from(Tok::I);
+    let k = match (p.coef, p.factors.as_slice()) {
+        (Coef::Q(q), [(a, m), (b, n)])
+            if m.is_one() && n.is_one() && (*a == i || *b == i) && a != b =>
+        {
+            let pi = if *a == i { b } else { a };
+            twelfths(&product(&[q.to_ex(), pi.clone()]))?
+        }
+        _ => return None,
+    };
+    Some(sum(&[sin(k + 6), product(&[i, sin(k)])]))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// `k` of an exact argument `k*pi/12`, reduced modulo 24
+fn twelfths(a: &Ex) -> Option<i128> {
+    if a.is_zero() && !matches!(a, Ex::Val(Val::Num(Num::Flt(_)))) {
+        return Some(0);
+    }
+    let p = Product::of(a);
+    match (p.coef, p.factors.as_slice()) {
+        (Coef::Q(q), [(b, k)]) if *b == Ex::from(Tok::Pi) && k.is_one() => {
+            let k = q.checked_mul(Rat::int(12))?;
+            k.is_int().then(|| k.numer().rem_euclid(24))
+        }
+        _ => None,
+    }
+}
+
+/// `sin(k*pi/12)`
+fn sin(k: i128) -> Ex {
+    let k = k.rem_euclid(24);
+    match k {
+        0..=6 => quadrant(k),
+        7..=12 => quadrant(12 - k),
+        _ => product(&[Ex::from(-1), sin(k - 12)]),
+    }
+}
+
+/// `sin(k*pi/12)` for `0 <= k <= 6`
+fn quadrant(k: i128) -> Ex {
+    let half = || Rat::new(1, 2).to_ex();
+    let quarter = |s: i32| {
+        product(&[
+            Rat::new(1, 4).to_ex(),
+            sum(&[root(6), product(&[Ex::from(s), root(2)])]),
+        ])
+    };
+    match k {
+        0 => Ex::from(0),
+        1 => quarter(-1),
+        2 => half(),
+        3 => product(&[half(), root(2)]),
+        4 => product(&[half(), root(3)]),
+        5 => quarter(1),
+        _ => Ex::from(1),
+    }
+}
+
+/// `tan(k*pi/12)`, undefined at odd multiples of `pi/2`
+fn tan(k: i128) -> Option<Ex> {
+    let k = k.rem_euclid(12);
+    Some(match k {
+        0 => Ex::from(0),
+        1 => sum(&[Ex::from(2), product(&[Ex::from(-1), root(3)])]),
+        2 => product(&[Rat::new(1, 3).to_ex(), root(3)]),
+        3 => Ex::from(1),
+        4 => root(3),
+        5 => sum(&[Ex::from(2), root(3)]),
+        6 => return None,
+        _ => product(&[Ex::from(-1), tan(12 - k)?]),
+    })
+}
+
+fn root(n: i32) -> Ex {
+    power(Ex::from(n), Rat::new(1, 2).to_ex())
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::*;
+    use crate::ex3::testing::simp;
+
+    #[test]
+    fn ex3_special_trig() {
+        assert_eq!(simp("sin(pi)"), simp("0"));
+        assert_eq!(simp("cos(pi)"), simp("-1"));
+        assert_eq!(simp("sin(pi/6)"), simp("1/2"));
+        assert_eq!(simp("cos(pi/4)"), simp("2^(1/2)/2"));
+        assert_eq!(simp("sin(-pi/3)"), simp("-(3^(1/2))/2"));
+        assert_eq!(simp("cos(5*pi/6)"), simp("-(3^(1/2))/2"));
+        assert_eq!(simp("sin(7*pi/4)"), simp("-(2^(1/2))/2"));
+        assert_eq!(simp("cos(13*pi/3)"), simp("1/2"));
+        assert_eq!(simp("sin(pi/12)"), simp("(6^(1/2) - 2^(1/2))/4"));
+        assert_eq!(simp("tan(pi/4)"), simp("1"));
+        assert_eq!(simp("tan(2*pi/3)"), simp("-(3^(1/2))"));
+        assert_eq!(simp("tan(pi/2)"), simp("tan(pi/2)"));
+        assert_eq!(simp("atan(1)"), simp("pi/4"));
+        assert_eq!(simp("atan(-(3^(1/2)))"), simp("-pi/3"));
+        // not a multiple of pi/12
+        assert_eq!(simp("sin(pi/5)").to_text(), "sin(pi/5)");
+        // too large to reduce exactly
+        let huge = "sin(100000000000000000000000000000000000000*pi)";
+        assert_eq!(simp(huge), simp(huge).simplified());
+        assert!(matches!(simp(huge), Ex::Fn(_)));
+    }
+
+    #[test]
+    fn ex3_special_exp_ln() {
+        assert_eq!(simp("ln(1)"), simp("0"));
+        assert_eq!(simp("ln(e^3)"), simp("3"));
+        assert_eq!(simp("ln(e^(x+1))"), simp("x + 1"));
+        assert_eq!(simp("e^0"), simp("1"));
+        assert_eq!(simp("e^(ln(x))"), simp("x"));
+        assert_eq!(simp("e^(i*pi)"), simp("-1"));
+        assert_eq!(simp("e^(i*pi/2)"), simp("i"));
+        // floats only from floats
+        assert_eq!(simp("ln(1.0)"), Ex::from(0.0));
+        assert_eq!(simp("sin(0.0)"), Ex::from(0.0));
+    }
+
+    #[test]
+    fn ex3_special_numeric() {
+        for (s, want) in [("sin(pi)", "0"), ("cos(pi)", "-1"), ("ln(1)", "0")] {
+            let mut e: Ex = s.parse().unwrap();
+            e.numeric();
+            assert_eq!(e, want.parse::<Ex>().unwrap().simplified());
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,247 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, limit::Side, poly::UPoly, rational::*, simplify::*, solve::coeffs};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Most terms added or multiplied one by one when the bounds are numbers
+const MAX_TERMS: i128 = 1_000;
+
+/// Highest power of the index summed by Faulhaber's formula
+const MAX_POWER: usize = 20;
+
+/// Placeholder upper bound for infinite sums and products, never produced by
+/// the parser
+const N: &str = "_n";
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Sum of `self` over `k` from `lo` to `hi`
+    ///
+    /// - Numeric bounds close together are added term by term
+    /// - Closed forms for polynomial and geometric terms, by linearity
+    /// - An infinite upper bound is the limit of the partial sums, which is
+    ///   `inf` or `undefined` for divergent series
+    pub fn sum_over(&self, k: &str, lo: &Ex, hi: &Ex) -> Option<Ex> {
+        over(self, k, lo, hi, &Ex::from(0), sum, sum_closed)
+    }
+
+    /// Product of `self` over `k` from `lo` to `hi`
+    ///
+    /// - Numeric bounds close together are multiplied term by term
+    /// - Closed forms for factors with exponents that can be summed
+    /// - An infinite upper bound is the limit of the partial products
+    pub fn prod_over(&self, k: &str, lo: &Ex, hi: &Ex) -> Option<Ex> {
+        over(self, k, lo, hi, &Ex::from(1), product, prod_closed)
+    }
+}
+
+/// Shared driver of sums and products
+fn over(
+    a: &Ex,
+    k: &str,
+    lo: &Ex,
+    hi: &Ex,
+    empty: &Ex,
+    join: fn(&[Ex]) -> Ex,
+    closed: fn(&Ex, &str, &Ex, &Ex) -> Option<Ex>,
+) -> Option<Ex> {
+    let (a, lo, hi) = (a.simplified(), lo.simplified(), hi.simplified());
+    if hi.flt(&[]) == Some(f64::INFINITY) && lo.flt(&[]).is_some_and(f64::is_finite) {
+        let partial = closed(&a, k, &lo, &Ex::from(N))?;
+        return partial.limit(N, &hi, Side::Left);
+    }
+    if let (Some(l), Some(h)) = (lo.as_rat(), hi.as_rat()) {
+        if l.is_int() && h.is_int() && h.numer() - l.numer() < MAX_TERMS {
+            let terms: Vec<Ex> = (l.numer()..=h.numer())
+                .map(|i| a.subs(k, &Rat::int(i).to_ex()).simplified())
+                .collect();
+            return Some(match terms.is_empty() {
+                true => empty.clone(),
+                false => join(&terms),
+            });
+        }
+    }
+    closed(&a, k, &lo, &hi)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+fn sum_closed(a: &Ex, k: &str, lo: &Ex, hi: &Ex) -> Option<Ex> {
+    if !a.has(k) {
+        return Some(product(&[count(lo, hi), a.clone()]));
+    }
+    if let Some(c) = coeffs(a, k).filter(|c| c.len() <= MAX_POWER + 1) {
+        let below = sum(&[lo.clone(), Ex::from(-1)]);
+        let terms: Vec<Ex> = c
+            .iter()
+            .enumerate()
+            .map(|(p, c)| {
+                let f = faulhaber(p);
+                let diff = sum(&[f.subs(N, hi), product(&[Ex::from(-1), f.subs(N, &below)])]);
+                product(&[c.clone(), diff])
+            })
+            .collect();
+        return Some(tidy(sum(&terms)));
+    }
+
+    // linearity
+    let s = Sum::of(a);
+    if s.terms.len() > 1 || !s.constant.is_zero() {
+        let mut out = vec![product(&[count(lo, hi), s.constant.to_ex()])];
+        for (c, t) in &s.terms {
+            out.push(product(&[c.to_ex(), sum_closed(t, k, lo, hi)?]));
+        }
+        return Some(sum(&out));
+    }
+
+    geometric(a, k, lo, hi)
+}
+
+/// `Σ c r^k` for factors `b^(αk+β)` with `b` free of `k`
+fn geometric(a: &Ex, k: &str, lo: &Ex, hi: &Ex) -> Option<Ex> {
+    let mut ratio = vec![];
+    for (b, e) in &Product::of(a).factors {
+        if b.has(k) {
+            return None;
+        }
+        match coeffs(e, k)?.as_slice() {
+            [_] => {}
+            [_, alpha] => ratio.push(power(b.clone(), alpha.clone())),
+            _ => return None,
+        }
+    }
+    let r = product(&ratio);
+    let first = a.subs(k, lo).simplified();
+    let n = count(lo, hi);
+    if r.is_one() {
+        return Some(product(&[n, first]));
+    }
+    let num = sum(&[power(r.clone(), n), Ex::from(-1)]);
+    let den = power(sum(&[r, Ex::from(-1)]), Ex::from(-1));
+    Some(product(&[first, num, den]))
+}
+
+/// `Σ_{k=1}^{n} k^p` as a polynomial in the placeholder `n`, by Faulhaber's
+/// formula with `B_1 = +1/2`
+fn faulhaber(p: usize) -> Ex {
+    let mut b = vec![Rat::ONE];
+    for m in 1..=p {
+        let s = (0..m)
+            .map(|j| Rat::int(binomial(m + 1, j)) * b[j])
+            .fold(Rat::ZERO, |acc, t| acc + t);
+        b.push(-s / Rat::int(m as i128 + 1));
+    }
+    if p >= 1 {
+        b[1] = Rat::new(1, 2);
+    }
+    let mut c = vec![Rat::ZERO; p + 2];
+    for (j, bj) in b.iter().enumerate() {
+        c[p + 1 - j] = Rat::int(binomial(p + 1, j)) * *bj / Rat::int(p as i128 + 1);
+    }
+    UPoly::new(c).to_ex(N)
+}
+
+fn binomial(n: usize, k: usize) -> i128 {
+    (0..k).fold(1, |acc, i| acc * (n - i) as i128 / (i as i128 + 1))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+fn prod_closed(a: &Ex, k: &str, lo: &Ex, hi: &Ex) -> Option<Ex> {
+    if !a.has(k) {
+        return Some(power(a.clone(), count(lo, hi)));
+    }
+    let p = Product::of(a);
+    let mut out = vec![power(p.coef.to_ex(), count(lo, hi))];
+    for (b, e) in &p.factors {
+        out.push(match (b.has(k), e.has(k)) {
+            (false, _) => power(b.clone(), sum_closed(e, k, lo, hi)?),
+            (true, false) if *e != Ex::from(1) => power(prod_closed(b, k, lo, hi)?, e.clone()),
+            _ => return None,
+        });
+    }
+    Some(product(&out))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Number of terms from `lo` to `hi`
+fn count(lo: &Ex, hi: &Ex) -> Ex {
+    sum(&[
+        hi.clone(),
+        product(&[Ex::from(-1), lo.clone()]),
+        Ex::from(1),
+    ])
+}
+
+/// The factored form if it is smaller
+fn tidy(e: Ex) -> Ex {
+    match e.factor() {
+        f if f.size() < e.size() => f,
+        _ => e,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::*;
+    use crate::ex3::testing::simp;
+
+    #[test]
+    fn ex3_summation_numeric() {
+        assert_eq!(simp("sum(k^2, k, 1, 10)"), simp("385"));
+        assert_eq!(simp("sum(1/k, k, 1, 4)"), simp("25/12"));
+        assert_eq!(simp("sum(k, k, 5, 4)"), simp("0"));
+        assert_eq!(simp("prod(k, k, 1, 6)"), simp("720"));
+        assert_eq!(simp("prod(x, k, 1, 0)"), simp("1"));
+
+        // 40! does not fit an i128, so part of the product stays unevaluated
+        let big = simp("prod(k, k, 1, 40)").flt(&[]).unwrap();
+        assert!((big / 8.159152832478977e47 - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ex3_summation_closed() {
+        assert_eq!(simp("sum(k, k, 1, n)"), simp("n*(n+1)/2"));
+        assert_eq!(simp("sum(k^3, k, 1, n)"), simp("n^2*(n+1)^2/4"));
+        assert_eq!(simp("sum(k^2, k, 1, 1500)"), simp("1126125250"));
+        assert_eq!(simp("sum(2*k+1, k, 0, n-1)"), simp("n^2"));
+        assert_eq!(simp("sum(a, k, 1, n)"), simp("a*n"));
+        assert_eq!(simp("sum(2^k, k, 0, n)"), simp("2^(n+1)-1"));
+        assert_eq!(
+            simp("sum((-1)^k, k, 0, n)").to_text(),
+            "-(((-1)^(n + 1) - 1)/2)"
+        );
+        assert_eq!(simp("prod(2^k, k, 1, n)"), simp("2^(n*(n+1)/2)"));
+        assert_eq!(simp("prod(3, k, 1, n)"), simp("3^n"));
+    }
+
+    #[test]
+    fn ex3_summation_infinite() {
+        assert_eq!(simp("sum((1/2)^k, k, 0, inf)"), simp("2"));
+        assert_eq!(simp("sum(3*(1/3)^k, k, 1, inf)"), simp("3/2"));
+        assert_eq!(simp("sum(k, k, 1, inf)"), simp("inf"));
+        // oscillating partial sums have no limit to find
+        assert!(matches!(
+            simp("sum((-1)^k, k, 0, inf)"),
+            Ex::Fn(Fnc::Sum(..))
+        ));
+        assert_eq!(simp("prod(2^((1/2)^k), k, 1, inf)"), simp("2"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
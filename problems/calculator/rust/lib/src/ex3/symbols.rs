@@ -0,0 +1,183 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Symbols accepted in variable names as `(unicode, ascii name, latex)`
+///
+/// - Variables always carry the ASCII name, so `α` and `alpha` are the same
+///   variable
+/// - Capitals that look like Latin letters are those letters
+pub const SYMBOLS: &[(char, &str, &str)] = &[
+    ('α', "alpha", "\\alpha"),
+    ('β', "beta", "\\beta"),
+    ('γ', "gamma", "\\gamma"),
+    ('δ', "delta", "\\delta"),
+    ('ε', "epsilon", "\\epsilon"),
+    ('ϵ', "epsilon", "\\epsilon"),
+    ('ζ', "zeta", "\\zeta"),
+    ('η', "eta", "\\eta"),
+    ('θ', "theta", "\\theta"),
+    ('ϑ', "vartheta", "\\vartheta"),
+    ('ι', "iota", "\\iota"),
+    ('κ', "kappa", "\\kappa"),
+    ('ϰ', "varkappa", "\\varkappa"),
+    ('λ', "lambda", "\\lambda"),
+    ('μ', "mu", "\\mu"),
+    ('ν', "nu", "\\nu"),
+    ('ξ', "xi", "\\xi"),
+    ('ο', "omicron", "o"),
+    ('π', "pi", "\\pi"),
+    ('ρ', "rho", "\\rho"),
+    ('σ', "sigma", "\\sigma"),
+    ('ς', "sigma", "\\sigma"),
+    ('τ', "tau", "\\tau"),
+    ('υ', "upsilon", "\\upsilon"),
+    ('φ', "phi", "\\phi"),
+    ('ϕ', "phi", "\\phi"),
+    ('χ', "chi", "\\chi"),
+    ('ψ', "psi", "\\psi"),
+    ('ω', "omega", "\\omega"),
+    ('ϝ', "digamma", "\\digamma"),
+    ('Α', "A", "A"),
+    ('Β', "B", "B"),
+    ('Γ', "Gamma", "\\Gamma"),
+    ('Δ', "Delta", "\\Delta"),
+    ('Ε', "E", "E"),
+    ('Ζ', "Z", "Z"),
+    ('Η', "H", "H"),
+    ('Θ', "Theta", "\\Theta"),
+    ('Ι', "I", "I"),
+    ('Κ', "K", "K"),
+    ('Λ', "Lambda", "\\Lambda"),
+    ('Μ', "M", "M"),
+    ('Ν', "N", "N"),
+    ('Ξ', "Xi", "\\Xi"),
+    ('Ο', "O", "O"),
+    ('Π', "Pi", "\\Pi"),
+    ('Ρ', "P", "P"),
+    ('Σ', "Sigma", "\\Sigma"),
+    ('Τ', "T", "T"),
+    ('Υ', "Upsilon", "\\Upsilon"),
+    ('Φ', "Phi", "\\Phi"),
+    ('Χ', "X", "X"),
+    ('Ψ', "Psi", "\\Psi"),
+    ('Ω', "Omega", "\\Omega"),
+    ('∇', "nabla", "\\nabla"),
+    ('∂', "partial", "\\partial"),
+    ('ℵ', "aleph", "\\aleph"),
+];
+
+/// ASCII names without a Unicode form of their own in [`SYMBOLS`]
+const LATEX_ONLY: &[(&str, &str)] = &[("varepsilon", "\\varepsilon"), ("varphi", "\\varphi")];
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Variable name `s` with the symbols of [`SYMBOLS`] replaced by their
+/// ASCII names
+///
+/// - A symbol is only spelled out when it is a whole part of the name
+///   between `_` and `'`, so `α_1` is `alpha_1` but `αβ` stays as it is,
+///   and never meets the name `alphabeta`
+/// - Capitals that look like Latin letters are always those letters
+pub fn ascii(s: &str) -> String {
+    let name = |c: char| SYMBOLS.iter().find(|(u, _, _)| *u == c).map(|(_, n, _)| *n);
+    let mut out = String::new();
+    for part in s.split_inclusive(['_', '\'']) {
+        let body = part.trim_end_matches(['_', '\'']);
+        let mut cs = body.chars();
+        match (cs.next().and_then(name), cs.next()) {
+            (Some(n), None) => out.push_str(n),
+            _ => out.extend(body.chars().map(|c| match name(c) {
+                Some(n) if n.len() == 1 => n.to_string(),
+                _ => c.to_string(),
+            })),
+        }
+        out.push_str(&part[body.len()..]);
+    }
+    out
+}
+
+/// LaTeX for the ASCII name of a symbol, such as `\alpha` for `alpha`
+pub fn latex(name: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .map(|(_, n, l)| (*n, *l))
+        .chain(LATEX_ONLY.iter().copied())
+        .find(|(n, _)| *n == name)
+        .map(|(_, l)| l)
+}
+
+/// Unicode for the ASCII name of a symbol, such as `α` for `alpha`
+///
+/// - The first form listed in [`SYMBOLS`], and never for single letters
+pub fn unicode(name: &str) -> Option<char> {
+    SYMBOLS
+        .iter()
+        .find(|(_, n, _)| *n == name && n.len() > 1)
+        .map(|(u, _, _)| *u)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::*;
+    use crate::ex3::testing::parse;
+
+    #[test]
+    fn ex3_symbols_input() {
+        assert_eq!(parse("α + β"), parse("alpha + beta"));
+        assert_eq!(parse("θ'"), Ex::from("theta'"));
+        assert_eq!(parse("2*π"), parse("2*pi"));
+        assert_eq!(parse("π"), Ex::from(Tok::Pi));
+        assert_eq!(parse("∞"), Ex::from(Tok::Inf));
+        assert_eq!(parse("-∞"), parse("-inf"));
+        assert_eq!(parse("Ω_ab"), Ex::from("Omega_ab"));
+        assert_eq!(parse("∂ + ∇ + ℵ"), parse("partial + nabla + aleph"));
+        assert_eq!(parse("θ''"), Ex::from("theta''"));
+        assert_eq!(parse("α_β"), Ex::from("alpha_beta"));
+        assert_eq!(parse("ΑΒ"), Ex::from("AB"));
+
+        // only whole parts are spelled out, so no two names meet
+        assert_ne!(parse("αβ"), parse("alphabeta"));
+        assert_ne!(parse("αx"), parse("alphax"));
+        assert_eq!(parse(&parse("αβ").to_text()), parse("αβ"));
+
+        // names that start like a token
+        assert_eq!(parse("eta + epsilon + iota + pix"), {
+            let v = |x: &str| Ex::from(x);
+            v("eta") + v("epsilon") + v("iota") + v("pix")
+        });
+        assert_eq!(parse("e^ε"), Ex::from(Tok::E).pow(Ex::from("epsilon")));
+
+        assert_eq!(parse("x × y ÷ z − w"), parse("x * y / z - w"));
+        assert_eq!(parse("x·y"), parse("x*y"));
+        assert_eq!(parse("−x"), parse("-x"));
+        assert_eq!(parse("x ≤ 1"), parse("x <= 1"));
+        assert_eq!(parse("x ≥ 1"), parse("x >= 1"));
+        assert_eq!(parse("x ≠ 1"), parse("x != 1"));
+        assert_eq!(parse("x <= 1").to_text(), "x <= 1");
+        assert_eq!(parse("√x"), parse("x^(1/2)"));
+        assert_eq!(parse("√(x+1) + √2"), parse("(x+1)^(1/2) + 2^(1/2)"));
+        assert_eq!(parse("sqrt(y)"), parse("y^(1/2)"));
+        assert_eq!(parse("√sin(x)"), parse("sin(x)^(1/2)"));
+    }
+
+    #[test]
+    fn ex3_symbols_render() {
+        assert_eq!(parse("α + ω_1").to_latex(), "\\alpha + \\omega_{1}");
+        assert_eq!(parse("Δ*x").to_latex(), "\\Delta \\cdot x");
+        assert_eq!(parse("varphi").to_latex(), "\\varphi");
+        assert_eq!(parse("θ").to_text(), "theta");
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,23 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::ast::Ex;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// `s` parsed as it is
+pub fn parse(s: &str) -> Ex {
+    s.parse().unwrap()
+}
+
+/// `s` parsed and simplified
+pub fn simp(s: &str) -> Ex {
+    parse(s).simplified()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,276 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use std::cell::RefCell;
+
+use super::ast::*;
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// One rewrite of a traced evaluation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub rule: &'static str,
+    /// Child indices from the traced expression down to the rewritten subterm
+    pub path: Vec<usize>,
+    pub before: Ex,
+    pub after: Ex,
+}
+
+/// Steps of a traced evaluation, in the order they were taken
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Simplify, recording every step taken, including the steps inside
+    /// `d`, `int` and `solve`
+    pub fn simplified_traced(&self) -> (Ex, Trace) {
+        traced(|| self.simplified())
+    }
+}
+
+impl Trace {
+    /// One numbered line per step
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (i, s) in self.steps.iter().enumerate() {
+            let path: Vec<String> = s.path.iter().map(usize::to_string).collect();
+            out += &format!(
+                "{}. {} [{}]: {} -> {}\n",
+                i + 1,
+                s.rule,
+                path.join("."),
+                s.before.to_text(),
+                s.after.to_text()
+            );
+        }
+        out
+    }
+
+    /// An `align*` environment with the rule names as annotations
+    ///
+    /// - Steps between relations are joined by `\iff` instead of `=`
+    pub fn to_latex(&self) -> String {
+        let mut out = "\\begin{align*}\n".to_owned();
+        for s in &self.steps {
+            let eq = match s.before {
+                Ex::Infix(_, Bin::Rl(_), _) => "\\iff",
+                _ => "=",
+            };
+            out += &format!(
+                "{} &{} {} && \\text{{{}}} \\\\\n",
+                s.before.to_latex(),
+                eq,
+                s.after.to_latex(),
+                s.rule
+            );
+        }
+        out + "\\end{align*}"
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct Recorder {
+    steps: Vec<Step>,
+    path: Vec<usize>,
+    /// Steps are being kept
+    live: bool,
+    /// The next `node` continues the traced evaluation
+    pending: bool,
+}
+
+fn with<T>(f: impl FnOnce(&mut Recorder) -> T) -> Option<T> {
+    RECORDER.with(|r| r.borrow_mut().as_mut().map(f))
+}
+
+/// Run `f`, keeping the steps of the first simplification inside it
+pub fn traced<T>(f: impl FnOnce() -> T) -> (T, Trace) {
+    let prev = RECORDER.with(|r| {
+        r.replace(Some(Recorder {
+            live: true,
+            pending: true,
+            ..Default::default()
+        }))
+    });
+    let out = f();
+    let rec = RECORDER.with(|r| r.replace(prev)).unwrap();
+    (out, Trace { steps: rec.steps })
+}
+
+/// Whether steps are being kept
+pub fn active() -> bool {
+    with(|rec| rec.live).unwrap_or(false)
+}
+
+/// Keep a step at the current path, if anything changed and it does not
+/// repeat the last step
+pub fn record(rule: &'static str, before: &Ex, after: &Ex) {
+    with(|rec| {
+        let repeat = rec
+            .steps
+            .last()
+            .is_some_and(|s| s.before == *before && s.after == *after);
+        if rec.live && before != after && !repeat {
+            rec.steps.push(Step {
+                rule,
+                path: rec.path.clone(),
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+    });
+}
+
+/// Run `f` on child `i` of the current subterm
+pub fn at<T>(i: usize, f: impl FnOnce() -> T) -> T {
+    with(|rec| rec.path.push(i));
+    let out = f();
+    with(|rec| rec.path.pop());
+    out
+}
+
+/// Run `f` on child `i`, tracing the simplification it starts with
+pub fn child<T>(i: usize, f: impl FnOnce() -> T) -> T {
+    at(i, || {
+        with(|rec| rec.pending = rec.live);
+        f()
+    })
+}
+
+/// Run one node of a simplification, which is traced only if its parent
+/// asked for it through [`child`]
+///
+/// - Every other simplification, such as a helper simplifying an
+///   intermediate result, is kept out of the trace
+pub fn node<T>(f: impl FnOnce() -> T) -> T {
+    let prev = with(|rec| {
+        let prev = rec.live;
+        rec.live = rec.pending;
+        rec.pending = false;
+        prev
+    });
+    let out = f();
+    if let Some(prev) = prev {
+        with(|rec| rec.live = prev);
+    }
+    out
+}
+
+/// Run `f`, dropping the steps it kept if it fails
+pub fn attempt<T>(f: impl FnOnce() -> Option<T>) -> Option<T> {
+    let len = with(|rec| rec.steps.len());
+    let out = f();
+    if let (None, Some(len)) = (&out, len) {
+        with(|rec| rec.steps.truncate(len));
+    }
+    out
+}
+
+/// Run `f` without keeping any steps
+pub fn quiet<T>(f: impl FnOnce() -> T) -> T {
+    let prev = with(|rec| std::mem::replace(&mut rec.live, false));
+    let out = f();
+    if let Some(prev) = prev {
+        with(|rec| rec.live = prev);
+    }
+    out
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(s: &str) -> (Ex, Trace) {
+        s.parse::<Ex>().unwrap().simplified_traced()
+    }
+
+    fn rules(t: &Trace) -> Vec<&'static str> {
+        t.steps.iter().map(|s| s.rule).collect()
+    }
+
+    #[test]
+    fn ex3_trace_simplify() {
+        let (e, t) = trace("2*(3+4) + x*1");
+        assert_eq!(e, "x+14".parse::<Ex>().unwrap().simplified());
+        assert_eq!(rules(&t), vec!["add", "multiply", "multiply", "add"]);
+        assert_eq!(t.steps[0].path, vec![0, 1]);
+        assert_eq!(t.steps[0].after, Ex::from(7));
+        assert_eq!(t.steps.last().unwrap().path, Vec::<usize>::new());
+        assert_eq!(t.steps.last().unwrap().after, e);
+
+        // untraced simplification records nothing
+        let (_, t) = traced(|| ());
+        assert!(t.steps.is_empty());
+        assert!(!active());
+    }
+
+    #[test]
+    fn ex3_trace_calculus() {
+        let (_, t) = trace("d(x*sin(x), x)");
+        assert_eq!(
+            rules(&t),
+            vec![
+                "product rule",
+                "variable",
+                "sin rule",
+                "variable",
+                "simplify",
+                "d"
+            ]
+        );
+        assert_eq!(t.steps[0].path, vec![0]);
+        assert_eq!(t.steps[2].path, vec![0, 1]);
+        assert_eq!(
+            t.steps[0].after.to_text(),
+            "d(x, x)*sin(x) + x*d(sin(x), x)"
+        );
+
+        let (_, t) = trace("int(x^2+cos(x), x)");
+        assert_eq!(rules(&t), vec!["table", "table", "linearity"]);
+
+        let (_, t) = trace("solve(x^2 = 4, x)");
+        assert_eq!(
+            rules(&t),
+            vec![
+                "subtract right side",
+                "polynomial roots",
+                "check solutions",
+                "solve"
+            ]
+        );
+        assert!(t.to_latex().contains("x^{2} = 4 &\\iff x^{2} - 4 = 0"));
+    }
+
+    #[test]
+    fn ex3_trace_render() {
+        let (_, t) = trace("1+2");
+        assert_eq!(t.to_text(), "1. add []: 1 + 2 -> 3\n");
+        assert_eq!(
+            t.to_latex(),
+            "\\begin{align*}\n1 + 2 &= 3 && \\text{add} \\\\\n\\end{align*}"
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
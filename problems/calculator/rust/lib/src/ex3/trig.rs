@@ -0,0 +1,230 @@
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+use super::{ast::*, expand::Expand, rational::*, simplify::*};
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Highest power of `sin` or `cos` turned into multiple angles by
+/// [`Ex::tcollect`]
+const MAX_POWER: i128 = 16;
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl Ex {
+    /// Expand `sin`, `cos` and `tan` of sums and integer multiples into
+    /// functions of single angles, as `tExpand` on the TI-89
+    pub fn texpand(&self) -> Ex {
+        self.expand(Expand {
+            ln: false,
+            trig: true,
+        })
+    }
+
+    /// Turn products and powers of `sin` and `cos` into sums of single
+    /// functions of multiple angles, as `tCollect` on the TI-89
+    ///
+    /// - Product-to-sum formulas, so `2*sin(x)*cos(x) = sin(2*x)` and
+    ///   `sin(x)^2 = 1/2 - cos(2*x)/2`
+    pub fn tcollect(&self) -> Ex {
+        let s = Sum::of(&self.expand(Expand::default()));
+        let mut out = vec![s.constant.to_ex()];
+        for (c, t) in &s.terms {
+            out.push(product(&[c.to_ex(), collect(t)]));
+        }
+        sum(&out).expand(Expand::default())
+    }
+}
+
+/// One term of [`Ex::tcollect`], combining its `sin` and `cos` factors two
+/// at a time
+fn collect(t: &Ex) -> Ex {
+    let p = Product::of(t);
+    let (mut waves, mut rest) = (vec![], vec![p.coef.to_ex()]);
+    for (b, k) in p.factors {
+        match (&b, k.as_rat()) {
+            (Ex::Fn(Fnc::Sin(_) | Fnc::Cos(_)), Some(n))
+                if n.is_int() && n.numer() > 0 && n.numer() <= MAX_POWER =>
+            {
+                waves.extend((0..n.numer()).map(|_| b.clone()))
+            }
+            _ => rest.push(power(b, k)),
+        }
+    }
+    let (Some(f), Some(g)) = (waves.pop(), waves.pop()) else {
+        return t.clone();
+    };
+    rest.extend(waves);
+    let rest = product(&rest);
+
+    let s = Sum::of(&product_to_sum(&f, &g));
+    let mut out = vec![product(&[s.constant.to_ex(), rest.clone()])];
+    for (c, w) in &s.terms {
+        out.push(collect(&product(&[c.to_ex(), w.clone(), rest.clone()])));
+    }
+    sum(&out)
+}
+
+/// `f*g` for `sin` or `cos` `f` and `g`, as a sum of single functions
+fn product_to_sum(f: &Ex, g: &Ex) -> Ex {
+    let (Ex::Fn(f), Ex::Fn(g)) = (f, g) else {
+        unreachable!()
+    };
+    let (a, b) = (f.args()[0], g.args()[0]);
+    let diff = sum(&[a.clone(), product(&[Ex::from(-1), b.clone()])]);
+    let add = sum(&[a.clone(), b.clone()]);
+    let sin = |e: &Ex| Ex::from(Fnc::Sin(Box::new(e.clone()))).simplified();
+    let cos = |e: &Ex| Ex::from(Fnc::Cos(Box::new(e.clone()))).simplified();
+    let (x, y, sign) = match (f, g) {
+        (Fnc::Sin(_), Fnc::Sin(_)) => (cos(&diff), cos(&add), -1),
+        (Fnc::Cos(_), Fnc::Cos(_)) => (cos(&diff), cos(&add), 1),
+        (Fnc::Sin(_), _) => (sin(&add), sin(&diff), 1),
+        _ => (sin(&add), sin(&diff), -1),
+    };
+    sum(&[
+        product(&[Rat::new(1, 2).to_ex(), x]),
+        product(&[Rat::new(sign, 2).to_ex(), y]),
+    ])
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+/// Whether `sin(a)` is better written `-sin(-a)`
+pub fn negated(a: &Ex) -> bool {
+    !matches!(a, Ex::Infix(_, Bin::Op(Op::Add | Op::Sub), _)) && Product::of(a).coef.is_neg()
+}
+
+/// `c*R*sin(a)^2 + c*R*cos(a)^2 = c*R` and `k - k*sin(a)^2 = k*cos(a)^2`,
+/// on the terms of a sum
+pub fn pythagoras(constant: &mut Coef, terms: &mut Vec<(Coef, Ex)>) {
+    let mut i = 0;
+    while i < terms.len() {
+        let Some((sin, a, rest)) = square(&terms[i].1) else {
+            i += 1;
+            continue;
+        };
+        let partner = terms.iter().position(|(c, t)| {
+            *c == terms[i].0 && square(t).is_some_and(|(s, b, r)| s != sin && b == a && r == rest)
+        });
+        match partner {
+            Some(j) => {
+                let c = terms[i].0;
+                terms.remove(i.max(j));
+                terms.remove(i.min(j));
+                match Coef::of(&rest) {
+                    Some(k) => *constant = constant.add(c.mul(k)),
+                    None => terms.push((c, rest)),
+                }
+                i = 0;
+            }
+            None if rest.is_one()
+                && !constant.is_zero()
+                && terms[i].0 == constant.mul(Coef::Q(-Rat::ONE)) =>
+            {
+                let other = match sin {
+                    true => Fnc::Cos(Box::new(a)),
+                    false => Fnc::Sin(Box::new(a)),
+                };
+                terms[i] = (*constant, power(other.into(), Ex::from(2)));
+                *constant = Coef::Q(Rat::ZERO);
+                i += 1;
+            }
+            None => i += 1,
+        }
+    }
+}
+
+/// `(is sin, a, R)` of a term `R*sin(a)^2` or `R*cos(a)^2`
+fn square(t: &Ex) -> Option<(bool, Ex, Ex)> {
+    let p = Product::of(t);
+    let i = p.factors.iter().position(|(b, k)| {
+        matches!(b, Ex::Fn(Fnc::Sin(_) | Fnc::Cos(_))) && k.as_rat() == Some(Rat::int(2))
+    })?;
+    let mut rest = p.clone();
+    let (b, _) = rest.factors.remove(i);
+    let Ex::Fn(f) = b else { unreachable!() };
+    Some((matches!(f, Fnc::Sin(_)), f.args()[0].clone(), rest.to_ex()))
+}
+
+/// `tan(a)^k*cos(a)^m = sin(a)^k*cos(a)^(m-k)` and
+/// `sin(a)^k*cos(a)^(-k) = tan(a)^k`, on the merged factors of a product
+pub fn quotients(factors: &mut Vec<(Ex, Ex)>) {
+    let find = |fs: &[(Ex, Ex)], g: fn(Box<Ex>) -> Fnc, a: &Ex| {
+        let e: Ex = g(Box::new(a.clone())).into();
+        fs.iter().position(|(b, k)| *b == e && k.as_rat().is_some())
+    };
+    let args: Vec<Ex> = factors
+        .iter()
+        .filter_map(|(b, _)| match b {
+            Ex::Fn(Fnc::Tan(a) | Fnc::Sin(a)) => Some((**a).clone()),
+            _ => None,
+        })
+        .collect();
+    for a in args {
+        if let (Some(t), Some(c)) = (find(factors, Fnc::Tan, &a), find(factors, Fnc::Cos, &a)) {
+            let k = factors[t].1.clone();
+            factors[c].1 = sum(&[factors[c].1.clone(), product(&[Ex::from(-1), k.clone()])]);
+            match find(factors, Fnc::Sin, &a) {
+                Some(s) => {
+                    factors[s].1 = sum(&[factors[s].1.clone(), k]);
+                    factors[t].1 = Ex::from(0);
+                }
+                None => factors[t].0 = Fnc::Sin(Box::new(a.clone())).into(),
+            }
+        }
+        if let (Some(s), Some(c)) = (find(factors, Fnc::Sin, &a), find(factors, Fnc::Cos, &a)) {
+            let (k, m) = (factors[s].1.as_rat().unwrap(), factors[c].1.as_rat());
+            if !k.is_zero() && m == Some(-k) {
+                factors[s].0 = Fnc::Tan(Box::new(a.clone())).into();
+                factors[c].1 = Ex::from(0);
+            }
+        }
+    }
+    factors.retain(|(_, k)| !k.is_zero());
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ex3::ast::*;
+    use crate::ex3::testing::simp;
+
+    #[test]
+    fn ex3_trig_identities() {
+        assert_eq!(simp("sin(x)^2 + cos(x)^2"), simp("1"));
+        assert_eq!(simp("3*y*sin(x+1)^2 + 3*y*cos(x+1)^2"), simp("3*y"));
+        assert_eq!(simp("1 - sin(x)^2"), simp("cos(x)^2"));
+        assert_eq!(simp("tan(x)*cos(x)"), simp("sin(x)"));
+        assert_eq!(simp("tan(x)*cos(x)^2").to_text(), "cos(x)*sin(x)");
+        assert_eq!(simp("sin(x)/cos(x)"), simp("tan(x)"));
+        assert_eq!(simp("cos(x)/sin(x)").to_text(), "1/tan(x)");
+        assert_eq!(simp("sin(-x)"), simp("-sin(x)"));
+        assert_eq!(simp("cos(-2*x)"), simp("cos(2*x)"));
+    }
+
+    #[test]
+    fn ex3_trig_expand_collect() {
+        assert_eq!(
+            simp("tExpand(cos(x+y))"),
+            simp("cos(x)*cos(y) - sin(x)*sin(y)")
+        );
+        assert_eq!(simp("tExpand(sin(2*x))"), simp("2*sin(x)*cos(x)"));
+        assert_eq!(simp("tCollect(2*sin(x)*cos(x))"), simp("sin(2*x)"));
+        assert_eq!(simp("tCollect(sin(x)^2)"), simp("1/2 - cos(2*x)/2"));
+        assert_eq!(simp("tCollect(cos(x)^2 - sin(x)^2)"), simp("cos(2*x)"));
+        assert_eq!(simp("tCollect(cos(x)^3)"), simp("3*cos(x)/4 + cos(3*x)/4"));
+        assert_eq!(simp("tCollect(tExpand(sin(3*x)))"), simp("sin(3*x)"));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//---------------------------------------------------------------------------//
+///////////////////////////////////////////////////////////////////////////////